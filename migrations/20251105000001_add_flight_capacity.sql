@@ -0,0 +1,4 @@
+-- Menambahkan kapasitas / jumlah penumpang yang diharapkan per penerbangan
+-- NULL berarti kapasitas belum diketahui (progress boarding tanpa persentase)
+ALTER TABLE flights
+    ADD COLUMN capacity INTEGER CHECK (capacity IS NULL OR capacity > 0);
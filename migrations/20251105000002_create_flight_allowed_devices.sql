@@ -0,0 +1,8 @@
+-- Allowlist device per penerbangan
+-- Penerbangan tanpa baris di tabel ini menerima scan dari device mana pun
+CREATE TABLE flight_allowed_devices (
+    flight_id INTEGER NOT NULL REFERENCES flights(id) ON DELETE CASCADE,
+    device_id VARCHAR(50) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (flight_id, device_id)
+);
@@ -0,0 +1,17 @@
+-- Satu scan_data hanya boleh punya satu hasil decode
+-- Decode ulang untuk scan yang sama akan meng-update baris yang ada (upsert)
+
+BEGIN;
+
+-- Bersihkan duplikat lama: simpan hanya hasil decode terbaru per scan_data_id
+DELETE FROM decode_barcode older
+USING decode_barcode newer
+WHERE older.scan_data_id IS NOT NULL
+  AND older.scan_data_id = newer.scan_data_id
+  AND older.id < newer.id;
+
+CREATE UNIQUE INDEX idx_unique_decode_per_scan
+    ON decode_barcode(scan_data_id)
+    WHERE scan_data_id IS NOT NULL;
+
+COMMIT;
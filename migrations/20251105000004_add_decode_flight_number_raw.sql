@@ -0,0 +1,13 @@
+-- Menyimpan nomor penerbangan persis seperti di barcode (mis. "0312", "123A")
+-- Kolom integer flight_number tetap ada untuk kompatibilitas
+
+ALTER TABLE decode_barcode
+    ADD COLUMN flight_number_raw VARCHAR(10);
+
+-- Data lama: rekonstruksi best-effort dalam format IATA 4 digit
+UPDATE decode_barcode
+SET flight_number_raw = LPAD(flight_number::text, 4, '0')
+WHERE flight_number_raw IS NULL;
+
+ALTER TABLE decode_barcode
+    ALTER COLUMN flight_number_raw SET NOT NULL;
@@ -0,0 +1,12 @@
+-- Simbologi asal barcode yang di-decode: PDF417 (boarding pass kertas) vs AZTEC (mobile pass)
+-- Payload BCBP sama untuk keduanya; kolom ini hanya untuk analitik
+
+ALTER TABLE decode_barcode
+    ADD COLUMN source_format VARCHAR(50);
+
+-- Data lama: ambil dari scan yang terhubung (decode ad-hoc tetap NULL)
+UPDATE decode_barcode db
+SET source_format = sd.barcode_format
+FROM scan_data sd
+WHERE db.scan_data_id = sd.id
+  AND db.source_format IS NULL;
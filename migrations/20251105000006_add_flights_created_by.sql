@@ -0,0 +1,7 @@
+-- User yang membuat flight lewat POST /api/flights (untuk timeline aktivitas user)
+-- Flight dari bulk sync dan data lama tetap NULL
+
+ALTER TABLE flights
+    ADD COLUMN created_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_flights_created_by ON flights(created_by);
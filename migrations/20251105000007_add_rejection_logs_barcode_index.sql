@@ -0,0 +1,5 @@
+-- Index untuk lookup rejection log per barcode (GET /api/rejection-logs/by-barcode)
+-- Hash index: hanya perlu pencarian exact match, dan tidak ada batas ukuran baris
+-- seperti btree untuk barcode_value (TEXT) yang panjang
+
+CREATE INDEX IF NOT EXISTS idx_rejection_logs_barcode_value ON rejection_logs USING HASH (barcode_value);
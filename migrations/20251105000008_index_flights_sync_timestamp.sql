@@ -0,0 +1,4 @@
+-- Index untuk incremental sync: get_flights_since memfilter dan mengurutkan berdasarkan
+-- COALESCE(updated_at, created_at) agar flight yang belum pernah di-update tetap ikut sync
+
+CREATE INDEX IF NOT EXISTS idx_flights_sync_timestamp ON flights ((COALESCE(updated_at, created_at)));
@@ -0,0 +1,5 @@
+-- Index untuk lookup scan per barcode lintas penerbangan (GET /api/scan-data/by-barcode)
+-- idx_unique_barcode_per_flight adalah partial index (flight_id IS NOT NULL), jadi tidak
+-- dipakai untuk WHERE barcode_value = $1 saja. Hash index cukup untuk exact match.
+
+CREATE INDEX IF NOT EXISTS idx_scan_data_barcode_value ON scan_data USING HASH (barcode_value);
@@ -0,0 +1,5 @@
+-- Tanggal penerbangan hasil konversi julian date (hari ke-N) ke tanggal kalender
+-- Dihitung server saat decode; NULL untuk decode lama atau julian date tidak valid
+
+ALTER TABLE decode_barcode
+    ADD COLUMN flight_date DATE;
@@ -1,6 +1,6 @@
 use axum::{
     extract::Request,
-    http::{StatusCode, HeaderValue},
+    http::StatusCode,
     middleware::Next,
     response::Response,
 };
@@ -70,29 +70,7 @@ pub async fn api_key_only_middleware(
         "Authenticated request"
     );
 
-    // Add security headers to response
-    let mut response = next.run(req).await;
-    let headers = response.headers_mut();
-
-    // Security headers
-    headers.insert(
-        "X-Content-Type-Options",
-        HeaderValue::from_static("nosniff"),
-    );
-    headers.insert(
-        "X-Frame-Options",
-        HeaderValue::from_static("SAMEORIGIN"),
-    );
-    headers.insert(
-        "X-XSS-Protection",
-        HeaderValue::from_static("1; mode=block"),
-    );
-    headers.insert(
-        "Referrer-Policy",
-        HeaderValue::from_static("strict-origin-when-cross-origin"),
-    );
-
-    Ok(response)
+    Ok(next.run(req).await)
 }
 
 /// Legacy API Authentication Middleware (WITH IP WHITELIST - NOT USED)
@@ -162,29 +140,7 @@ pub async fn api_auth_middleware(
         "Authenticated request"
     );
 
-    // Add security headers to response
-    let mut response = next.run(req).await;
-    let headers = response.headers_mut();
-
-    // Security headers
-    headers.insert(
-        "X-Content-Type-Options",
-        HeaderValue::from_static("nosniff"),
-    );
-    headers.insert(
-        "X-Frame-Options",
-        HeaderValue::from_static("SAMEORIGIN"),
-    );
-    headers.insert(
-        "X-XSS-Protection",
-        HeaderValue::from_static("1; mode=block"),
-    );
-    headers.insert(
-        "Referrer-Policy",
-        HeaderValue::from_static("strict-origin-when-cross-origin"),
-    );
-
-    Ok(response)
+    Ok(next.run(req).await)
 }
 
 /// Extract client IP from request headers
@@ -261,7 +217,7 @@ fn is_allowed_ip(ip: IpAddr, config: &AppConfig) -> bool {
 /// Check if IP is within the specified network range
 ///
 /// Supports CIDR notation (e.g., "192.168.1.0/24")
-fn is_ip_in_network(ip: IpAddr, network: &str) -> bool {
+pub fn is_ip_in_network(ip: IpAddr, network: &str) -> bool {
     let parts: Vec<&str> = network.split('/').collect();
     if parts.len() == 2 {
         let (ip_str, mask) = (parts[0], parts[1]);
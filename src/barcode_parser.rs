@@ -2,6 +2,8 @@
 // This module is SYNCHRONIZED with mobile app (rust/src/api/barcode.rs)
 // Any changes here MUST be replicated in mobile app parser!
 
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
 /// Normalize and clean barcode data - removes control characters but keeps internal spaces
 pub fn normalize_barcode_data(raw_data: &str) -> String {
     raw_data
@@ -11,11 +13,80 @@ pub fn normalize_barcode_data(raw_data: &str) -> String {
         .collect()
 }
 
+/// Symbologies whose payload is decoded as IATA BCBP (paper PDF417, mobile AZTEC)
+/// Other accepted scan formats (QR, CODE128, DATAMATRIX) have no decoder yet
+pub const BCBP_DECODER_FORMATS: [&str; 2] = ["PDF417", "AZTEC"];
+
+/// True when a (normalized) barcode format has a decoder
+pub fn has_decoder(format: &str) -> bool {
+    BCBP_DECODER_FORMATS.contains(&format)
+}
+
+/// Minimum characters a BCBP boarding pass needs after normalization
+pub const MIN_BARCODE_LENGTH: usize = 50;
+
+/// True when raw input is longer than `max_length` bytes
+///
+/// Callers check this before parsing: it looks only at the byte length, so an
+/// oversized payload is rejected in constant time without normalization or char scanning.
+pub fn exceeds_max_length(raw: &str, max_length: usize) -> bool {
+    raw.len() > max_length
+}
+
+/// Encoding damage found in raw barcode input (e.g. latin-1 bytes from a scanner
+/// decoded as UTF-8), which normalization would otherwise drop silently
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingIssue {
+    /// Number of U+FFFD replacement characters in the input
+    pub replacement_chars: usize,
+    /// Number of non-ASCII characters normalization would drop (replacement chars included)
+    pub dropped_chars: usize,
+    /// Character index of the first dropped character
+    pub first_position: usize,
+    /// Characters left after normalization
+    pub usable_chars: usize,
+}
+
+/// Check raw barcode input for encoding problems before normalization
+///
+/// Replacement characters always mean the scanner sent bytes that weren't UTF-8, so
+/// positional fields after them would shift. Other non-ASCII characters are only an
+/// issue when dropping them leaves too few characters to be a boarding pass.
+pub fn check_encoding(raw: &str) -> Result<(), EncodingIssue> {
+    let mut replacement_chars = 0;
+    let mut dropped_chars = 0;
+    let mut first_position = None;
+    for (position, c) in raw.chars().enumerate() {
+        if !c.is_ascii() {
+            dropped_chars += 1;
+            first_position.get_or_insert(position);
+            if c == char::REPLACEMENT_CHARACTER {
+                replacement_chars += 1;
+            }
+        }
+    }
+
+    let Some(first_position) = first_position else {
+        return Ok(());
+    };
+    let usable_chars = normalize_barcode_data(raw).len();
+    if replacement_chars > 0 || usable_chars < MIN_BARCODE_LENGTH {
+        return Err(EncodingIssue {
+            replacement_chars,
+            dropped_chars,
+            first_position,
+            usable_chars,
+        });
+    }
+    Ok(())
+}
+
 /// PDF417 parsed data structure
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct PDF417Data {
     pub passenger_name: String,
+    pub passenger_name_raw: String,
     pub e_ticket_indicator: String,
     pub booking_code: String,
     pub origin: String,
@@ -94,30 +165,244 @@ fn format_passenger_name(raw_name: &str) -> String {
     }
 }
 
-/// Multi-strategy IATA BCBP parser with fallback
-/// Synchronized with mobile app parser
-pub fn parse_iata_bcbp(barcode: &str) -> Option<PDF417Data> {
-    // Normalize first - remove control characters but keep spaces
-    let normalized = normalize_barcode_data(barcode);
+/// Output style for the decoded passenger name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameFormat {
+    /// "Title Firstname Lastname" (e.g., "Ms Siti Putri")
+    #[default]
+    Display,
+    /// Name field exactly as encoded in the barcode (e.g., "PUTRI/SITI MS")
+    Raw,
+    /// IATA "LAST/FIRST TITLE" order in title case (e.g., "Putri/Siti Ms")
+    LastFirst,
+}
+
+/// Format the raw IATA name field using the requested style
+pub fn apply_name_format(raw_name: &str, format: NameFormat) -> String {
+    match format {
+        NameFormat::Display => format_passenger_name(raw_name),
+        NameFormat::Raw => raw_name.to_string(),
+        NameFormat::LastFirst => raw_name
+            .split('/')
+            .map(|part| title_case(part.trim()))
+            .collect::<Vec<_>>()
+            .join("/"),
+    }
+}
 
-    if normalized.len() < 50 {
+/// Resolve a BCBP Julian day-of-year ("001"-"366") to a calendar date (server-only)
+/// Uses the reference time's year; a date more than 30 days after the reference is taken from
+/// the previous year (pass issued in December, scanned in January). Returns None for "000",
+/// non-numeric input, or a day that does not exist in the reference year (366 in a non-leap year)
+pub fn julian_to_gregorian(julian: &str, reference: DateTime<Utc>) -> Option<NaiveDate> {
+    if julian.len() != 3 || !julian.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let day: u32 = julian.parse().ok()?;
+    if day == 0 {
         return None;
     }
 
-    let chars: Vec<char> = normalized.chars().collect();
+    let reference_date = reference.date_naive();
+    let year = reference_date.year();
+    let date = NaiveDate::from_yo_opt(year, day)?;
+    if date <= reference_date + Duration::days(30) {
+        Some(date)
+    } else {
+        NaiveDate::from_yo_opt(year - 1, day)
+    }
+}
 
-    if chars.len() < 50 || chars[0] != 'M' {
+/// Guess the symbology of a raw barcode string without a declared format
+/// Returns "PDF417" for IATA BCBP boarding passes (M + leg count, minimum length,
+/// and a structure one of the parser strategies accepts), None otherwise
+pub fn detect_format(raw: &str) -> Option<&'static str> {
+    let chars = bcbp_chars(raw)?;
+    if !matches!(chars[1], '1'..='4') {
         return None;
     }
+    parse_chars_with_strategy(&chars).map(|_| "PDF417")
+}
+
+/// Parser strategy that produced a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStrategy {
+    SpaceDelimited,
+    StrictIata,
+    /// Strict layout with a 3-char airline designator; later fields shift one position right
+    StrictIata3CharAirline,
+}
+
+impl ParseStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ParseStrategy::SpaceDelimited => "space_delimited",
+            ParseStrategy::StrictIata => "strict_iata",
+            ParseStrategy::StrictIata3CharAirline => "strict_iata_3char_airline",
+        }
+    }
+}
+
+/// Intermediate parse details for support diagnostics (server-only, not mirrored in the mobile app)
+#[derive(Debug, Clone)]
+pub struct ParseDiagnostics {
+    pub normalized: String,
+    /// Whitespace-separated tokens after the 20-char passenger name field
+    pub tokens: Vec<String>,
+    pub strategy: Option<ParseStrategy>,
+    pub data: Option<PDF417Data>,
+}
+
+/// Run the parser and report which strategy matched, with the normalized input and its tokens
+pub fn diagnose_iata_bcbp(barcode: &str) -> ParseDiagnostics {
+    let normalized = normalize_barcode_data(barcode);
+    let tokens = normalized
+        .chars()
+        .skip(22)
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let (strategy, data) = match parse_with_strategy(barcode) {
+        Some((strategy, data)) => (Some(strategy), Some(data)),
+        None => (None, None),
+    };
+
+    ParseDiagnostics {
+        normalized,
+        tokens,
+        strategy,
+        data,
+    }
+}
+
+/// Best-effort fields from a barcode the full parser rejected (server-only, for rejection logs)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartialBcbp {
+    pub passenger_name_raw: Option<String>,
+    pub booking_code: Option<String>,
+    pub origin: Option<String>,
+    pub destination: Option<String>,
+    pub airline_code: Option<String>,
+    pub flight_number: Option<String>,
+    pub flight_date_julian: Option<String>,
+    pub cabin_class: Option<String>,
+    pub seat_number: Option<String>,
+    pub sequence_number: Option<String>,
+}
+
+impl From<PDF417Data> for PartialBcbp {
+    fn from(data: PDF417Data) -> Self {
+        let non_empty = |value: String| (!value.is_empty()).then_some(value);
+        Self {
+            passenger_name_raw: non_empty(data.passenger_name_raw),
+            booking_code: non_empty(data.booking_code),
+            origin: non_empty(data.origin),
+            destination: non_empty(data.destination),
+            airline_code: non_empty(data.airline_code),
+            flight_number: non_empty(data.flight_number),
+            flight_date_julian: non_empty(data.flight_date_julian),
+            cabin_class: non_empty(data.cabin_class),
+            seat_number: non_empty(data.seat_number),
+            sequence_number: non_empty(data.sequence_number),
+        }
+    }
+}
+
+/// Extract whatever fields are recognizable, token by token like the space-delimited strategy
+///
+/// Uses the full parser when it succeeds. Otherwise each field is taken only if its token
+/// exists and has the expected shape, so a truncated or damaged barcode still yields its
+/// leading fields. Returns None when nothing could be extracted.
+pub fn parse_partial_bcbp(barcode: &str) -> Option<PartialBcbp> {
+    if let Some(data) = parse_iata_bcbp(barcode) {
+        return Some(data.into());
+    }
+
+    let normalized = normalize_barcode_data(barcode);
+    let chars: Vec<char> = normalized.chars().collect();
+    if chars.first() != Some(&'M') {
+        return None;
+    }
+
+    let mut partial = PartialBcbp::default();
+    let name_end = chars.len().min(22);
+    if name_end > 2 {
+        let name = chars[2..name_end].iter().collect::<String>().trim().to_string();
+        partial.passenger_name_raw = (!name.is_empty()).then_some(name);
+    }
+
+    let remainder: String = chars.iter().skip(22).collect();
+    let tokens: Vec<&str> = remainder.split_whitespace().collect();
+    let alphanumeric = |value: &str| value.chars().all(|c| c.is_ascii_alphanumeric());
+
+    // E-ticket indicator + PNR (6 karakter), mis. "ESMMTHQ"
+    if let Some(token) = tokens.first().filter(|t| t.len() == 7 && alphanumeric(t)) {
+        partial.booking_code = Some(token[1..].to_string());
+    }
+    // Origin + destination + airline, mis. "DHXCGKID"
+    if let Some(token) = tokens.get(1).filter(|t| t.len() >= 6 && t.is_ascii()) {
+        let airport = |value: &str| value.chars().all(|c| c.is_ascii_alphabetic()).then(|| value.to_string());
+        partial.origin = airport(&token[0..3]);
+        partial.destination = airport(&token[3..6]);
+        if token.len() >= 8 && alphanumeric(&token[6..8]) {
+            partial.airline_code = Some(token[6..8].to_string());
+        }
+    }
+    if let Some(token) = tokens.get(2).filter(|t| !t.is_empty() && t.len() <= 5 && alphanumeric(t)) {
+        partial.flight_number = Some(token.to_string());
+    }
+    // Julian date + class + seat + sequence, mis. "032Y007A0002"
+    if let Some(token) = tokens.get(3).filter(|t| t.is_ascii()) {
+        if token.len() >= 3 && token[0..3].chars().all(|c| c.is_ascii_digit()) {
+            partial.flight_date_julian = Some(token[0..3].to_string());
+        }
+        if let Some(class) = token.chars().nth(3).filter(|c| c.is_ascii_alphabetic()) {
+            partial.cabin_class = Some(class.to_ascii_uppercase().to_string());
+        }
+        if token.len() >= 8 && !token[4..8].contains("INF") {
+            partial.seat_number = Some(token[4..8].to_string());
+        }
+        if token.len() >= 12 {
+            partial.sequence_number = Some(token[8..12].to_string());
+        }
+    }
+
+    (partial != PartialBcbp::default()).then_some(partial)
+}
+
+/// Multi-strategy IATA BCBP parser with fallback
+/// Synchronized with mobile app parser
+pub fn parse_iata_bcbp(barcode: &str) -> Option<PDF417Data> {
+    parse_with_strategy(barcode).map(|(_, data)| data)
+}
+
+fn parse_with_strategy(barcode: &str) -> Option<(ParseStrategy, PDF417Data)> {
+    parse_chars_with_strategy(&bcbp_chars(barcode)?)
+}
 
+/// Normalized characters of a barcode that has the minimum BCBP length and the 'M' format code
+fn bcbp_chars(barcode: &str) -> Option<Vec<char>> {
+    // Normalize first - remove control characters but keep spaces
+    let chars: Vec<char> = normalize_barcode_data(barcode).chars().collect();
+    (chars.len() >= MIN_BARCODE_LENGTH && chars[0] == 'M').then_some(chars)
+}
+
+/// Run the parser strategies in order on characters that passed `bcbp_chars`
+fn parse_chars_with_strategy(chars: &[char]) -> Option<(ParseStrategy, PDF417Data)> {
     // Strategy 1: Try space-delimited format (Indonesian airlines)
-    if let Some(data) = try_parse_space_delimited(&chars) {
-        return Some(data);
+    if let Some(data) = try_parse_space_delimited(chars) {
+        return Some((ParseStrategy::SpaceDelimited, data));
     }
 
     // Strategy 2: Try strict IATA fixed-length format (International airlines)
-    if let Some(data) = try_parse_strict_iata(&chars) {
-        return Some(data);
+    if let Some(data) = try_parse_strict_iata(chars, 2) {
+        return Some((ParseStrategy::StrictIata, data));
+    }
+
+    // Strategy 3: Strict format with a 3-char airline designator (e.g. "GIA" instead of "GA")
+    if let Some(data) = try_parse_strict_iata(chars, 3) {
+        return Some((ParseStrategy::StrictIata3CharAirline, data));
     }
 
     None
@@ -214,11 +499,14 @@ fn try_parse_space_delimited(chars: &[char]) -> Option<PDF417Data> {
     } else {
         return None;
     };
-    let cabin_class = if token3.len() >= 4 {
-        token3.chars().nth(3).unwrap_or('Y').to_string()
-    } else {
-        "Y".to_string()
-    };
+    // Class byte must be a letter; blank/invalid stays empty instead of assuming economy ("Y"),
+    // so premium passengers are never silently mislabeled (caller decides on any fallback)
+    let cabin_class = token3
+        .chars()
+        .nth(3)
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase().to_string())
+        .unwrap_or_default();
     let seat_number_raw = if token3.len() >= 8 {
         token3[4..8].trim().to_string()
     } else {
@@ -248,6 +536,7 @@ fn try_parse_space_delimited(chars: &[char]) -> Option<PDF417Data> {
 
     Some(PDF417Data {
         passenger_name: format_passenger_name(&passenger_name),
+        passenger_name_raw: passenger_name,
         e_ticket_indicator,
         booking_code,
         origin,
@@ -263,11 +552,14 @@ fn try_parse_space_delimited(chars: &[char]) -> Option<PDF417Data> {
     })
 }
 
-// Strategy 2: Strict IATA fixed-length parser (for international airlines)
-// Format: M1NAME(20)E(1)BOOKING(6)ORIGIN(3)DEST(3)AIRLINE(2)FLIGHT(5)JULIAN(3)CLASS(1)SEAT(4)SEQ(4)STATUS(1)
-fn try_parse_strict_iata(chars: &[char]) -> Option<PDF417Data> {
-    // Minimum length for strict IATA: 2 + 20 + 1 + 6 + 3 + 3 + 2 + 5 + 3 + 1 + 4 + 4 + 1 = 55
-    if chars.len() < 55 {
+// Strategy 2/3: Strict IATA fixed-length parser (for international airlines)
+// Format: M1NAME(20)E(1)BOOKING(6)ORIGIN(3)DEST(3)AIRLINE(2|3)FLIGHT(5)JULIAN(3)CLASS(1)SEAT(4)SEQ(4)STATUS(1)
+// airline_len 3 shifts every field after the airline designator one position right
+fn try_parse_strict_iata(chars: &[char], airline_len: usize) -> Option<PDF417Data> {
+    let shift = airline_len - 2;
+
+    // Minimum length for strict IATA: 2 + 20 + 1 + 6 + 3 + 3 + 2 + 5 + 3 + 1 + 4 + 4 + 1 = 55 (+1 for 3-char airline)
+    if chars.len() < 55 + shift {
         return None;
     }
 
@@ -278,6 +570,11 @@ fn try_parse_strict_iata(chars: &[char]) -> Option<PDF417Data> {
         return None;
     }
 
+    // Positions are trusted blindly below, so reject structurally inconsistent data first
+    if !strict_iata_structure_is_consistent(chars, airline_len) {
+        return None;
+    }
+
     // IMPORTANT: Don't trim before slicing - positions are fixed!
     // Passenger name is EXACTLY positions 2-22 (20 chars), trim AFTER extraction
     let passenger_name_raw: String = chars[2..22].iter().collect();
@@ -287,17 +584,17 @@ fn try_parse_strict_iata(chars: &[char]) -> Option<PDF417Data> {
     let booking_code = chars[23..29].iter().collect::<String>().trim().to_string();
     let origin = chars[29..32].iter().collect::<String>().to_string();
     let destination = chars[32..35].iter().collect::<String>().to_string();
-    let airline_code = chars[35..37].iter().collect::<String>().to_string();
-    let flight_number = chars[37..42].iter().collect::<String>().trim().to_string();
-    let flight_date_julian = chars[42..45].iter().collect::<String>().to_string();
-    let cabin_class = chars[45].to_string();
-    let seat_number_raw = if chars.len() >= 50 {
-        chars[46..50].iter().collect::<String>().trim().to_string()
+    let airline_code = chars[35..37 + shift].iter().collect::<String>().to_string();
+    let flight_number = chars[37 + shift..42 + shift].iter().collect::<String>().trim().to_string();
+    let flight_date_julian = chars[42 + shift..45 + shift].iter().collect::<String>().to_string();
+    let cabin_class = chars[45 + shift].to_string();
+    let seat_number_raw = if chars.len() >= 50 + shift {
+        chars[46 + shift..50 + shift].iter().collect::<String>().trim().to_string()
     } else {
         "".to_string()
     };
-    let sequence_number = if chars.len() >= 54 {
-        chars[50..54].iter().collect::<String>().trim().to_string()
+    let sequence_number = if chars.len() >= 54 + shift {
+        chars[50 + shift..54 + shift].iter().collect::<String>().trim().to_string()
     } else {
         "".to_string()
     };
@@ -310,15 +607,16 @@ fn try_parse_strict_iata(chars: &[char]) -> Option<PDF417Data> {
         seat_number_raw
     };
 
-    // Conditional data (everything after position 55)
-    let conditional_data = if chars.len() > 55 {
-        Some(chars[55..].iter().collect::<String>().trim().to_string())
+    // Conditional data (everything after position 55, or 56 with a 3-char airline)
+    let conditional_data = if chars.len() > 55 + shift {
+        Some(chars[55 + shift..].iter().collect::<String>().trim().to_string())
     } else {
         None
     };
 
     Some(PDF417Data {
         passenger_name: format_passenger_name(&passenger_name),
+        passenger_name_raw: passenger_name,
         e_ticket_indicator,
         booking_code,
         origin,
@@ -334,10 +632,123 @@ fn try_parse_strict_iata(chars: &[char]) -> Option<PDF417Data> {
     })
 }
 
+/// Structural sanity check for the strict fixed-length layout
+/// Verifies that each mandatory field has the character class its position implies
+/// and that the variable-field size (2 hex digits after the status byte) fits the data.
+/// With a 3-char airline designator its last char must be a letter, so a 2-char airline
+/// followed by a numeric flight number is never read as the 3-char variant (or vice versa).
+fn strict_iata_structure_is_consistent(chars: &[char], airline_len: usize) -> bool {
+    let all = |range: std::ops::Range<usize>, pred: fn(&char) -> bool| chars[range].iter().all(pred);
+    let shift = airline_len - 2;
+
+    // Number of legs encoded: 1-4
+    if !matches!(chars[1], '1'..='4') {
+        return false;
+    }
+
+    if !chars[22].is_ascii_alphabetic()
+        || !all(29..35, char::is_ascii_alphabetic)
+        || !all(35..37 + shift, char::is_ascii_alphanumeric)
+        || (shift == 1 && !chars[37].is_ascii_alphabetic())
+        || !chars[45 + shift].is_ascii_alphabetic()
+    {
+        return false;
+    }
+
+    // Flight number: leading digits, optional operational suffix letter, space padded
+    let flight_number: String = chars[37 + shift..42 + shift].iter().collect();
+    let flight_number = flight_number.trim();
+    if flight_number.is_empty()
+        || !flight_number.starts_with(|c: char| c.is_ascii_digit())
+        || !flight_number.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return false;
+    }
+
+    // Julian date: 001-366
+    let julian: String = chars[42 + shift..45 + shift].iter().collect();
+    match julian.parse::<u16>() {
+        Ok(day) if (1..=366).contains(&day) && all(42 + shift..45 + shift, char::is_ascii_digit) => {}
+        _ => return false,
+    }
+
+    // Variable-size field length must be hex and must not run past the end of the data
+    let size_start = 55 + shift;
+    if chars.len() > size_start {
+        if chars.len() < size_start + 2 {
+            return false;
+        }
+        let size_field: String = chars[size_start..size_start + 2].iter().collect();
+        match usize::from_str_radix(&size_field, 16) {
+            Ok(size) if size_start + 2 + size <= chars.len() => {}
+            _ => return false,
+        }
+    }
+
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_oversized_barcode_rejected_before_normalization() {
+        // 16 MB "barcode" yang diawali header BCBP valid
+        let golden = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let huge = format!("{}{}", golden, " \u{7}".repeat(8 * 1024 * 1024));
+
+        let started = std::time::Instant::now();
+        assert!(exceeds_max_length(&huge, 512));
+        assert!(started.elapsed() < std::time::Duration::from_millis(20));
+
+        // Batas default tidak mengganggu boarding pass normal
+        assert!(!exceeds_max_length(golden, 512));
+        assert!(parse_iata_bcbp(golden).is_some());
+        assert!(exceeds_max_length(golden, golden.len() - 1));
+    }
+
+    #[test]
+    fn test_check_encoding_flags_replacement_chars() {
+        // Latin-1 "Ö" (0xD6) dari scanner tiba sebagai U+FFFD
+        let barcode = "M1PUTRI/SIT\u{FFFD} MS      EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let issue = check_encoding(barcode).unwrap_err();
+        assert_eq!(issue.replacement_chars, 1);
+        assert_eq!(issue.dropped_chars, 1);
+        assert_eq!(issue.first_position, 11);
+
+        assert!(check_encoding("M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300").is_ok());
+    }
+
+    #[test]
+    fn test_check_encoding_flags_too_few_usable_chars() {
+        let issue = check_encoding("M1ÄÖÜ/SHORT").unwrap_err();
+        assert_eq!(issue.replacement_chars, 0);
+        assert_eq!(issue.dropped_chars, 3);
+        assert!(issue.usable_chars < MIN_BARCODE_LENGTH);
+
+        // Pendek tapi murni ASCII: bukan masalah encoding, biarkan parser menolak
+        assert!(check_encoding("M1SHORT").is_ok());
+    }
+
+    #[test]
+    fn test_partial_parse_of_truncated_barcode() {
+        let barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473";
+        assert!(parse_iata_bcbp(barcode).is_none());
+
+        let partial = parse_partial_bcbp(barcode).unwrap();
+        assert_eq!(partial.passenger_name_raw.as_deref(), Some("BAYU/MUHAMMAD MR"));
+        assert_eq!(partial.booking_code.as_deref(), Some("SMMTHQ"));
+        assert_eq!(partial.origin.as_deref(), Some("DHX"));
+        assert_eq!(partial.destination.as_deref(), Some("CGK"));
+        assert_eq!(partial.airline_code.as_deref(), Some("ID"));
+        assert_eq!(partial.flight_number.as_deref(), Some("6473"));
+        assert_eq!(partial.flight_date_julian, None);
+        assert_eq!(partial.seat_number, None);
+
+        assert_eq!(parse_partial_bcbp("NOT-A-BOARDING-PASS"), None);
+    }
+
     #[test]
     fn test_parse_garuda() {
         let barcode = "M1PRASETYO/YUDHA DWI  EE6UVIL CGKSUBGA 0312 260Y045C0120 348>5180  5259B1A              2A12621429493830 GA                        N";
@@ -420,6 +831,35 @@ mod tests {
         assert_eq!(data.seat_number, "045C");
     }
 
+    #[test]
+    fn test_detect_format() {
+        let bcbp = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.";
+        assert_eq!(detect_format(bcbp), Some("PDF417"));
+
+        assert_eq!(detect_format("8991002101234"), None);
+        assert_eq!(detect_format("https://example.com/checkin?pnr=SMMTHQ&name=BAYU/MUHAMMAD"), None);
+        // Long enough and starts with M, but not a boarding pass structure
+        assert_eq!(detect_format(&format!("M{}", "x".repeat(60))), None);
+    }
+
+    #[test]
+    fn test_cabin_class_valid_byte() {
+        let barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032J007A0002 300.";
+        let data = parse_iata_bcbp(barcode).unwrap();
+        assert_eq!(data.cabin_class, "J");
+    }
+
+    #[test]
+    fn test_cabin_class_blank_or_invalid_byte_is_empty() {
+        // Class byte missing (blank) - must not default to economy
+        let blank = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032 007A0002 300.";
+        assert_eq!(parse_iata_bcbp(blank).unwrap().cabin_class, "");
+
+        // Class byte present but not a letter
+        let invalid = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 0320007A0002 300.";
+        assert_eq!(parse_iata_bcbp(invalid).unwrap().cabin_class, "");
+    }
+
     #[test]
     fn test_name_formatting_with_title() {
         // User's requested format: PUTRI/SITI MS -> Ms Siti Putri
@@ -508,4 +948,81 @@ mod tests {
         assert_eq!(data.flight_number, "1630");
         assert_eq!(data.flight_date_julian, "284");
     }
+
+    #[test]
+    fn test_name_format_styles() {
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let data = parse_iata_bcbp(barcode).unwrap();
+        assert_eq!(data.passenger_name_raw, "PUTRI/SITI MS");
+        assert_eq!(apply_name_format(&data.passenger_name_raw, NameFormat::Display), "Ms Siti Putri");
+        assert_eq!(apply_name_format(&data.passenger_name_raw, NameFormat::Raw), "PUTRI/SITI MS");
+        assert_eq!(apply_name_format(&data.passenger_name_raw, NameFormat::LastFirst), "Putri/Siti Ms");
+    }
+
+    #[test]
+    fn test_strict_iata_rejects_inconsistent_field_size() {
+        // Strict layout (no space-delimited tokens), variable-size field "00"
+        let valid = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANVN0123 045Y012A0034100";
+        let data = parse_iata_bcbp(valid).expect("consistent strict barcode should parse");
+        assert_eq!(data.origin, "SGN");
+        assert_eq!(data.destination, "HAN");
+        assert_eq!(data.airline_code, "VN");
+        assert_eq!(data.flight_number, "0123");
+        assert_eq!(data.flight_date_julian, "045");
+
+        // Field size 0x3F claims 63 more chars than are present
+        let corrupted_size = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANVN0123 045Y012A003413F";
+        assert!(parse_iata_bcbp(corrupted_size).is_none());
+
+        // Non-hex field size
+        let garbage_size = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANVN0123 045Y012A00341ZZ";
+        assert!(parse_iata_bcbp(garbage_size).is_none());
+
+        // Julian date out of range
+        let bad_julian = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANVN0123 400Y012A0034100";
+        assert!(parse_iata_bcbp(bad_julian).is_none());
+    }
+
+    #[test]
+    fn test_strict_iata_three_char_airline_designator() {
+        // Same layout with "HVN" instead of "VN": every field after the airline shifts by one
+        let barcode = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANHVN0123 045Y012A0034100";
+        let diagnostics = diagnose_iata_bcbp(barcode);
+        assert_eq!(diagnostics.strategy, Some(ParseStrategy::StrictIata3CharAirline));
+
+        let data = diagnostics.data.unwrap();
+        assert_eq!(data.origin, "SGN");
+        assert_eq!(data.destination, "HAN");
+        assert_eq!(data.airline_code, "HVN");
+        assert_eq!(data.flight_number, "0123");
+        assert_eq!(data.flight_date_julian, "045");
+        assert_eq!(data.cabin_class, "Y");
+        assert_eq!(data.seat_number, "012A");
+        assert_eq!(data.sequence_number, "0034");
+
+        // 2-char designator still uses the plain strict strategy
+        let two_char = "M1NGUYENVANTHANH/ANHTUEABC123SGNHANVN0123 045Y012A0034100";
+        assert_eq!(diagnose_iata_bcbp(two_char).strategy, Some(ParseStrategy::StrictIata));
+    }
+
+    #[test]
+    fn test_julian_to_gregorian() {
+        let at = |y: i32, m: u32, d: u32| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+        let date = |y: i32, m: u32, d: u32| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(julian_to_gregorian("260", at(2025, 9, 17)), date(2025, 9, 17));
+        // Up to 30 days ahead stays in the reference year
+        assert_eq!(julian_to_gregorian("060", at(2025, 2, 1)), date(2025, 3, 1));
+        // Further ahead: pass issued last December, scanned in January
+        assert_eq!(julian_to_gregorian("365", at(2025, 1, 3)), date(2024, 12, 30));
+        assert_eq!(julian_to_gregorian("366", at(2024, 12, 31)), date(2024, 12, 31));
+
+        // Day 366 in a non-leap year, "000" and non-numeric input
+        assert_eq!(julian_to_gregorian("366", at(2025, 1, 5)), None);
+        assert_eq!(julian_to_gregorian("366", at(2025, 6, 1)), None);
+        assert_eq!(julian_to_gregorian("000", at(2025, 6, 1)), None);
+        assert_eq!(julian_to_gregorian("367", at(2025, 6, 1)), None);
+        assert_eq!(julian_to_gregorian("2A0", at(2025, 6, 1)), None);
+        assert_eq!(julian_to_gregorian("26", at(2025, 6, 1)), None);
+    }
 }
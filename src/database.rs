@@ -2,16 +2,26 @@ use crate::{
     errors::AppError,
     models::{
         CreateFlight, Flight, FlightStatistics, GetScanDataQuery, ScanData, ScanDataInput,
-        ScansByHour, TopDevice, UpdateFlight, DecodedBarcode, DecodeRequest, DecodedStatistics,
-        CreateRejectionLog, RejectionLog, RejectionLogQuery,
+        ScansByHour, TopDevice, FormatCount, UpdateFlight, BoardingProgress, FlightDetail, DecodedBarcode, DecodeRequest, DecodedStatistics, DecodedDuplicate,
+        CreateRejectionLog, RejectionLog, RejectionLogQuery, ScanCreated, BulkSyncSummary, BulkConflict, BulkConflictMode, GetDecodedBarcodesQuery,
+        ExportFlightsQuery, DecodedStatisticsByAirlineQuery, AirlineDecodeStatistics, DeviceFlightStatistics, UpdateDecodedBarcode, DecodedFlightMismatch,
+        UpsertAirportCode, UpsertAirlineCode, UpsertCabinClassCode, CodeImportSummary, CabinClassCount, DashboardSummary,
+        DecodeDiagnostics, ManifestReconciliation, UnexpectedPassenger, BoardingTiming, PurgeOperationalDataSummary,
+        NameFormat,
     },
     barcode_parser,
+    extractors::Pagination,
+    logging,
 };
 use chrono::{DateTime, Local, NaiveDate, Utc};
 use sqlx::PgPool;
 
 // Fungsi untuk membuat penerbangan baru di database
-pub async fn create_flight(pool: &PgPool, flight: CreateFlight) -> Result<Flight, AppError> {
+pub async fn create_flight(
+    pool: &PgPool,
+    flight: CreateFlight,
+    created_by: Option<i32>,
+) -> Result<Flight, AppError> {
     // Validasi: departure_time harus sama dengan tanggal scan (scanned_at)
     let scan_date = flight.scanned_at.with_timezone(&Local).date_naive();
     let departure_date = flight.departure_time.with_timezone(&Local).date_naive();
@@ -29,9 +39,9 @@ pub async fn create_flight(pool: &PgPool, flight: CreateFlight) -> Result<Flight
     let new_flight = sqlx::query_as!(
         Flight,
         r#"
-        INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate, device_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id
+        INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate, device_id, capacity, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity
         "#,
         flight.flight_number,
         flight.airline,
@@ -39,7 +49,9 @@ pub async fn create_flight(pool: &PgPool, flight: CreateFlight) -> Result<Flight
         flight.departure_time,
         flight.destination,
         flight.gate,
-        flight.device_id
+        flight.device_id,
+        flight.capacity,
+        created_by
     )
         .fetch_one(pool)
         .await;
@@ -74,6 +86,8 @@ pub async fn create_flight(pool: &PgPool, flight: CreateFlight) -> Result<Flight
 }
 
 // Fungsi untuk mengambil penerbangan berdasarkan nomor penerbangan dan tanggal (untuk handling duplicate)
+// Nomor flight dinormalisasi di kedua sisi (spasi/strip dibuang, huruf besar), jadi " ga-312 "
+// menemukan "GA312" maupun "GA-312" yang tersimpan; kecocokan persis didahulukan
 pub async fn get_flight_by_number_and_date(
     pool: &PgPool,
     flight_number: &str,
@@ -83,11 +97,14 @@ pub async fn get_flight_by_number_and_date(
         Flight,
         r#"
         SELECT id, flight_number, airline, aircraft, departure_time,
-               destination, gate, is_active, created_at, updated_at, device_id
+               destination, gate, is_active, created_at, updated_at, device_id, capacity
         FROM flights
-        WHERE flight_number = $1
+        WHERE upper(replace(replace(flight_number, ' ', ''), '-', ''))
+              = upper(replace(replace($1, ' ', ''), '-', ''))
           AND (departure_time AT TIME ZONE 'utc')::date = $2
           AND is_active = true
+        ORDER BY (flight_number = $1) DESC
+        LIMIT 1
         "#,
         flight_number,
         date
@@ -98,42 +115,113 @@ pub async fn get_flight_by_number_and_date(
     Ok(flight)
 }
 
-// Fungsi untuk mengambil semua penerbangan, dengan filter tanggal opsional
+// Fungsi untuk mengambil semua penerbangan, dengan filter tanggal dan limit opsional
 pub async fn get_all_flights(
     pool: &PgPool,
     date: Option<NaiveDate>,
-) -> Result<(Vec<Flight>, i64), AppError> {
+    pagination: Option<Pagination>,
+) -> Result<Vec<Flight>, AppError> {
     let mut query_builder = sqlx::QueryBuilder::new(
-        "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id FROM flights WHERE is_active = true ",
+        "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE is_active = true ",
     );
-    let mut count_builder =
-        sqlx::QueryBuilder::new("SELECT COUNT(*) FROM flights WHERE is_active = true ");
 
     if let Some(d) = date {
         // Casting ke date harus dilakukan dengan zona waktu yang benar
         query_builder.push("AND (departure_time AT TIME ZONE 'utc')::date = ");
         query_builder.push_bind(d);
+    }
+
+    query_builder.push(" ORDER BY departure_time ASC, id ASC");
+
+    if let Some(Pagination { limit, offset }) = pagination {
+        query_builder.push(" LIMIT ").push_bind(limit);
+        query_builder.push(" OFFSET ").push_bind(offset);
+    }
+
+    let flights = query_builder.build_query_as::<Flight>().fetch_all(pool).await?;
+    Ok(flights)
+}
+
+// Fungsi untuk menghitung penerbangan aktif tanpa mengambil baris (count_only)
+pub async fn count_all_flights(pool: &PgPool, date: Option<NaiveDate>) -> Result<i64, AppError> {
+    let mut count_builder =
+        sqlx::QueryBuilder::new("SELECT COUNT(*) FROM flights WHERE is_active = true ");
+
+    if let Some(d) = date {
         count_builder.push("AND (departure_time AT TIME ZONE 'utc')::date = ");
         count_builder.push_bind(d);
     }
 
-    query_builder.push(" ORDER BY departure_time ASC");
+    let total: (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+    Ok(total.0)
+}
+
+
+// Fungsi untuk export penerbangan dalam rentang tanggal keberangkatan (JSON)
+pub async fn export_flights(pool: &PgPool, query: &ExportFlightsQuery) -> Result<Vec<Flight>, AppError> {
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE 1=1 ",
+    );
+    push_flight_export_filters(&mut query_builder, query);
 
     let flights = query_builder.build_query_as::<Flight>().fetch_all(pool).await?;
-    let total: (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+    Ok(flights)
+}
+
+// Versi streaming untuk export CSV: baris dikirim satu per satu lewat channel
+// sehingga export periode panjang tidak perlu dimuat seluruhnya ke memori
+pub fn stream_flights_for_export(
+    pool: PgPool,
+    query: ExportFlightsQuery,
+) -> tokio::sync::mpsc::Receiver<Result<Flight, sqlx::Error>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        use futures_util::StreamExt;
+
+        let mut query_builder = sqlx::QueryBuilder::new(
+            "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE 1=1 ",
+        );
+        push_flight_export_filters(&mut query_builder, &query);
 
-    Ok((flights, total.0))
+        let mut rows = query_builder.build_query_as::<Flight>().fetch(&pool);
+        while let Some(row) = rows.next().await {
+            // Client menutup koneksi - hentikan query
+            if tx.send(row).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
 }
 
+// Filter bersama untuk export JSON dan CSV
+fn push_flight_export_filters(
+    builder: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    query: &ExportFlightsQuery,
+) {
+    builder
+        .push(" AND (departure_time AT TIME ZONE 'utc')::date BETWEEN ")
+        .push_bind(query.from)
+        .push(" AND ")
+        .push_bind(query.to);
+
+    if !query.include_inactive.unwrap_or(false) {
+        builder.push(" AND is_active = true");
+    }
+
+    builder.push(" ORDER BY departure_time ASC, id ASC");
+}
 
 // Fungsi untuk mengambil satu penerbangan berdasarkan ID
-pub async fn get_flight_by_id(pool: &PgPool, id: i32) -> Result<Flight, AppError> {
+pub async fn get_flight_by_id(executor: impl sqlx::PgExecutor<'_>, id: i32) -> Result<Flight, AppError> {
     let flight = sqlx::query_as!(
         Flight,
-        "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id FROM flights WHERE id = $1 AND is_active = true",
+        "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE id = $1 AND is_active = true",
         id
     )
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?
         .ok_or(AppError::FlightNotFound)?;
 
@@ -141,6 +229,7 @@ pub async fn get_flight_by_id(pool: &PgPool, id: i32) -> Result<Flight, AppError
 }
 
 // Fungsi untuk memperbarui data penerbangan
+// Field None tidak berubah (COALESCE); field di clear_fields dikosongkan (TBD / NULL)
 pub async fn update_flight(
     pool: &PgPool,
     id: i32,
@@ -152,14 +241,15 @@ pub async fn update_flight(
         UPDATE flights
         SET
             airline = COALESCE($1, airline),
-            aircraft = COALESCE($2, aircraft),
+            aircraft = CASE WHEN $9 THEN 'TBD' ELSE COALESCE($2, aircraft) END,
             departure_time = COALESCE($3, departure_time),
             destination = COALESCE($4, destination),
-            gate = COALESCE($5, gate),
+            gate = CASE WHEN $10 THEN 'TBD' ELSE COALESCE($5, gate) END,
             is_active = COALESCE($6, is_active),
+            capacity = CASE WHEN $11 THEN NULL ELSE COALESCE($7, capacity) END,
             updated_at = NOW()
-        WHERE id = $7
-        RETURNING id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id
+        WHERE id = $8
+        RETURNING id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity
         "#,
         flight.airline,
         flight.aircraft,
@@ -167,7 +257,11 @@ pub async fn update_flight(
         flight.destination,
         flight.gate,
         flight.is_active,
-        id
+        flight.capacity,
+        id,
+        flight.clears("aircraft"),
+        flight.clears("gate"),
+        flight.clears("capacity")
     )
         .fetch_one(pool)
         .await?;
@@ -175,6 +269,26 @@ pub async fn update_flight(
     Ok(updated_flight)
 }
 
+// Fungsi untuk "touch" penerbangan: set updated_at = NOW() tanpa mengubah data,
+// sehingga penerbangan ikut terkirim lagi pada sync berikutnya (get_flights_since)
+pub async fn touch_flight(pool: &PgPool, id: i32) -> Result<Flight, AppError> {
+    let flight = sqlx::query_as!(
+        Flight,
+        r#"
+        UPDATE flights
+        SET updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity
+        "#,
+        id
+    )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::FlightNotFound)?;
+
+    Ok(flight)
+}
+
 // Fungsi untuk soft delete penerbangan
 pub async fn delete_flight(pool: &PgPool, id: i32) -> Result<(), AppError> {
     let result = sqlx::query!(
@@ -192,33 +306,70 @@ pub async fn delete_flight(pool: &PgPool, id: i32) -> Result<(), AppError> {
 }
 
 // Fungsi untuk mengambil statistik penerbangan
-pub async fn get_flight_statistics(pool: &PgPool, id: i32) -> Result<FlightStatistics, AppError> {
-    let flight_info = get_flight_by_id(pool, id).await?;
+// Dengan zero_fill, jam tanpa scan di antara scan pertama dan terakhir diisi count 0
+pub async fn get_flight_statistics(
+    pool: &PgPool,
+    id: i32,
+    zero_fill: bool,
+) -> Result<FlightStatistics, AppError> {
+    let flight = get_flight_by_id(pool, id).await?;
+    flight_statistics_for(pool, &flight, zero_fill).await
+}
 
-    let total_scans: (i64,) =
-        sqlx::query_as("SELECT COUNT(*) FROM scan_data WHERE flight_id = $1")
-            .bind(id)
-            .fetch_one(pool)
-            .await?;
+// Statistik scan untuk flight yang sudah diambil pemanggil, agar flight tidak dibaca ulang
+async fn flight_statistics_for(
+    pool: &PgPool,
+    flight: &Flight,
+    zero_fill: bool,
+) -> Result<FlightStatistics, AppError> {
+    let id = flight.id;
 
-    let unique_scans: (i64,) = sqlx::query_as(
-        "SELECT COUNT(DISTINCT barcode_value) FROM scan_data WHERE flight_id = $1",
+    // Semua agregat dibaca dari satu snapshot (REPEATABLE READ) agar scan yang masuk bersamaan
+    // tidak membuat total, unique, dan breakdown saling tidak konsisten
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let (total_scans, unique_scans): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(DISTINCT barcode_value) FROM scan_data WHERE flight_id = $1",
     )
         .bind(id)
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-    let scans_by_hour: Vec<ScansByHour> = sqlx::query_as(
+    let scans_by_hour_sql = if zero_fill {
+        r#"
+        WITH bounds AS (
+            SELECT DATE_TRUNC('hour', MIN(scan_time)) as first_hour,
+                   DATE_TRUNC('hour', MAX(scan_time)) as last_hour
+            FROM scan_data
+            WHERE flight_id = $1
+        ),
+        hours AS (
+            SELECT generate_series(first_hour, last_hour, INTERVAL '1 hour') as hour_start
+            FROM bounds
+        )
+        SELECT TO_CHAR(h.hour_start, 'HH24:00') as hour, COUNT(sd.id) as count
+        FROM hours h
+        LEFT JOIN scan_data sd
+            ON sd.flight_id = $1 AND DATE_TRUNC('hour', sd.scan_time) = h.hour_start
+        GROUP BY h.hour_start
+        ORDER BY h.hour_start
+        "#
+    } else {
         r#"
         SELECT TO_CHAR(DATE_TRUNC('hour', scan_time), 'HH24:00') as hour, COUNT(*) as count
         FROM scan_data
         WHERE flight_id = $1
         GROUP BY DATE_TRUNC('hour', scan_time)
         ORDER BY hour
-        "#,
-    )
+        "#
+    };
+
+    let scans_by_hour: Vec<ScansByHour> = sqlx::query_as(scans_by_hour_sql)
         .bind(id)
-        .fetch_all(pool)
+        .fetch_all(&mut *tx)
         .await?;
 
     let top_devices: Vec<TopDevice> = sqlx::query_as(
@@ -232,17 +383,34 @@ pub async fn get_flight_statistics(pool: &PgPool, id: i32) -> Result<FlightStati
         "#,
     )
         .bind(id)
-        .fetch_all(pool)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    // Distribusi format barcode; format dinormalisasi agar "pdf417" dan "PDF417" terhitung sama
+    let format_breakdown: Vec<FormatCount> = sqlx::query_as(
+        r#"
+        SELECT UPPER(TRIM(barcode_format)) as format, COUNT(*) as count
+        FROM scan_data
+        WHERE flight_id = $1
+        GROUP BY UPPER(TRIM(barcode_format))
+        ORDER BY count DESC, format
+        "#,
+    )
+        .bind(id)
+        .fetch_all(&mut *tx)
         .await?;
 
+    tx.commit().await?;
+
     Ok(FlightStatistics {
         flight_id: id,
-        flight_number: flight_info.flight_number,
-        total_scans: total_scans.0,
-        unique_scans: unique_scans.0,
-        duplicate_scans: total_scans.0 - unique_scans.0,
+        flight_number: flight.flight_number.clone(),
+        total_scans,
+        unique_scans,
+        duplicate_scans: total_scans - unique_scans,
         scans_by_hour,
         top_devices,
+        format_breakdown,
     })
 }
 
@@ -251,8 +419,18 @@ pub async fn get_decoded_statistics(
     pool: &PgPool,
     flight_id: i32,
 ) -> Result<DecodedStatistics, AppError> {
-    // Get flight info first
+    // Flight tidak ada atau sudah non-aktif (soft delete) -> FlightNotFound (404).
+    // Flight aktif tanpa hasil decode -> statistik bernilai nol, bukan 404.
     let flight = get_flight_by_id(pool, flight_id).await?;
+    decoded_statistics_for(pool, &flight).await
+}
+
+// Statistik decode untuk flight yang sudah diambil pemanggil, agar flight tidak dibaca ulang
+async fn decoded_statistics_for(
+    pool: &PgPool,
+    flight: &Flight,
+) -> Result<DecodedStatistics, AppError> {
+    let flight_id = flight.id;
 
     // Count total decoded barcodes (JOIN with scan_data by flight_id)
     let total_decoded: (i64,) = sqlx::query_as(
@@ -280,23 +458,384 @@ pub async fn get_decoded_statistics(
     .fetch_one(pool)
     .await?;
 
+    // Distribusi cabin class penumpang
+    let class_breakdown = sqlx::query_as::<_, CabinClassCount>(
+        r#"
+        SELECT db.cabin_class, COUNT(*) AS count
+        FROM decode_barcode db
+        JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE sd.flight_id = $1
+        GROUP BY db.cabin_class
+        ORDER BY count DESC, db.cabin_class
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
+
     Ok(DecodedStatistics {
         flight_id,
-        flight_number: flight.flight_number,
+        flight_number: flight.flight_number.clone(),
         total_decoded: total_decoded.0,
         infant_count: infant_count.0,
         adult_count: total_decoded.0 - infant_count.0,
+        class_breakdown,
+    })
+}
+
+// Fungsi untuk laporan penumpang yang ter-decode lebih dari sekali dalam satu penerbangan
+// (penumpang men-tap ulang boarding pass), untuk rekonsiliasi manifest.
+// Satu PNR bisa berisi beberapa penumpang (keluarga), jadi dikelompokkan per penumpang:
+// booking code + nama + sequence number check-in
+pub async fn get_decoded_duplicates(
+    pool: &PgPool,
+    flight_id: i32,
+) -> Result<Vec<DecodedDuplicate>, AppError> {
+    get_flight_by_id(pool, flight_id).await?;
+
+    let duplicates = sqlx::query_as::<_, DecodedDuplicate>(
+        r#"
+        SELECT db.booking_code,
+               db.passenger_name,
+               db.sequence_number,
+               COUNT(*) AS occurrences,
+               ARRAY_AGG(sd.id ORDER BY sd.id) AS scan_data_ids
+        FROM decode_barcode db
+        JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE sd.flight_id = $1
+        GROUP BY db.booking_code, db.passenger_name, db.sequence_number
+        HAVING COUNT(*) > 1
+        ORDER BY occurrences DESC, db.booking_code, db.sequence_number
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(duplicates)
+}
+
+// Fungsi untuk rekonsiliasi manifest terhadap barcode ter-decode pada satu penerbangan.
+// Satu query mengambil semua penumpang ter-decode, selisih himpunan dihitung di Rust.
+// Entri manifest cocok jika sama dengan booking code atau nama penumpang (tanpa beda huruf besar/spasi).
+// Kecocokan dicatat per baris decode: entri berupa nama hanya menandai penumpang itu,
+// penumpang lain pada PNR yang sama tetap dilaporkan sebagai unexpected.
+pub async fn reconcile_manifest(
+    pool: &PgPool,
+    flight_id: i32,
+    manifest: &[String],
+) -> Result<ManifestReconciliation, AppError> {
+    get_flight_by_id(pool, flight_id).await?;
+
+    let rows: Vec<(i32, String, String)> = sqlx::query_as(
+        r#"
+        SELECT db.id, db.booking_code, db.passenger_name
+        FROM decode_barcode db
+        JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE sd.flight_id = $1
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
+
+    let normalize = |value: &str| value.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+    // (id, booking code, nama, booking code ternormalisasi, nama ternormalisasi) - dinormalisasi sekali
+    let passengers: Vec<(i32, String, String, String, String)> = rows
+        .into_iter()
+        .map(|(id, booking_code, passenger_name)| {
+            let booking_key = normalize(&booking_code);
+            let name_key = normalize(&passenger_name);
+            (id, booking_code, passenger_name, booking_key, name_key)
+        })
+        .collect();
+
+    let mut matched_ids = std::collections::HashSet::new();
+    let mut scanned = Vec::new();
+    let mut missing = Vec::new();
+    for entry in manifest {
+        let key = normalize(entry);
+        let mut found = false;
+        for (id, _, _, booking_key, name_key) in &passengers {
+            if *booking_key == key || *name_key == key {
+                matched_ids.insert(*id);
+                found = true;
+            }
+        }
+        if found {
+            scanned.push(entry.clone());
+        } else {
+            missing.push(entry.clone());
+        }
+    }
+
+    // Re-tap penumpang yang sama cukup dilaporkan sekali
+    let unexpected: std::collections::BTreeSet<(String, String)> = passengers
+        .into_iter()
+        .filter(|(id, ..)| !matched_ids.contains(id))
+        .map(|(_, booking_code, passenger_name, ..)| (booking_code, passenger_name))
+        .collect();
+
+    Ok(ManifestReconciliation {
+        scanned,
+        missing,
+        unexpected: unexpected
+            .into_iter()
+            .map(|(booking_code, passenger_name)| UnexpectedPassenger { booking_code, passenger_name })
+            .collect(),
     })
 }
 
+// Fungsi untuk statistik decode beberapa penerbangan sekaligus dalam satu query GROUP BY
+// Flight id yang tidak ada di tabel flights tidak dikembalikan
+pub async fn get_decoded_statistics_batch(
+    pool: &PgPool,
+    flight_ids: &[i32],
+) -> Result<Vec<DecodedStatistics>, AppError> {
+    let rows: Vec<(i32, String, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT f.id, f.flight_number,
+               COUNT(db.id) AS total_decoded,
+               COUNT(db.id) FILTER (WHERE db.infant_status) AS infant_count
+        FROM flights f
+        LEFT JOIN scan_data sd ON sd.flight_id = f.id
+        LEFT JOIN decode_barcode db ON db.scan_data_id = sd.id
+        WHERE f.id = ANY($1)
+        GROUP BY f.id, f.flight_number
+        ORDER BY f.id
+        "#,
+    )
+    .bind(flight_ids)
+    .fetch_all(pool)
+    .await?;
+
+    // Distribusi cabin class untuk semua flight sekaligus, lalu dibagi per flight
+    let class_rows: Vec<(i32, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT sd.flight_id, db.cabin_class, COUNT(*) AS count
+        FROM decode_barcode db
+        JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE sd.flight_id = ANY($1)
+        GROUP BY sd.flight_id, db.cabin_class
+        ORDER BY sd.flight_id, count DESC, db.cabin_class
+        "#,
+    )
+    .bind(flight_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(flight_id, flight_number, total_decoded, infant_count)| DecodedStatistics {
+            flight_id,
+            flight_number,
+            total_decoded,
+            infant_count,
+            adult_count: total_decoded - infant_count,
+            class_breakdown: class_rows
+                .iter()
+                .filter(|(id, _, _)| *id == flight_id)
+                .map(|(_, cabin_class, count)| CabinClassCount { cabin_class: cabin_class.clone(), count: *count })
+                .collect(),
+        })
+        .collect())
+}
+
+// Fungsi untuk statistik decode per airline dalam satu query GROUP BY atas decode_barcode
+// Decode dianggap tidak valid jika julian date bukan 001-366 atau cabin class kosong
+pub async fn get_decoded_statistics_by_airline(
+    pool: &PgPool,
+    query: &DecodedStatisticsByAirlineQuery,
+) -> Result<Vec<AirlineDecodeStatistics>, AppError> {
+    let mut query_builder = sqlx::QueryBuilder::new(
+        r#"
+        SELECT airline_code,
+               COUNT(*) AS total_decoded,
+               COUNT(*) FILTER (WHERE is_valid) AS valid_count,
+               COUNT(*) FILTER (WHERE NOT is_valid) AS invalid_count
+        FROM (
+            SELECT airline_code,
+                   CASE WHEN flight_date_julian ~ '^[0-9]{3}$'
+                        THEN flight_date_julian::int BETWEEN 1 AND 366
+                        ELSE false
+                   END AND btrim(cabin_class) <> '' AS is_valid
+            FROM decode_barcode
+            WHERE 1=1
+        "#,
+    );
+    if let Some(from) = query.from {
+        query_builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        query_builder.push(" AND created_at <= ").push_bind(to);
+    }
+    query_builder.push(") decodes GROUP BY airline_code ORDER BY total_decoded DESC, airline_code");
+
+    let stats = query_builder
+        .build_query_as::<AirlineDecodeStatistics>()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(stats)
+}
+
+// Fungsi untuk statistik scan per penerbangan dari satu device pada tanggal scan (UTC)
+// Satu query GROUP BY; penerbangan tanpa scan dari device tersebut tidak ikut
+pub async fn get_device_flight_statistics(
+    pool: &PgPool,
+    device_id: &str,
+    date: NaiveDate,
+) -> Result<Vec<DeviceFlightStatistics>, AppError> {
+    let stats = sqlx::query_as::<_, DeviceFlightStatistics>(
+        r#"
+        SELECT f.id AS flight_id,
+               f.flight_number,
+               f.departure_time,
+               COUNT(*) AS total_scans,
+               MIN(s.scan_time) AS first_scan,
+               MAX(s.scan_time) AS last_scan
+        FROM scan_data s
+        JOIN flights f ON f.id = s.flight_id
+        WHERE s.device_id = $1
+          AND (s.scan_time AT TIME ZONE 'utc')::date = $2
+        GROUP BY f.id, f.flight_number, f.departure_time
+        ORDER BY f.departure_time, f.flight_number
+        "#,
+    )
+    .bind(device_id)
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+// Fungsi untuk mencari penerbangan aktif pada tanggal keberangkatan (UTC) yang belum pernah di-scan oleh device
+// (anti-join flights vs scan_data milik device), untuk melihat celah cakupan gate
+pub async fn get_unscanned_flights_for_device(
+    pool: &PgPool,
+    device_id: &str,
+    date: NaiveDate,
+) -> Result<Vec<Flight>, AppError> {
+    let flights = sqlx::query_as::<_, Flight>(
+        r#"
+        SELECT f.id, f.flight_number, f.airline, f.aircraft, f.departure_time, f.destination, f.gate,
+               f.is_active, f.created_at, f.updated_at, f.device_id, f.capacity
+        FROM flights f
+        WHERE f.is_active = true
+          AND (f.departure_time AT TIME ZONE 'utc')::date = $2
+          AND NOT EXISTS (
+              SELECT 1 FROM scan_data s
+              WHERE s.flight_id = f.id AND s.device_id = $1
+          )
+        ORDER BY f.departure_time, f.flight_number
+        "#,
+    )
+    .bind(device_id)
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(flights)
+}
+
 // Fungsi untuk membuat data scan baru
+// max_skew_seconds: toleransi selisih jam device vs server untuk scanned_at yang sedikit di masa depan
 pub async fn create_scan_data(
     pool: &PgPool,
     scan: ScanDataInput,
+    max_skew_seconds: i64,
 ) -> Result<ScanData, AppError> {
+    check_new_scan(pool, &scan, max_skew_seconds).await?;
+    insert_scan_data(pool, &scan).await
+}
+
+// Fungsi untuk menyimpan scan lalu langsung decode dalam satu transaksi (POST /api/scan-data?decode=true)
+// Barcode yang gagal di-parse tetap disimpan sebagai scan dan dicatat otomatis di rejection_logs
+pub async fn create_scan_data_with_decode(
+    pool: &PgPool,
+    scan: ScanDataInput,
+    max_barcode_length: usize,
+    max_skew_seconds: i64,
+    cabin_class_fallback: Option<&str>,
+) -> Result<ScanCreated, AppError> {
+    check_new_scan(pool, &scan, max_skew_seconds).await?;
+
+    let mut tx = pool.begin().await?;
+    let new_scan = insert_scan_data(&mut *tx, &scan).await?;
+
+    let request = DecodeRequest {
+        barcode_value: scan.barcode_value.clone(),
+        scan_data_id: Some(new_scan.id),
+        name_format: NameFormat::default(),
+        barcode_format: None,
+    };
+    let decoded = decode_barcode_iata_on(&mut tx, request, max_barcode_length, cabin_class_fallback).await;
+    let (decoded, rejection) = match decoded {
+        Ok(decoded) => (Some(decoded), None),
+        Err(e @ (AppError::InvalidBarcodeFormat | AppError::InvalidBarcodeEncoding(_))) => {
+            let reason = match e {
+                AppError::InvalidBarcodeEncoding(_) => "DECODE_FAILED_ENCODING",
+                _ => "DECODE_FAILED",
+            };
+            tracing::warn!(
+                scan_id = new_scan.id,
+                flight_id = scan.flight_id,
+                reason = reason,
+                "Scan stored but barcode could not be decoded"
+            );
+            let flight = get_flight_by_id(&mut *tx, scan.flight_id).await?;
+            let log = CreateRejectionLog {
+                barcode_value: scan.barcode_value.clone(),
+                barcode_format: scan.barcode_format.clone(),
+                reason: reason.to_string(),
+                expected_date: None,
+                actual_date: None,
+                flight_number: Some(flight.flight_number),
+                // flights.airline berisi nama maskapai, kolom ini menyimpan kode maskapai
+                airline: None,
+                device_id: Some(scan.device_id.clone()),
+            };
+            (None, Some(create_rejection_log(&mut *tx, log).await?))
+        }
+        Err(e) => return Err(e),
+    };
+
+    tx.commit().await?;
+
+    Ok(ScanCreated {
+        scan: new_scan,
+        decoded,
+        rejection,
+    })
+}
+
+// Validasi sebelum scan disimpan: waktu scan, flight aktif, allowlist device, dan duplikat
+async fn check_new_scan(pool: &PgPool, scan: &ScanDataInput, max_skew_seconds: i64) -> Result<(), AppError> {
+    // scanned_at dari device boleh di masa lalu (upload offline), tapi tidak di masa depan
+    if let Some(scanned_at) = scan.scanned_at
+        && scanned_at > Utc::now() + chrono::Duration::seconds(max_skew_seconds)
+    {
+        tracing::warn!(
+            scanned_at = %scanned_at,
+            device_id = %scan.device_id,
+            "Rejected scan with future scanned_at"
+        );
+        return Err(AppError::InvalidScanTime);
+    }
+
     // Pastikan flight_id valid
     let _ = get_flight_by_id(pool, scan.flight_id).await?;
 
+    // Jika flight punya allowlist, hanya device yang terdaftar boleh scan
+    let allowed_devices = get_flight_allowed_devices(pool, scan.flight_id).await?;
+    if !allowed_devices.is_empty() && !allowed_devices.contains(&scan.device_id) {
+        return Err(AppError::DeviceNotAllowed {
+            device_id: scan.device_id.clone(),
+            flight_id: scan.flight_id,
+        });
+    }
+
     // Check for duplicate scan (same barcode + same flight)
     let existing_scan = sqlx::query_as!(
         ScanData,
@@ -321,68 +860,353 @@ pub async fn create_scan_data(
         });
     }
 
-    // No duplicate, proceed with insert
+    Ok(())
+}
+
+// Insert scan yang sudah lolos check_new_scan (pool atau transaksi)
+async fn insert_scan_data(
+    executor: impl sqlx::PgExecutor<'_>,
+    scan: &ScanDataInput,
+) -> Result<ScanData, AppError> {
     let new_scan = sqlx::query_as!(
         ScanData,
         r#"
-        INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time)
+        VALUES ($1, $2, $3, $4, COALESCE($5, NOW()))
         RETURNING id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at
         "#,
         scan.barcode_value,
         scan.barcode_format,
         scan.device_id,
         scan.flight_id,
+        scan.scanned_at,
     )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
     Ok(new_scan)
 }
 
-// Fungsi untuk mengambil data scan dengan filter
-pub async fn get_scan_data(
-    pool: &PgPool,
-    query: GetScanDataQuery,
-) -> Result<(Vec<ScanData>, i64), AppError> {
-    let mut query_builder = sqlx::QueryBuilder::new("SELECT id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at FROM scan_data WHERE 1=1 ");
-    let mut count_builder = sqlx::QueryBuilder::new("SELECT COUNT(*) FROM scan_data WHERE 1=1 ");
+// Fungsi untuk mengambil allowlist device sebuah penerbangan (kosong = semua device diizinkan)
+pub async fn get_flight_allowed_devices(pool: &PgPool, flight_id: i32) -> Result<Vec<String>, AppError> {
+    let devices = sqlx::query_scalar(
+        "SELECT device_id FROM flight_allowed_devices WHERE flight_id = $1 ORDER BY device_id",
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
 
-    if let Some(flight_id) = query.flight_id {
-        query_builder.push(" AND flight_id = ").push_bind(flight_id);
-        count_builder.push(" AND flight_id = ").push_bind(flight_id);
-    }
+    Ok(devices)
+}
 
-    if let Some(date_range) = query.date_range {
-        let parts: Vec<&str> = date_range.split(',').collect();
-        if parts.len() == 2
-            && let (Ok(start), Ok(end)) = (parts[0].parse::<DateTime<Utc>>(), parts[1].parse::<DateTime<Utc>>())
-        {
-            query_builder.push(" AND scan_time BETWEEN ").push_bind(start).push(" AND ").push_bind(end);
-            count_builder.push(" AND scan_time BETWEEN ").push_bind(start).push(" AND ").push_bind(end);
-        }
-    }
+// Fungsi untuk menambahkan device ke allowlist penerbangan (idempotent)
+pub async fn add_flight_allowed_devices(
+    pool: &PgPool,
+    flight_id: i32,
+    device_ids: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let _ = get_flight_by_id(pool, flight_id).await?;
 
-    let scans = query_builder.build_query_as::<ScanData>().fetch_all(pool).await?;
-    let total: (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO flight_allowed_devices (flight_id, device_id)
+        SELECT $1, UNNEST($2::varchar[])
+        ON CONFLICT (flight_id, device_id) DO NOTHING
+        "#,
+    )
+    .bind(flight_id)
+    .bind(&device_ids)
+    .execute(pool)
+    .await?;
 
-    Ok((scans, total.0))
+    get_flight_allowed_devices(pool, flight_id).await
 }
 
-
-// Fungsi untuk mengambil penerbangan sejak timestamp terakhir
-pub async fn get_flights_since(
+// Fungsi untuk menghapus satu device dari allowlist penerbangan
+pub async fn remove_flight_allowed_device(
     pool: &PgPool,
-    last_sync: Option<DateTime<Utc>>,
-) -> Result<Vec<Flight>, AppError> {
-    let flights = match last_sync {
-        Some(ts) => {
-            sqlx::query_as!(Flight, "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id FROM flights WHERE updated_at > $1 OR created_at > $1 ORDER BY updated_at", ts)
-                .fetch_all(pool)
-                .await?
-        }
-        None => {
-            sqlx::query_as!(Flight, "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id FROM flights ORDER BY created_at")
-                .fetch_all(pool)
+    flight_id: i32,
+    device_id: &str,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "DELETE FROM flight_allowed_devices WHERE flight_id = $1 AND device_id = $2",
+    )
+    .bind(flight_id)
+    .bind(device_id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!(
+            "Device '{}' is not on the allowlist of flight {}",
+            device_id, flight_id
+        )));
+    }
+
+    Ok(())
+}
+
+// Fungsi untuk mengambil data scan dengan filter; jika limit diisi, ambil scan terbaru lebih dulu
+pub async fn get_scan_data(
+    pool: &PgPool,
+    query: &GetScanDataQuery,
+    pagination: Option<Pagination>,
+) -> Result<Vec<ScanData>, AppError> {
+    let mut query_builder = sqlx::QueryBuilder::new("SELECT id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at FROM scan_data WHERE 1=1 ");
+    push_scan_data_filters(&mut query_builder, query);
+
+    if let Some(Pagination { limit, offset }) = pagination {
+        query_builder.push(" ORDER BY scan_time DESC, id DESC LIMIT ").push_bind(limit);
+        query_builder.push(" OFFSET ").push_bind(offset);
+    }
+
+    let scans = query_builder.build_query_as::<ScanData>().fetch_all(pool).await?;
+    Ok(scans)
+}
+
+// Fungsi untuk semua scan dari satu barcode (exact match) di semua penerbangan, terbaru dulu
+pub async fn get_scan_data_by_barcode(
+    pool: &PgPool,
+    barcode_value: &str,
+) -> Result<Vec<ScanData>, AppError> {
+    let scans = sqlx::query_as::<_, ScanData>(
+        "SELECT id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at
+         FROM scan_data
+         WHERE barcode_value = $1
+         ORDER BY scan_time DESC, id DESC",
+    )
+    .bind(barcode_value)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(scans)
+}
+
+// Fungsi untuk parse ulang barcode scan tersimpan dan melaporkan detail parser (tanpa menyimpan hasil)
+pub async fn get_decode_diagnostics(
+    pool: &PgPool,
+    scan_data_id: i32,
+) -> Result<DecodeDiagnostics, AppError> {
+    let scan = sqlx::query_as::<_, ScanData>(
+        "SELECT id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at
+         FROM scan_data
+         WHERE id = $1",
+    )
+    .bind(scan_data_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound(format!("Scan data with id {} not found", scan_data_id)))?;
+
+    let diagnostics = barcode_parser::diagnose_iata_bcbp(&scan.barcode_value);
+
+    Ok(DecodeDiagnostics {
+        scan_data_id: scan.id,
+        normalized_length: diagnostics.normalized.chars().count(),
+        normalized: diagnostics.normalized,
+        strategy: diagnostics.strategy.map(|s| s.as_str().to_string()),
+        tokens: diagnostics.tokens,
+        fields: diagnostics.data.map(Into::into),
+        barcode_value: scan.barcode_value,
+        barcode_format: scan.barcode_format,
+    })
+}
+
+// Fungsi untuk mengambil scan terbaru per barcode pada satu penerbangan (re-tap diabaikan),
+// untuk rekonsiliasi manifest
+pub async fn get_latest_scans_for_flight(
+    pool: &PgPool,
+    flight_id: i32,
+) -> Result<Vec<ScanData>, AppError> {
+    get_flight_by_id(pool, flight_id).await?;
+
+    let scans = sqlx::query_as::<_, ScanData>(
+        r#"
+        SELECT id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at
+        FROM (
+            SELECT DISTINCT ON (barcode_value)
+                   id, barcode_value, barcode_format, scan_time, device_id, flight_id, created_at
+            FROM scan_data
+            WHERE flight_id = $1
+            ORDER BY barcode_value, scan_time DESC, id DESC
+        ) latest
+        ORDER BY scan_time DESC, id DESC
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(scans)
+}
+
+// Fungsi untuk menghitung data scan dengan filter yang sama tanpa mengambil baris
+pub async fn count_scan_data(pool: &PgPool, query: &GetScanDataQuery) -> Result<i64, AppError> {
+    let mut count_builder = sqlx::QueryBuilder::new("SELECT COUNT(*) FROM scan_data WHERE 1=1 ");
+    push_scan_data_filters(&mut count_builder, query);
+
+    let total: (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+    Ok(total.0)
+}
+
+// Filter bersama untuk query list dan count scan_data
+fn push_scan_data_filters(builder: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>, query: &GetScanDataQuery) {
+    if let Some(flight_id) = query.flight_id {
+        builder.push(" AND flight_id = ").push_bind(flight_id);
+    }
+
+    if let Some(date_range) = &query.date_range {
+        let parts: Vec<&str> = date_range.split(',').collect();
+        if parts.len() == 2
+            && let (Ok(start), Ok(end)) = (parts[0].parse::<DateTime<Utc>>(), parts[1].parse::<DateTime<Utc>>())
+        {
+            builder.push(" AND scan_time BETWEEN ").push_bind(start).push(" AND ").push_bind(end);
+        }
+    }
+}
+
+
+// Fungsi untuk mengambil flight beserta statistik scan dan decode secara paralel
+pub async fn get_flight_detail(pool: &PgPool, flight_id: i32) -> Result<FlightDetail, AppError> {
+    // Flight dibaca sekali: not-found ditangani di sini dan hasilnya dipakai kedua statistik
+    let flight = get_flight_by_id(pool, flight_id).await?;
+
+    let (statistics, decoded_statistics) = tokio::try_join!(
+        flight_statistics_for(pool, &flight, false),
+        decoded_statistics_for(pool, &flight),
+    )?;
+
+    Ok(FlightDetail {
+        flight,
+        statistics,
+        decoded_statistics,
+    })
+}
+
+// Fungsi untuk angka utama dashboard pada satu tanggal (UTC): flight aktif (tanggal keberangkatan),
+// scan, rejection, dan device yang melakukan scan; keempat query dijalankan paralel
+pub async fn get_dashboard_summary(
+    pool: &PgPool,
+    date: NaiveDate,
+) -> Result<DashboardSummary, AppError> {
+    let count = |sql: &'static str| async move {
+        sqlx::query_scalar::<_, i64>(sql)
+            .bind(date)
+            .fetch_one(pool)
+            .await
+    };
+
+    let (active_flights, total_scans, total_rejections, active_devices) = tokio::try_join!(
+        count("SELECT COUNT(*) FROM flights WHERE is_active = true AND (departure_time AT TIME ZONE 'utc')::date = $1"),
+        count("SELECT COUNT(*) FROM scan_data WHERE (scan_time AT TIME ZONE 'utc')::date = $1"),
+        count("SELECT COUNT(*) FROM rejection_logs WHERE (rejected_at AT TIME ZONE 'utc')::date = $1"),
+        count("SELECT COUNT(DISTINCT device_id) FROM scan_data WHERE (scan_time AT TIME ZONE 'utc')::date = $1"),
+    )?;
+
+    Ok(DashboardSummary {
+        date,
+        active_flights,
+        total_scans,
+        total_rejections,
+        active_devices,
+    })
+}
+
+// Fungsi untuk menghitung progress boarding: penumpang unik yang sudah scan vs kapasitas
+pub async fn get_boarding_progress(
+    pool: &PgPool,
+    flight_id: i32,
+) -> Result<BoardingProgress, AppError> {
+    let flight = get_flight_by_id(pool, flight_id).await?;
+
+    let boarded: i64 = sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT barcode_value) FROM scan_data WHERE flight_id = $1",
+    )
+    .bind(flight_id)
+    .fetch_one(pool)
+    .await?;
+
+    // Tanpa kapasitas, persentase tidak bisa dihitung
+    let percentage = flight
+        .capacity
+        .map(|capacity| (boarded as f64 / capacity as f64 * 10000.0).round() / 100.0);
+
+    Ok(BoardingProgress {
+        flight_id,
+        flight_number: flight.flight_number,
+        boarded,
+        expected: flight.capacity,
+        percentage,
+    })
+}
+
+// Fungsi untuk menghitung scan per kategori waktu terhadap departure_time penerbangan
+// Batas on_time inklusif: tepat 60 menit sebelum dan tepat saat keberangkatan masih on_time
+pub async fn get_boarding_timing(pool: &PgPool, flight_id: i32) -> Result<BoardingTiming, AppError> {
+    let _ = get_flight_by_id(pool, flight_id).await?;
+
+    let timing = sqlx::query_as::<_, BoardingTiming>(
+        r#"
+        SELECT f.id AS flight_id,
+               COUNT(sd.id) FILTER (WHERE sd.scan_time < f.departure_time - INTERVAL '60 minutes') AS early,
+               COUNT(sd.id) FILTER (WHERE sd.scan_time BETWEEN f.departure_time - INTERVAL '60 minutes'
+                                                           AND f.departure_time) AS on_time,
+               COUNT(sd.id) FILTER (WHERE sd.scan_time > f.departure_time) AS late
+        FROM flights f
+        LEFT JOIN scan_data sd ON sd.flight_id = f.id
+        WHERE f.id = $1
+        GROUP BY f.id
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(timing)
+}
+
+// Fungsi untuk mengambil scan penerbangan yang belum memiliki hasil decode
+// (decode gagal atau belum pernah dicoba), agar bisa diinvestigasi/di-decode ulang
+pub async fn get_undecoded_scans(pool: &PgPool, flight_id: i32) -> Result<Vec<ScanData>, AppError> {
+    let _ = get_flight_by_id(pool, flight_id).await?;
+
+    let scans = sqlx::query_as::<_, ScanData>(
+        r#"
+        SELECT sd.id, sd.barcode_value, sd.barcode_format, sd.scan_time, sd.device_id, sd.flight_id, sd.created_at
+        FROM scan_data sd
+        LEFT JOIN decode_barcode db ON db.scan_data_id = sd.id
+        WHERE sd.flight_id = $1 AND db.id IS NULL
+        ORDER BY sd.scan_time
+        "#,
+    )
+    .bind(flight_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(scans)
+}
+
+// Fungsi untuk mengambil penerbangan sejak timestamp terakhir
+// updated_at NULL untuk flight yang belum pernah di-update, jadi filter dan urutan memakai
+// COALESCE(updated_at, created_at) - posisi NULL di ORDER BY tidak bisa diandalkan.
+// Tanpa include_inactive, flight yang di-soft delete tidak dikirim sehingga client tidak
+// pernah tahu flight itu dihapus (cache lokal harus dibersihkan dengan cara lain)
+pub async fn get_flights_since(
+    pool: &PgPool,
+    last_sync: Option<DateTime<Utc>>,
+    include_inactive: bool,
+) -> Result<Vec<Flight>, AppError> {
+    let flights = match last_sync {
+        // Response memotong timestamp ke milidetik (format_timestamp), sedangkan kolom menyimpan
+        // mikrodetik: baris yang updatedAt-nya dikirim balik sebagai last_sync tidak boleh muncul lagi.
+        // Setara dengan date_trunc('milliseconds', COALESCE(...)) > date_trunc('milliseconds', $1),
+        // tapi ditulis di sisi parameter agar idx_flights_sync_timestamp tetap terpakai
+        Some(ts) => {
+            sqlx::query_as!(Flight, "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE COALESCE(updated_at, created_at) >= date_trunc('milliseconds', $1::timestamptz) + INTERVAL '1 millisecond' AND ($2 OR is_active) ORDER BY COALESCE(updated_at, created_at), id", ts, include_inactive)
+                .fetch_all(pool)
+                .await?
+        }
+        None => {
+            sqlx::query_as!(Flight, "SELECT id, flight_number, airline, aircraft, departure_time, destination, gate, is_active, created_at, updated_at, device_id, capacity FROM flights WHERE ($1 OR is_active) ORDER BY COALESCE(updated_at, created_at), id", include_inactive)
+                .fetch_all(pool)
                 .await?
         }
     };
@@ -390,32 +1214,55 @@ pub async fn get_flights_since(
 }
 
 // Fungsi untuk bulk insert flights (TELAH DIPERBAIKI)
+// Pada mode dry-run, upsert tetap dijalankan di dalam transaksi agar ringkasan akurat,
+// lalu transaksi di-rollback sehingga tidak ada perubahan yang tersimpan.
+// on_conflict menentukan nasib flight yang sudah ada (flight_number + tanggal keberangkatan UTC):
+// upsert menimpanya, skip_existing membiarkannya, fail_on_conflict membatalkan seluruh batch.
+// Semua bentrokan dilaporkan di summary.conflicts (atau di error BulkConflict).
 pub async fn bulk_insert_flights(
     pool: &PgPool,
     flights: Vec<CreateFlight>,
-) -> Result<usize, AppError> {
+    dry_run: bool,
+    on_conflict: BulkConflictMode,
+) -> Result<BulkSyncSummary, AppError> {
     let mut tx = pool.begin().await?;
-    let mut total_affected: u64 = 0;
+    let mut summary = BulkSyncSummary {
+        dry_run,
+        ..Default::default()
+    };
 
     for flight in flights {
         if flight.departure_time < Utc::now() {
             // Kita bisa skip atau return error, di sini kita skip
+            summary.skipped += 1;
             continue;
         }
 
-        let result = sqlx::query(
-            r#"
-            INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate, device_id)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            ON CONFLICT (flight_number, ((departure_time AT TIME ZONE 'utc')::date)) DO UPDATE SET
+        let on_conflict_clause = match on_conflict {
+            BulkConflictMode::Upsert => {
+                r#"DO UPDATE SET
                 airline = EXCLUDED.airline,
                 aircraft = EXCLUDED.aircraft,
                 departure_time = EXCLUDED.departure_time,
                 destination = EXCLUDED.destination,
                 gate = EXCLUDED.gate,
-                updated_at = NOW()
-            "#
-        )
+                capacity = COALESCE(EXCLUDED.capacity, flights.capacity),
+                updated_at = NOW()"#
+            }
+            BulkConflictMode::SkipExisting | BulkConflictMode::FailOnConflict => "DO NOTHING",
+        };
+
+        // xmax = 0 berarti baris baru di-insert, selain itu baris lama di-update;
+        // DO NOTHING tidak mengembalikan baris saat bentrok
+        let result: Option<(i32, bool)> = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate, device_id, capacity)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (flight_number, ((departure_time AT TIME ZONE 'utc')::date)) {}
+            RETURNING id, (xmax = 0)
+            "#,
+            on_conflict_clause
+        ))
             .bind(&flight.flight_number)
             .bind(&flight.airline)
             .bind(&flight.aircraft)
@@ -423,38 +1270,143 @@ pub async fn bulk_insert_flights(
             .bind(&flight.destination)
             .bind(&flight.gate)
             .bind(&flight.device_id)
-            .execute(&mut *tx)
+            .bind(flight.capacity)
+            .fetch_optional(&mut *tx)
             .await?;
 
-        total_affected += result.rows_affected();
+        match result {
+            Some((_, true)) => summary.inserted += 1,
+            Some((id, false)) => {
+                summary.updated += 1;
+                summary.conflicts.push(BulkConflict {
+                    flight_number: flight.flight_number,
+                    departure_time: flight.departure_time,
+                    existing_flight_id: id,
+                });
+            }
+            None => {
+                let existing_flight_id: i32 = sqlx::query_scalar(
+                    r#"
+                    SELECT id FROM flights
+                    WHERE flight_number = $1
+                      AND (departure_time AT TIME ZONE 'utc')::date = ($2::timestamptz AT TIME ZONE 'utc')::date
+                    "#,
+                )
+                    .bind(&flight.flight_number)
+                    .bind(flight.departure_time)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                summary.skipped += 1;
+                summary.conflicts.push(BulkConflict {
+                    flight_number: flight.flight_number,
+                    departure_time: flight.departure_time,
+                    existing_flight_id,
+                });
+            }
+        }
     }
 
-    tx.commit().await?;
+    if on_conflict == BulkConflictMode::FailOnConflict && !summary.conflicts.is_empty() {
+        tx.rollback().await?;
+        return Err(AppError::BulkConflict(summary.conflicts));
+    }
 
-    Ok(total_affected as usize)
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(summary)
 }
 
 // Barcode decoder functions
 
+// Kelas kabin pengganti (CABIN_CLASS_FALLBACK) hanya dipakai jika byte kelas di barcode
+// kosong/tidak valid; tanpa pengganti, kelas disimpan kosong daripada diasumsikan ekonomi
+fn resolve_cabin_class(parsed: String, fallback: Option<&str>) -> String {
+    match fallback {
+        Some(fallback) if parsed.is_empty() => fallback.to_string(),
+        _ => parsed,
+    }
+}
+
 // Fungsi untuk decode barcode IATA format
 // Uses shared parser module synchronized with mobile app
+// PDF417 (kertas) dan AZTEC (mobile) membawa payload BCBP yang sama, jadi keduanya memakai
+// parse_iata_bcbp; simbologinya hanya dicatat di source_format
+// Decode ulang untuk scan_data_id yang sama meng-update baris yang sudah ada
+// Barcode lebih panjang dari max_barcode_length (MAX_BARCODE_LENGTH) ditolak sebelum diparse
+// cabin_class_fallback (CABIN_CLASS_FALLBACK) mengisi kelas kabin yang kosong/tidak valid
 pub async fn decode_barcode_iata(
     pool: &PgPool,
     request: DecodeRequest,
+    max_barcode_length: usize,
+    cabin_class_fallback: Option<&str>,
 ) -> Result<DecodedBarcode, AppError> {
+    let mut conn = pool.acquire().await?;
+    decode_barcode_iata_on(&mut conn, request, max_barcode_length, cabin_class_fallback).await
+}
+
+// Versi decode di atas koneksi/transaksi yang sudah ada (dipakai create_scan_data_with_decode)
+async fn decode_barcode_iata_on(
+    conn: &mut sqlx::PgConnection,
+    request: DecodeRequest,
+    max_barcode_length: usize,
+    cabin_class_fallback: Option<&str>,
+) -> Result<DecodedBarcode, AppError> {
+    // Dispatcher: format yang dideklarasikan harus punya decoder; tanpa format tetap diparse sebagai BCBP
+    if let Some(format) = request.barcode_format.as_deref()
+        && !barcode_parser::has_decoder(format)
+    {
+        return Err(AppError::UnsupportedBarcodeFormat(format.to_string()));
+    }
+
+    // Input kelewat panjang ditolak sebelum pengecekan encoding/normalisasi
+    if barcode_parser::exceeds_max_length(&request.barcode_value, max_barcode_length) {
+        return Err(AppError::InvalidBarcodeFormat);
+    }
+
+    // Byte non-UTF-8 dari scanner akan terbuang saat normalisasi dan menggeser field
+    barcode_parser::check_encoding(&request.barcode_value)
+        .map_err(AppError::InvalidBarcodeEncoding)?;
+
     // Use shared parser (synchronized with mobile app)
     let parsed = barcode_parser::parse_iata_bcbp(&request.barcode_value)
         .ok_or(AppError::InvalidBarcodeFormat)?;
 
     // Extract data from parsed result
-    let passenger_name = parsed.passenger_name;
+    let passenger_name =
+        barcode_parser::apply_name_format(&parsed.passenger_name_raw, request.name_format.into());
     let booking_code = parsed.booking_code;
     let origin = parsed.origin;
     let destination = parsed.destination;
     let airline_code = parsed.airline_code;
     let flight_number = parsed.flight_number.parse::<i32>().unwrap_or(0);
+    let flight_number_raw = parsed.flight_number;
     let flight_date_julian = parsed.flight_date_julian;
-    let cabin_class = parsed.cabin_class;
+    // Tahun diambil dari waktu scan yang di-link (decode ulang bisa terjadi jauh setelah scan);
+    // tanpa scan_data_id, decode dianggap terjadi saat scan sehingga dipakai waktu sekarang
+    let reference = match request.scan_data_id {
+        Some(scan_data_id) => {
+            sqlx::query_scalar!("SELECT scan_time FROM scan_data WHERE id = $1", scan_data_id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => None,
+    };
+    let flight_date = barcode_parser::julian_to_gregorian(
+        &flight_date_julian,
+        reference.unwrap_or_else(Utc::now),
+    );
+    let cabin_class = resolve_cabin_class(parsed.cabin_class, cabin_class_fallback);
+    if cabin_class.is_empty() {
+        tracing::warn!(
+            scan_data_id = ?request.scan_data_id,
+            booking_code = %logging::redact_pii(&booking_code),
+            "Cabin class byte blank or invalid, stored without class"
+        );
+    }
     let seat_number = parsed.seat_number;
     let sequence_number = parsed.sequence_number;
     let infant_status = parsed.infant_status;
@@ -465,11 +1417,29 @@ pub async fn decode_barcode_iata(
         INSERT INTO decode_barcode
         (barcode_value, passenger_name, booking_code, origin, destination, airline_code,
          flight_number, flight_date_julian, cabin_class, seat_number, sequence_number,
-         infant_status, scan_data_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+         infant_status, scan_data_id, flight_number_raw, source_format, flight_date)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+                COALESCE($15, (SELECT barcode_format FROM scan_data WHERE id = $13)), $16)
+        ON CONFLICT (scan_data_id) WHERE scan_data_id IS NOT NULL DO UPDATE SET
+            barcode_value = EXCLUDED.barcode_value,
+            passenger_name = EXCLUDED.passenger_name,
+            booking_code = EXCLUDED.booking_code,
+            origin = EXCLUDED.origin,
+            destination = EXCLUDED.destination,
+            airline_code = EXCLUDED.airline_code,
+            flight_number = EXCLUDED.flight_number,
+            flight_number_raw = EXCLUDED.flight_number_raw,
+            flight_date_julian = EXCLUDED.flight_date_julian,
+            flight_date = EXCLUDED.flight_date,
+            cabin_class = EXCLUDED.cabin_class,
+            seat_number = EXCLUDED.seat_number,
+            sequence_number = EXCLUDED.sequence_number,
+            infant_status = EXCLUDED.infant_status,
+            source_format = EXCLUDED.source_format
         RETURNING id, barcode_value, passenger_name, booking_code, origin, destination,
-                  airline_code, flight_number, flight_date_julian, cabin_class, seat_number,
-                  sequence_number, infant_status, scan_data_id, created_at
+                  airline_code, flight_number, flight_number_raw, flight_date_julian, flight_date, cabin_class,
+                  (SELECT name FROM cabin_class_codes WHERE code = cabin_class) AS cabin_class_name,
+                  seat_number, sequence_number, infant_status, scan_data_id, source_format, created_at
         "#,
         request.barcode_value,
         passenger_name,
@@ -483,63 +1453,282 @@ pub async fn decode_barcode_iata(
         seat_number,
         sequence_number,
         infant_status,
-        request.scan_data_id
+        request.scan_data_id,
+        flight_number_raw,
+        request.barcode_format,
+        flight_date
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     Ok(decoded)
 }
 
-// Fungsi untuk mengambil semua decoded barcodes dengan filter flight_id optional
-pub async fn get_all_decoded_barcodes(
+// Fungsi untuk koreksi manual hasil decode (infant_status / cabin_class)
+// Statistik decode selalu dihitung ulang dari tabel (tidak di-cache), jadi langsung ikut berubah
+pub async fn update_decoded_barcode(
     pool: &PgPool,
-    flight_id: Option<i32>,
-) -> Result<Vec<DecodedBarcode>, AppError> {
-    let decoded_list = if let Some(fid) = flight_id {
-        // Filter by flight_id via JOIN dengan scan_data
-        sqlx::query_as!(
-            DecodedBarcode,
+    id: i32,
+    update: UpdateDecodedBarcode,
+) -> Result<DecodedBarcode, AppError> {
+    let decoded = sqlx::query_as!(
+        DecodedBarcode,
+        r#"
+        UPDATE decode_barcode
+        SET
+            infant_status = COALESCE($1, infant_status),
+            cabin_class = COALESCE($2, cabin_class)
+        WHERE id = $3
+        RETURNING id, barcode_value, passenger_name, booking_code, origin, destination,
+                  airline_code, flight_number, flight_number_raw, flight_date_julian, flight_date, cabin_class,
+                  (SELECT name FROM cabin_class_codes WHERE code = cabin_class) AS cabin_class_name,
+                  seat_number, sequence_number, infant_status, scan_data_id, source_format, created_at
+        "#,
+        update.infant_status,
+        update.cabin_class,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound(format!("Decoded barcode with id {} not found", id)))?;
+
+    Ok(decoded)
+}
+
+// Fungsi untuk id penerbangan dari scan yang di-link ke hasil decode (None jika decode tanpa scan)
+// Dipakai untuk membuang cache statistik flight tersebut setelah decode berubah
+pub async fn get_decoded_flight_id(pool: &PgPool, decoded_id: i32) -> Result<Option<i32>, AppError> {
+    let flight_id: Option<i32> = sqlx::query_scalar(
+        r#"
+        SELECT sd.flight_id
+        FROM decode_barcode db
+        JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE db.id = $1
+        "#,
+    )
+    .bind(decoded_id)
+    .fetch_optional(pool)
+    .await?
+    .flatten();
+
+    Ok(flight_id)
+}
+
+// Fungsi untuk mengambil penerbangan dari sebuah hasil decode (decode_barcode -> scan_data -> flights)
+// Decode tanpa scan, atau scan tanpa flight, dianggap tidak terhubung (404)
+pub async fn get_decoded_barcode_flight(pool: &PgPool, decoded_id: i32) -> Result<Flight, AppError> {
+    let link: Option<(Option<i32>,)> = sqlx::query_as(
+        r#"
+        SELECT sd.flight_id
+        FROM decode_barcode db
+        LEFT JOIN scan_data sd ON db.scan_data_id = sd.id
+        WHERE db.id = $1
+        "#,
+    )
+    .bind(decoded_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let (flight_id,) =
+        link.ok_or(AppError::NotFound(format!("Decoded barcode with id {} not found", decoded_id)))?;
+    let flight_id = flight_id.ok_or(AppError::NotFound(format!(
+        "Decoded barcode with id {} is not linked to a flight",
+        decoded_id
+    )))?;
+
+    get_flight_by_id(pool, flight_id).await
+}
+
+// Fungsi untuk membandingkan penerbangan menurut barcode dengan flight tempat scan dicatat
+// (mis. penumpang salah gate). 404 sama seperti get_decoded_barcode_flight
+pub async fn get_decoded_flight_mismatch(
+    pool: &PgPool,
+    decoded_id: i32,
+) -> Result<DecodedFlightMismatch, AppError> {
+    let flight = get_decoded_barcode_flight(pool, decoded_id).await?;
+    let (airline_code, flight_number, flight_date_julian, airline_name): (String, i32, String, Option<String>) =
+        sqlx::query_as(
             r#"
-            SELECT db.id, db.barcode_value, db.passenger_name, db.booking_code, db.origin, db.destination,
-                   db.airline_code, db.flight_number, db.flight_date_julian, db.cabin_class, db.seat_number,
-                   db.sequence_number, db.infant_status, db.scan_data_id, db.created_at
+            SELECT db.airline_code, db.flight_number, db.flight_date_julian, ac.name
             FROM decode_barcode db
-            JOIN scan_data sd ON db.scan_data_id = sd.id
-            WHERE sd.flight_id = $1
-            ORDER BY db.created_at DESC
+            LEFT JOIN airline_codes ac ON ac.code = db.airline_code
+            WHERE db.id = $1
             "#,
-            fid
         )
-        .fetch_all(pool)
-        .await?
+        .bind(decoded_id)
+        .fetch_one(pool)
+        .await?;
+
+    let mismatched_fields =
+        decoded_flight_mismatches(&airline_code, flight_number, &flight_date_julian, airline_name.as_deref(), &flight);
+
+    Ok(DecodedFlightMismatch {
+        decoded_barcode_id: decoded_id,
+        flight_id: flight.id,
+        matches: mismatched_fields.is_empty(),
+        mismatched_fields: mismatched_fields.into_iter().map(str::to_string).collect(),
+        decoded_airline_code: airline_code,
+        decoded_flight_number: flight_number,
+        decoded_flight_date_julian: flight_date_julian,
+        flight_number: flight.flight_number,
+        flight_airline: flight.airline,
+        flight_departure_time: flight.departure_time,
+    })
+}
+
+// Nomor penerbangan flight ("GA312", "3K0123") dipisah jadi designator + angka.
+// Tanpa designator di nomor flight, airline dicocokkan lewat kolom airline (kode atau nama di airline_codes).
+// Tanggal dibandingkan sebagai hari ke-N dalam tahun (julian) dari departure_time UTC.
+fn decoded_flight_mismatches(
+    airline_code: &str,
+    flight_number: i32,
+    flight_date_julian: &str,
+    airline_name: Option<&str>,
+    flight: &Flight,
+) -> Vec<&'static str> {
+    use chrono::Datelike;
+
+    let number = flight.flight_number.trim();
+    let digits_start = number.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (designator, digits) = number.split_at(digits_start);
+
+    let mut mismatches = Vec::new();
+    let airline_matches = if designator.is_empty() {
+        flight.airline.eq_ignore_ascii_case(airline_code)
+            || airline_name.is_some_and(|name| flight.airline.eq_ignore_ascii_case(name))
     } else {
-        // Get all decoded barcodes
-        sqlx::query_as!(
-            DecodedBarcode,
-            r#"
-            SELECT id, barcode_value, passenger_name, booking_code, origin, destination,
-                   airline_code, flight_number, flight_date_julian, cabin_class, seat_number,
-                   sequence_number, infant_status, scan_data_id, created_at
-            FROM decode_barcode
-            ORDER BY created_at DESC
-            "#
-        )
-        .fetch_all(pool)
-        .await?
+        designator.eq_ignore_ascii_case(airline_code)
     };
+    if !airline_matches {
+        mismatches.push("airline");
+    }
+    if digits.parse::<i32>().ok() != Some(flight_number) {
+        mismatches.push("flightNumber");
+    }
+    if flight_date_julian.parse::<u32>().ok() != Some(flight.departure_time.ordinal()) {
+        mismatches.push("flightDate");
+    }
+    mismatches
+}
+
+// Fungsi untuk mengambil decoded barcodes dengan filter opsional (flight, airline, rentang created_at)
+pub async fn get_all_decoded_barcodes(
+    pool: &PgPool,
+    query: &GetDecodedBarcodesQuery,
+    pagination: Pagination,
+) -> Result<Vec<DecodedBarcode>, AppError> {
+    let mut query_builder = sqlx::QueryBuilder::new(
+        r#"
+        SELECT db.id, db.barcode_value, db.passenger_name, db.booking_code, db.origin, db.destination,
+               db.airline_code, db.flight_number, db.flight_number_raw, db.flight_date_julian, db.flight_date, db.cabin_class,
+               cc.name AS cabin_class_name, db.seat_number,
+               db.sequence_number, db.infant_status, db.scan_data_id, db.source_format, db.created_at
+        FROM decode_barcode db
+        LEFT JOIN scan_data sd ON db.scan_data_id = sd.id
+        -- Nama kelas kabin di-join sekaligus (tanpa query per baris)
+        LEFT JOIN cabin_class_codes cc ON cc.code = db.cabin_class
+        WHERE 1=1
+        "#,
+    );
+    push_decoded_barcode_filters(&mut query_builder, query);
+    // Urutan stabil (id sebagai tie-breaker) agar pagination tidak melompati baris
+    query_builder.push(" ORDER BY db.created_at DESC, db.id DESC");
+
+    query_builder.push(" LIMIT ").push_bind(pagination.limit);
+    query_builder.push(" OFFSET ").push_bind(pagination.offset);
+
+    let decoded_list = query_builder
+        .build_query_as::<DecodedBarcode>()
+        .fetch_all(pool)
+        .await?;
 
     Ok(decoded_list)
 }
 
+// Fungsi untuk menghitung decoded barcodes tanpa mengambil baris (count_only)
+pub async fn count_decoded_barcodes(
+    pool: &PgPool,
+    query: &GetDecodedBarcodesQuery,
+) -> Result<i64, AppError> {
+    let mut count_builder = sqlx::QueryBuilder::new(
+        "SELECT COUNT(*) FROM decode_barcode db LEFT JOIN scan_data sd ON db.scan_data_id = sd.id WHERE 1=1 ",
+    );
+    push_decoded_barcode_filters(&mut count_builder, query);
+
+    let total: (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+    Ok(total.0)
+}
+
+// Filter bersama untuk query list dan count decode_barcode
+fn push_decoded_barcode_filters(
+    builder: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    query: &GetDecodedBarcodesQuery,
+) {
+    if let Some(flight_id) = query.flight_id {
+        builder.push(" AND sd.flight_id = ").push_bind(flight_id);
+    }
+    if let Some(airline_code) = &query.airline_code {
+        builder.push(" AND db.airline_code = ").push_bind(airline_code.to_uppercase());
+    }
+    if let Some(from) = query.from {
+        builder.push(" AND db.created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        builder.push(" AND db.created_at <= ").push_bind(to);
+    }
+}
+
 // NOTE: All parsing logic has been moved to shared barcode_parser module
 // This ensures 100% synchronization between mobile app and server
 
+/// Delete all operational data (decoded barcodes, scans, flights, optionally rejection logs)
+///
+/// Dipakai untuk membersihkan data seed/test sebelum go-live. Semua DELETE berjalan dalam
+/// satu transaksi; users, roles, dan tabel kode tidak disentuh.
+pub async fn purge_operational_data(
+    pool: &PgPool,
+    include_rejection_logs: bool,
+) -> Result<PurgeOperationalDataSummary, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // Urutan mengikuti foreign key: decode_barcode -> scan_data -> flights
+    // (flight_allowed_devices ikut terhapus lewat ON DELETE CASCADE)
+    let deleted_decoded_barcodes = sqlx::query!("DELETE FROM decode_barcode")
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let deleted_scans = sqlx::query!("DELETE FROM scan_data")
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let deleted_flights = sqlx::query!("DELETE FROM flights")
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let deleted_rejection_logs = if include_rejection_logs {
+        sqlx::query!("DELETE FROM rejection_logs")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+    } else {
+        0
+    };
+
+    tx.commit().await?;
+
+    Ok(PurgeOperationalDataSummary {
+        deleted_flights,
+        deleted_scans,
+        deleted_decoded_barcodes,
+        deleted_rejection_logs,
+    })
+}
+
 // ==================== REJECTION LOGGING FUNCTIONS ====================
 
 /// Create a rejection log entry in server database
 pub async fn create_rejection_log(
-    pool: &PgPool,
+    executor: impl sqlx::PgExecutor<'_>,
     log: CreateRejectionLog,
 ) -> Result<RejectionLog, AppError> {
     let rejection = sqlx::query_as!(
@@ -561,19 +1750,57 @@ pub async fn create_rejection_log(
         log.airline,
         log.device_id
     )
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(rejection)
 }
 
+/// Record a failed decode as an `invalid_format` rejection (AUTO_REJECTION_LOGS)
+/// Device and flight come from the linked scan when `scan_data_id` is set
+pub async fn record_decode_failure(
+    pool: &PgPool,
+    barcode_value: String,
+    barcode_format: Option<String>,
+    scan_data_id: Option<i32>,
+) -> Result<RejectionLog, AppError> {
+    let scan_context = match scan_data_id {
+        Some(id) => sqlx::query_as::<_, (String, String, Option<String>)>(
+            "SELECT s.barcode_format, s.device_id, f.flight_number
+             FROM scan_data s
+             LEFT JOIN flights f ON f.id = s.flight_id
+             WHERE s.id = $1",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?,
+        None => None,
+    };
+    let (scan_format, device_id, flight_number) = match scan_context {
+        Some((format, device_id, flight_number)) => (Some(format), Some(device_id), flight_number),
+        None => (None, None, None),
+    };
+
+    let log = CreateRejectionLog {
+        barcode_value,
+        barcode_format: barcode_format.or(scan_format).unwrap_or_else(|| "UNKNOWN".to_string()),
+        reason: "invalid_format".to_string(),
+        expected_date: None,
+        actual_date: None,
+        flight_number,
+        airline: None,
+        device_id,
+    };
+    create_rejection_log(pool, log).await
+}
+
 /// Get rejection logs with optional filtering
 pub async fn get_rejection_logs(
     pool: &PgPool,
     query: RejectionLogQuery,
+    pagination: Pagination,
 ) -> Result<Vec<RejectionLog>, AppError> {
-    let limit = query.limit.unwrap_or(100);
-    let offset = query.offset.unwrap_or(0);
+    let Pagination { limit, offset } = pagination;
 
     let mut query_builder = String::from(
         "SELECT id, barcode_value, barcode_format, reason, expected_date, actual_date,
@@ -638,6 +1865,25 @@ pub async fn get_rejection_logs(
     Ok(logs)
 }
 
+// Fungsi untuk semua rejection log dari satu barcode (exact match), terbaru dulu
+pub async fn get_rejection_logs_by_barcode(
+    pool: &PgPool,
+    barcode_value: &str,
+) -> Result<Vec<RejectionLog>, AppError> {
+    let logs = sqlx::query_as::<_, RejectionLog>(
+        "SELECT id, barcode_value, barcode_format, reason, expected_date, actual_date,
+                flight_number, airline, device_id, rejected_at
+         FROM rejection_logs
+         WHERE barcode_value = $1
+         ORDER BY rejected_at DESC, id DESC",
+    )
+    .bind(barcode_value)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(logs)
+}
+
 /// Get rejection statistics
 pub async fn get_rejection_stats(
     pool: &PgPool,
@@ -728,6 +1974,133 @@ pub async fn get_cabin_class_codes(
     Ok(codes)
 }
 
+// Naikkan starter_data_version satu kali dan kembalikan versi baru (dipakai di dalam transaksi import)
+async fn bump_starter_data_version(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<i32, AppError> {
+    let version = sqlx::query_scalar(
+        r#"
+        UPDATE starter_data_version
+        SET version = version + 1, updated_at = NOW()
+        WHERE id = (SELECT MAX(id) FROM starter_data_version)
+        RETURNING version
+        "#,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(version)
+}
+
+// Catat hasil upsert (xmax = 0 berarti baris baru) dan tutup transaksi dengan satu kenaikan versi
+async fn finish_code_import(
+    mut tx: sqlx::Transaction<'_, sqlx::Postgres>,
+    results: Vec<bool>,
+) -> Result<CodeImportSummary, AppError> {
+    let inserted = results.iter().filter(|inserted| **inserted).count();
+    let starter_data_version = bump_starter_data_version(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(CodeImportSummary {
+        inserted,
+        updated: results.len() - inserted,
+        starter_data_version,
+    })
+}
+
+/// Bulk upsert airport codes in one transaction, bumping the starter data version once
+pub async fn bulk_upsert_airport_codes(
+    pool: &PgPool,
+    codes: Vec<UpsertAirportCode>,
+) -> Result<CodeImportSummary, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(codes.len());
+
+    for code in codes {
+        let inserted: bool = sqlx::query_scalar(
+            r#"
+            INSERT INTO airport_codes (code, name, city, country)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (code) DO UPDATE SET
+                name = EXCLUDED.name,
+                city = EXCLUDED.city,
+                country = EXCLUDED.country,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(code.code.to_uppercase())
+        .bind(&code.name)
+        .bind(&code.city)
+        .bind(&code.country)
+        .fetch_one(&mut *tx)
+        .await?;
+        results.push(inserted);
+    }
+
+    finish_code_import(tx, results).await
+}
+
+/// Bulk upsert airline codes in one transaction, bumping the starter data version once
+pub async fn bulk_upsert_airline_codes(
+    pool: &PgPool,
+    codes: Vec<UpsertAirlineCode>,
+) -> Result<CodeImportSummary, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(codes.len());
+
+    for code in codes {
+        let inserted: bool = sqlx::query_scalar(
+            r#"
+            INSERT INTO airline_codes (code, name, country)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (code) DO UPDATE SET
+                name = EXCLUDED.name,
+                country = EXCLUDED.country,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(code.code.to_uppercase())
+        .bind(&code.name)
+        .bind(&code.country)
+        .fetch_one(&mut *tx)
+        .await?;
+        results.push(inserted);
+    }
+
+    finish_code_import(tx, results).await
+}
+
+/// Bulk upsert cabin class codes in one transaction, bumping the starter data version once
+pub async fn bulk_upsert_cabin_class_codes(
+    pool: &PgPool,
+    codes: Vec<UpsertCabinClassCode>,
+) -> Result<CodeImportSummary, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(codes.len());
+
+    for code in codes {
+        let inserted: bool = sqlx::query_scalar(
+            r#"
+            INSERT INTO cabin_class_codes (code, name, description)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (code) DO UPDATE SET
+                name = EXCLUDED.name,
+                description = EXCLUDED.description,
+                updated_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(code.code.to_uppercase())
+        .bind(&code.name)
+        .bind(&code.description)
+        .fetch_one(&mut *tx)
+        .await?;
+        results.push(inserted);
+    }
+
+    finish_code_import(tx, results).await
+}
+
 /// Get starter data version
 pub async fn get_starter_data_version(
     pool: &PgPool,
@@ -747,3 +2120,1516 @@ pub async fn get_starter_data_version(
 
     Ok(version)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DEFAULT_MAX_BARCODE_LENGTH, DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS};
+    use crate::models::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+    use chrono::{Duration, SubsecRound};
+    use validator::Validate;
+
+    fn sample_flight(flight_number: &str, departure_time: DateTime<Utc>) -> CreateFlight {
+        CreateFlight {
+            flight_number: flight_number.to_string(),
+            airline: "Garuda Indonesia".to_string(),
+            aircraft: "B738".to_string(),
+            departure_time,
+            scanned_at: departure_time,
+            destination: "SUB".to_string(),
+            gate: "A1".to_string(),
+            device_id: Some("device-1".to_string()),
+            capacity: None,
+        }
+    }
+
+    // Flight aktif standar (berangkat besok) untuk fixture test; mengembalikan id-nya
+    async fn seed_flight(pool: &PgPool, flight_number: &str) -> i32 {
+        let flight = sample_flight(flight_number, Utc::now() + Duration::days(1));
+        bulk_insert_flights(pool, vec![flight], false, BulkConflictMode::Upsert)
+            .await
+            .unwrap();
+        sqlx::query_scalar("SELECT id FROM flights WHERE flight_number = $1")
+            .bind(flight_number)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn count_flights(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM flights")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_sync_dry_run_persists_nothing(pool: PgPool) {
+        let departure = Utc::now() + Duration::days(1);
+        let flights = vec![
+            sample_flight("GA312", departure),
+            sample_flight("GA313", departure),
+            sample_flight("GA314", Utc::now() - Duration::days(1)),
+        ];
+
+        let summary = bulk_insert_flights(&pool, flights, true, BulkConflictMode::Upsert).await.unwrap();
+        assert!(summary.dry_run);
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.updated, 0);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(count_flights(&pool).await, 0);
+
+        // Setelah sync sungguhan, dry-run berikutnya harus melaporkan update
+        bulk_insert_flights(&pool, vec![sample_flight("GA312", departure)], false, BulkConflictMode::Upsert)
+            .await
+            .unwrap();
+        let summary = bulk_insert_flights(&pool, vec![sample_flight("GA312", departure)], true, BulkConflictMode::Upsert)
+            .await
+            .unwrap();
+        assert_eq!(summary.inserted, 0);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(count_flights(&pool).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_insert_conflict_modes(pool: PgPool) {
+        let departure = Utc::now() + Duration::days(1);
+        let existing_id = seed_flight(&pool, "GA320").await;
+        // Koreksi manual yang tidak boleh tertimpa sync
+        sqlx::query("UPDATE flights SET gate = 'Z9' WHERE id = $1")
+            .bind(existing_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let gate = |pool: PgPool| async move {
+            sqlx::query_scalar::<_, String>("SELECT gate FROM flights WHERE flight_number = 'GA320'")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+        let batch = || vec![sample_flight("GA320", departure), sample_flight("GA321", departure)];
+
+        let summary = bulk_insert_flights(&pool, batch(), false, BulkConflictMode::FailOnConflict).await;
+        let Err(AppError::BulkConflict(conflicts)) = summary else {
+            panic!("expected bulk conflict error");
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].existing_flight_id, existing_id);
+        assert_eq!(count_flights(&pool).await, 1);
+
+        let summary = bulk_insert_flights(&pool, batch(), false, BulkConflictMode::SkipExisting).await.unwrap();
+        assert_eq!((summary.inserted, summary.updated, summary.skipped), (1, 0, 1));
+        assert_eq!(summary.conflicts[0].flight_number, "GA320");
+        assert_eq!(summary.conflicts[0].existing_flight_id, existing_id);
+        assert_eq!(gate(pool.clone()).await, "Z9");
+
+        let summary = bulk_insert_flights(&pool, batch(), false, BulkConflictMode::Upsert).await.unwrap();
+        assert_eq!((summary.inserted, summary.updated, summary.skipped), (0, 2, 0));
+        assert_eq!(summary.conflicts.len(), 2);
+        assert_ne!(gate(pool.clone()).await, "Z9");
+        assert_eq!(count_flights(&pool).await, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_duplicates_by_booking_code(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA330").await;
+
+        // Boarding pass yang sama di-tap ulang menghasilkan scan berbeda (barcode berbeda di trailer);
+        // penumpang lain pada PNR yang sama (keluarga) bukan duplikat
+        let barcodes = [
+            "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.",
+            "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300",
+            "M1BAYU/AISYAH MS      ESMMTHQ DHXCGKID 6473 032Y007B0003 300",
+            "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300",
+        ];
+        for barcode in barcodes {
+            insert_scan(&pool, flight_id, barcode).await;
+            let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+                .bind(barcode)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            let request = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: Some(scan_id),
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            };
+            decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        }
+
+        let duplicates = get_decoded_duplicates(&pool, flight_id).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].booking_code, "SMMTHQ");
+        assert_eq!(duplicates[0].sequence_number, "0002");
+        assert_eq!(duplicates[0].occurrences, 2);
+        assert_eq!(duplicates[0].scan_data_ids.len(), 2);
+
+        assert!(matches!(get_decoded_duplicates(&pool, 999_999).await, Err(AppError::FlightNotFound)));
+    }
+
+    async fn insert_scan(pool: &PgPool, flight_id: i32, barcode: &str) {
+        sqlx::query(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id) VALUES ($1, 'PDF417', 'device-1', $2)",
+        )
+        .bind(barcode)
+        .bind(flight_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_boarding_timing_buckets(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA740").await;
+        let departure: DateTime<Utc> = sqlx::query_scalar("SELECT departure_time FROM flights WHERE id = $1")
+            .bind(flight_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        // 2 early, 3 on-time (termasuk kedua batas), 1 late
+        let offsets = [-180, -61, -60, -30, 0, 15];
+        for (i, minutes) in offsets.into_iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ($1, 'PDF417', 'device-1', $2, $3)",
+            )
+            .bind(format!("PAX-{}", i))
+            .bind(flight_id)
+            .bind(departure + Duration::minutes(minutes))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let timing = get_boarding_timing(&pool, flight_id).await.unwrap();
+        assert_eq!((timing.early, timing.on_time, timing.late), (2, 3, 1));
+
+        assert!(matches!(get_boarding_timing(&pool, flight_id + 1).await, Err(AppError::FlightNotFound)));
+    }
+
+    #[sqlx::test]
+    async fn test_boarding_progress_counts_distinct_passengers(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA400").await;
+        let no_capacity_id = seed_flight(&pool, "GA401").await;
+        sqlx::query("UPDATE flights SET capacity = 4 WHERE id = $1")
+            .bind(flight_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        // Unique index pada (barcode_value, flight_id) mencegah scan ganda di level database
+        for barcode in ["PAX-A", "PAX-B", "PAX-C"] {
+            insert_scan(&pool, flight_id, barcode).await;
+        }
+
+        let progress = get_boarding_progress(&pool, flight_id).await.unwrap();
+        assert_eq!(progress.boarded, 3);
+        assert_eq!(progress.expected, Some(4));
+        assert_eq!(progress.percentage, Some(75.0));
+
+        let progress = get_boarding_progress(&pool, no_capacity_id).await.unwrap();
+        assert_eq!(progress.boarded, 0);
+        assert_eq!(progress.expected, None);
+        assert_eq!(progress.percentage, None);
+    }
+
+    #[sqlx::test]
+    async fn test_scans_by_hour_zero_fill(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA500").await;
+
+        for (barcode, scan_time) in [("PAX-A", "2025-01-15 09:10:00+00"), ("PAX-B", "2025-01-15 11:40:00+00")] {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ($1, 'PDF417', 'device-1', $2, $3::timestamptz)",
+            )
+            .bind(barcode)
+            .bind(flight_id)
+            .bind(scan_time)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // Label jam mengikuti zona waktu sesi database
+        let hour_10: String =
+            sqlx::query_scalar("SELECT TO_CHAR(DATE_TRUNC('hour', '2025-01-15 10:00:00+00'::timestamptz), 'HH24:00')")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let sparse = get_flight_statistics(&pool, flight_id, false).await.unwrap();
+        assert_eq!(sparse.scans_by_hour.len(), 2);
+
+        let filled = get_flight_statistics(&pool, flight_id, true).await.unwrap();
+        let series: Vec<(String, i64)> = filled
+            .scans_by_hour
+            .into_iter()
+            .map(|entry| (entry.hour, entry.count))
+            .collect();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].1, 1);
+        assert_eq!(series[1], (hour_10, 0));
+        assert_eq!(series[2].1, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_flight_statistics_consistent_under_concurrent_inserts(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA725").await;
+
+        let writer = {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                for i in 0..200 {
+                    insert_scan(&pool, flight_id, &format!("PAX-{}", i)).await;
+                }
+            })
+        };
+
+        // Best-effort: setiap snapshot harus konsisten walaupun scan terus masuk
+        while !writer.is_finished() {
+            let statistics = get_flight_statistics(&pool, flight_id, false).await.unwrap();
+            assert!(statistics.duplicate_scans >= 0);
+            let by_format: i64 = statistics.format_breakdown.iter().map(|entry| entry.count).sum();
+            assert_eq!(by_format, statistics.total_scans);
+            let by_hour: i64 = statistics.scans_by_hour.iter().map(|entry| entry.count).sum();
+            assert_eq!(by_hour, statistics.total_scans);
+        }
+        writer.await.unwrap();
+
+        let statistics = get_flight_statistics(&pool, flight_id, false).await.unwrap();
+        assert_eq!(statistics.total_scans, 200);
+        assert_eq!(statistics.duplicate_scans, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_flight_statistics_format_breakdown(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA720").await;
+        for (barcode, format) in [("PAX-A", "PDF417"), ("PAX-B", "pdf417"), ("PAX-C", "QR_CODE")] {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id) VALUES ($1, $2, 'device-1', $3)",
+            )
+            .bind(barcode)
+            .bind(format)
+            .bind(flight_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let statistics = get_flight_statistics(&pool, flight_id, false).await.unwrap();
+        let breakdown: Vec<(&str, i64)> = statistics
+            .format_breakdown
+            .iter()
+            .map(|entry| (entry.format.as_str(), entry.count))
+            .collect();
+        assert_eq!(breakdown, vec![("PDF417", 2), ("QR_CODE", 1)]);
+    }
+
+    #[sqlx::test]
+    async fn test_device_flight_statistics(pool: PgPool) {
+        let flight_ids = [seed_flight(&pool, "GA730").await, seed_flight(&pool, "GA731").await, seed_flight(&pool, "GA732").await];
+        let today = Utc::now();
+        let scans = [
+            (flight_ids[0], "PAX-A", "gate-7", today),
+            (flight_ids[0], "PAX-F", "gate-7", today),
+            (flight_ids[0], "PAX-B", "gate-7", today),
+            (flight_ids[1], "PAX-C", "gate-7", today),
+            (flight_ids[1], "PAX-D", "gate-7", today - Duration::days(2)),
+            (flight_ids[2], "PAX-E", "gate-8", today),
+        ];
+        for (flight_id, barcode, device_id, scan_time) in scans {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ($1, 'PDF417', $2, $3, $4)",
+            )
+            .bind(barcode)
+            .bind(device_id)
+            .bind(flight_id)
+            .bind(scan_time)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = get_device_flight_statistics(&pool, "gate-7", today.date_naive()).await.unwrap();
+        let counts: Vec<(&str, i64)> = stats
+            .iter()
+            .map(|s| (s.flight_number.as_str(), s.total_scans))
+            .collect();
+        assert_eq!(counts, vec![("GA730", 3), ("GA731", 1)]);
+
+        // Device lain / device tanpa scan tidak tercampur
+        let other = get_device_flight_statistics(&pool, "gate-9", today.date_naive()).await.unwrap();
+        assert!(other.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_unscanned_flights_for_device(pool: PgPool) {
+        let departure = Utc::now() + Duration::days(1);
+        let flight_ids = [seed_flight(&pool, "GA740").await, seed_flight(&pool, "GA741").await];
+        insert_scan(&pool, flight_ids[0], "PAX-A").await;
+
+        let date = departure.date_naive();
+        let unscanned = get_unscanned_flights_for_device(&pool, "device-1", date).await.unwrap();
+        let numbers: Vec<&str> = unscanned.iter().map(|f| f.flight_number.as_str()).collect();
+        assert_eq!(numbers, vec!["GA741"]);
+
+        // Device tanpa scan sama sekali: semua flight pada tanggal itu
+        assert_eq!(get_unscanned_flights_for_device(&pool, "device-2", date).await.unwrap().len(), 2);
+        assert!(get_unscanned_flights_for_device(&pool, "device-2", date + Duration::days(3)).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_dashboard_summary_counts(pool: PgPool) {
+        let day = Utc::now() + Duration::days(1);
+        let flight_id = seed_flight(&pool, "GA750").await;
+        sqlx::query(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ('PAX-A', 'PDF417', 'gate-7', $1, $2)",
+        )
+        .bind(flight_id)
+        .bind(day)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO rejection_logs (barcode_value, barcode_format, reason, device_id, rejected_at) VALUES ('PAX-B', 'PDF417', 'date_mismatch', 'gate-7', $1)",
+        )
+        .bind(day)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let summary = get_dashboard_summary(&pool, day.date_naive()).await.unwrap();
+        assert_eq!(
+            (summary.active_flights, summary.total_scans, summary.total_rejections, summary.active_devices),
+            (1, 1, 1, 1)
+        );
+
+        let empty = get_dashboard_summary(&pool, day.date_naive() + Duration::days(7)).await.unwrap();
+        assert_eq!(
+            (empty.active_flights, empty.total_scans, empty.total_rejections, empty.active_devices),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_latest_scans_keep_only_most_recent_per_barcode(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA734").await;
+
+        let now = Utc::now();
+        let scans = [
+            ("PAX-A", "gate-1", now - Duration::minutes(30)),
+            ("PAX-B", "gate-1", now - Duration::minutes(10)),
+        ];
+        for (barcode, device_id, scan_time) in scans {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ($1, 'PDF417', $2, $3, $4)",
+            )
+            .bind(barcode)
+            .bind(device_id)
+            .bind(flight_id)
+            .bind(scan_time)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // Re-tap di gate lain ditolak, scan yang tersimpan tetap yang pertama
+        let retap = create_scan_data(&pool, scan_input(flight_id, "PAX-A", "gate-2"), DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS)
+            .await;
+        assert!(matches!(retap, Err(AppError::DuplicateScan { .. })));
+
+        let latest = get_latest_scans_for_flight(&pool, flight_id).await.unwrap();
+        let found: Vec<(&str, &str)> = latest
+            .iter()
+            .map(|s| (s.barcode_value.as_str(), s.device_id.as_str()))
+            .collect();
+        assert_eq!(found, vec![("PAX-B", "gate-1"), ("PAX-A", "gate-1")]);
+
+        assert!(matches!(
+            get_latest_scans_for_flight(&pool, flight_id + 1000).await,
+            Err(AppError::FlightNotFound)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_scan_data_by_barcode_across_flights(pool: PgPool) {
+        let flight_ids = [seed_flight(&pool, "GA740").await, seed_flight(&pool, "GA741").await];
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        for (flight_id, minutes_ago) in [(flight_ids[0], 90), (flight_ids[1], 5)] {
+            sqlx::query(
+                "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ($1, 'PDF417', 'device-1', $2, NOW() - make_interval(mins => $3))",
+            )
+            .bind(barcode)
+            .bind(flight_id)
+            .bind(minutes_ago)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        insert_scan(&pool, flight_ids[0], "OTHER-BARCODE").await;
+
+        let scans = get_scan_data_by_barcode(&pool, barcode).await.unwrap();
+        let flights: Vec<Option<i32>> = scans.iter().map(|scan| scan.flight_id).collect();
+        assert_eq!(flights, vec![Some(flight_ids[1]), Some(flight_ids[0])]);
+        assert!(scans[0].scan_time > scans[1].scan_time);
+
+        assert!(get_scan_data_by_barcode(&pool, "NEVER-SCANNED").await.unwrap().is_empty());
+    }
+
+    fn scan_input(flight_id: i32, barcode: &str, device_id: &str) -> ScanDataInput {
+        ScanDataInput {
+            barcode_value: barcode.to_string(),
+            barcode_format: "PDF417".to_string(),
+            device_id: device_id.to_string(),
+            flight_id,
+            scanned_at: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_flight_device_allowlist(pool: PgPool) {
+        let restricted = seed_flight(&pool, "GA600").await;
+        let open = seed_flight(&pool, "GA601").await;
+
+        let allowed = add_flight_allowed_devices(&pool, restricted, vec!["device-1".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(allowed, vec!["device-1".to_string()]);
+
+        let denied = create_scan_data(
+            &pool,
+            scan_input(restricted, "PAX-A", "device-2"),
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+        )
+        .await;
+        assert!(matches!(denied, Err(AppError::DeviceNotAllowed { flight_id, .. }) if flight_id == restricted));
+
+        let accepted = create_scan_data(
+            &pool,
+            scan_input(restricted, "PAX-A", "device-1"),
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+        )
+        .await
+        .unwrap();
+        assert_eq!(accepted.device_id, "device-1");
+
+        // Flight tanpa allowlist menerima device mana pun
+        create_scan_data(&pool, scan_input(open, "PAX-B", "device-2"), DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS)
+            .await
+            .unwrap();
+
+        // Setelah allowlist dikosongkan, device lain kembali diizinkan
+        remove_flight_allowed_device(&pool, restricted, "device-1").await.unwrap();
+        create_scan_data(&pool, scan_input(restricted, "PAX-C", "device-2"), DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_flight_detail_matches_individual_queries(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA700").await;
+        for barcode in ["PAX-A", "PAX-B"] {
+            insert_scan(&pool, flight_id, barcode).await;
+        }
+
+        let detail = get_flight_detail(&pool, flight_id).await.unwrap();
+        let flight = get_flight_by_id(&pool, flight_id).await.unwrap();
+        let statistics = get_flight_statistics(&pool, flight_id, false).await.unwrap();
+        let decoded = get_decoded_statistics(&pool, flight_id).await.unwrap();
+
+        assert_eq!(detail.flight.id, flight.id);
+        assert_eq!(detail.flight.flight_number, flight.flight_number);
+        assert_eq!(detail.statistics.total_scans, statistics.total_scans);
+        assert_eq!(detail.statistics.total_scans, 2);
+        assert_eq!(detail.statistics.unique_scans, statistics.unique_scans);
+        assert_eq!(detail.decoded_statistics.total_decoded, decoded.total_decoded);
+        assert_eq!(detail.decoded_statistics.flight_number, "GA700");
+
+        assert!(matches!(get_flight_detail(&pool, flight_id + 1000).await, Err(AppError::FlightNotFound)));
+    }
+
+    #[sqlx::test]
+    async fn test_scan_time_uses_scanned_at_within_skew(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA800").await;
+
+        let backdated = (Utc::now() - Duration::hours(3)).trunc_subsecs(0);
+        let mut offline = scan_input(flight_id, "PAX-A", "device-1");
+        offline.scanned_at = Some(backdated);
+        let scan = create_scan_data(&pool, offline, DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS).await.unwrap();
+        assert_eq!(scan.scan_time, backdated);
+
+        let mut future = scan_input(flight_id, "PAX-B", "device-1");
+        future.scanned_at = Some(Utc::now() + Duration::hours(1));
+        let result = create_scan_data(&pool, future, DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS).await;
+        assert!(matches!(result, Err(AppError::InvalidScanTime)));
+
+        // Toleransi lebih longgar dari konfigurasi: scan yang sama diterima
+        let mut future = scan_input(flight_id, "PAX-B", "device-1");
+        future.scanned_at = Some(Utc::now() + Duration::hours(1));
+        create_scan_data(&pool, future, 2 * 3600).await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_decoding_same_scan_twice_keeps_one_row(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA900").await;
+        let barcode = "M1ABU TALIB/SUZANA MS EQQZBWR KULTWUOD 1900 129Y012F0118 100";
+        let scan = create_scan_data(
+            &pool,
+            scan_input(flight_id, barcode, "device-1"),
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+        )
+        .await
+        .unwrap();
+
+        let request = |name_format| DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id: Some(scan.id),
+            name_format,
+            barcode_format: None,
+        };
+
+        let decode = |name_format| decode_barcode_iata(&pool, request(name_format), DEFAULT_MAX_BARCODE_LENGTH, None);
+        let first = decode(NameFormat::Display).await.unwrap();
+        let second = decode(NameFormat::Raw).await.unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert_eq!(second.flight_number, 1900);
+        assert_eq!(second.flight_number_raw, "1900");
+        assert_eq!(second.passenger_name, "ABU TALIB/SUZANA MS");
+
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decode_barcode WHERE scan_data_id = $1")
+            .bind(scan.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_corrected_decode_changes_statistics(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA716").await;
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        insert_scan(&pool, flight_id, barcode).await;
+        let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+            .bind(barcode)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let decoded = decode_barcode_iata(
+            &pool,
+            DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: Some(scan_id),
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            },
+            DEFAULT_MAX_BARCODE_LENGTH,
+            None,
+        )
+        .await
+        .unwrap();
+        // Simulasi salah parse: cabin class kosong -> decode tidak valid
+        sqlx::query("UPDATE decode_barcode SET cabin_class = ' ' WHERE id = $1")
+            .bind(decoded.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let by_airline = |pool: PgPool| async move {
+            let stats = get_decoded_statistics_by_airline(&pool, &DecodedStatisticsByAirlineQuery::default())
+                .await
+                .unwrap();
+            (stats[0].valid_count, stats[0].invalid_count)
+        };
+        assert_eq!(by_airline(pool.clone()).await, (0, 1));
+        let before = get_decoded_statistics(&pool, flight_id).await.unwrap();
+        assert_eq!((before.infant_count, before.adult_count), (0, 1));
+
+        let update = UpdateDecodedBarcode { infant_status: Some(true), cabin_class: Some("Y".to_string()) };
+        let corrected = update_decoded_barcode(&pool, decoded.id, update).await.unwrap();
+        assert!(corrected.infant_status);
+        assert_eq!(corrected.cabin_class, "Y");
+        assert_eq!(corrected.booking_code, "XYZ789");
+
+        assert_eq!(by_airline(pool.clone()).await, (1, 0));
+        let after = get_decoded_statistics(&pool, flight_id).await.unwrap();
+        assert_eq!((after.infant_count, after.adult_count), (1, 0));
+
+        let missing = UpdateDecodedBarcode { infant_status: Some(false), cabin_class: None };
+        assert!(matches!(update_decoded_barcode(&pool, 999_999, missing).await, Err(AppError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_barcode_flight_link(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA724").await;
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        insert_scan(&pool, flight_id, barcode).await;
+        let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+            .bind(barcode)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let request = |scan_data_id| DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id,
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+
+        let linked = decode_barcode_iata(&pool, request(Some(scan_id)), DEFAULT_MAX_BARCODE_LENGTH, None)
+            .await
+            .unwrap();
+        let flight = get_decoded_barcode_flight(&pool, linked.id).await.unwrap();
+        assert_eq!(flight.id, flight_id);
+        assert_eq!(flight.flight_number, "GA724");
+
+        // Decode ad-hoc tanpa scan tidak punya flight
+        let unlinked = decode_barcode_iata(&pool, request(None), DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        let Err(AppError::NotFound(message)) = get_decoded_barcode_flight(&pool, unlinked.id).await else {
+            panic!("expected unlinked decode to be 404");
+        };
+        assert!(message.contains("not linked"));
+
+        assert!(matches!(get_decoded_barcode_flight(&pool, 999_999).await, Err(AppError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_decode_flight_date_uses_linked_scan_time(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA724").await;
+        // Julian 365 dari boarding pass 30 Desember 2024, di-scan 5 Januari 2025
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 365Y023B0045 300";
+        let scan_id: i32 = sqlx::query_scalar(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time)
+             VALUES ($1, 'PDF417', 'device-1', $2, '2025-01-05T08:00:00Z') RETURNING id",
+        )
+        .bind(barcode)
+        .bind(flight_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let request = DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id: Some(scan_id),
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+
+        let decoded = decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        assert_eq!(decoded.flight_date, NaiveDate::from_ymd_opt(2024, 12, 30));
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_flight_mismatch(pool: PgPool) {
+        // Barcode: JT 0610, julian 277 (4 Oktober pada tahun non-kabisat)
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let mut decoded_ids = Vec::new();
+        for (flight_number, departure) in [
+            ("JT0610", "2030-10-04T08:00:00Z"),
+            ("JT0611", "2030-10-04T08:00:00Z"),
+            ("GA610", "2030-10-05T08:00:00Z"),
+        ] {
+            let flight_id: i32 = sqlx::query_scalar(
+                "INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate) VALUES ($1, 'Lion Air', 'B739', $2::timestamptz, 'SUB', 'A1') RETURNING id",
+            )
+            .bind(flight_number)
+            .bind(departure)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            insert_scan(&pool, flight_id, barcode).await;
+            let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE flight_id = $1")
+                .bind(flight_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            let request = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: Some(scan_id),
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            };
+            decoded_ids.push(decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap().id);
+        }
+
+        let matching = get_decoded_flight_mismatch(&pool, decoded_ids[0]).await.unwrap();
+        assert!(matching.matches);
+        assert!(matching.mismatched_fields.is_empty());
+
+        let wrong_number = get_decoded_flight_mismatch(&pool, decoded_ids[1]).await.unwrap();
+        assert!(!wrong_number.matches);
+        assert_eq!(wrong_number.mismatched_fields, vec!["flightNumber"]);
+        assert_eq!(wrong_number.decoded_flight_number, 610);
+        assert_eq!(wrong_number.flight_number, "JT0611");
+
+        let wrong_flight = get_decoded_flight_mismatch(&pool, decoded_ids[2]).await.unwrap();
+        assert_eq!(wrong_flight.mismatched_fields, vec!["airline", "flightDate"]);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_flight_number_raw_keeps_leading_zeros(pool: PgPool) {
+        let request = DecodeRequest {
+            barcode_value: "M1PRASETYO/YUDHA DWI  EE6UVIL CGKSUBGA 0312 260Y045C0120 348".to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+
+        let decoded = decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        assert_eq!(decoded.flight_number, 312);
+        assert_eq!(decoded.flight_number_raw, "0312");
+
+        let stored: String = sqlx::query_scalar("SELECT flight_number_raw FROM decode_barcode WHERE id = $1")
+            .bind(decoded.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, "0312");
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_barcodes_airline_and_date_filters(pool: PgPool) {
+        let barcodes = [
+            ("M1PRASETYO/YUDHA DWI  EE6UVIL CGKSUBGA 0312 260Y045C0120 348", "2025-01-10 08:00:00+00"),
+            ("M1SMITH/JOHN          EABC123 CGKJKTGA 0001 001Y001A0001 100", "2025-01-20 08:00:00+00"),
+            ("M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.", "2025-01-20 09:00:00+00"),
+        ];
+        for (barcode, created_at) in barcodes {
+            let request = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: None,
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            };
+            let decoded = decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+            sqlx::query("UPDATE decode_barcode SET created_at = $1::timestamptz WHERE id = $2")
+                .bind(created_at)
+                .bind(decoded.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let query = GetDecodedBarcodesQuery {
+            airline_code: Some("ga".to_string()),
+            from: Some("2025-01-15T00:00:00Z".parse().unwrap()),
+            to: Some("2025-01-31T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let rows = get_all_decoded_barcodes(&pool, &query, Pagination::default()).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].flight_number_raw, "0001");
+        assert_eq!(count_decoded_barcodes(&pool, &query).await.unwrap(), 1);
+
+        // Tanpa filter airline, rentang tanggal yang sama mencakup GA dan ID
+        let query = GetDecodedBarcodesQuery {
+            from: Some("2025-01-15T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let rows = get_all_decoded_barcodes(&pool, &query, Pagination { limit: 1, offset: 0 }).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].airline_code, "ID");
+        assert_eq!(count_decoded_barcodes(&pool, &query).await.unwrap(), 2);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_barcode_resolves_cabin_class_name(pool: PgPool) {
+        let decode = |barcode: &str| DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+        // Y ada di seed cabin_class_codes, Z tidak
+        let economy = decode_barcode_iata(&pool, decode("M1SMITH/JOHN          EABC123 CGKJKTGA 0001 001Y001A0001 100"), DEFAULT_MAX_BARCODE_LENGTH, None)
+            .await
+            .unwrap();
+        assert_eq!(economy.cabin_class_name.as_deref(), Some("Economy Class"));
+        let unmapped = decode_barcode_iata(&pool, decode("M1SMITH/JANE          EABC124 CGKJKTGA 0001 001Z001B0002 100"), DEFAULT_MAX_BARCODE_LENGTH, None)
+            .await
+            .unwrap();
+        assert_eq!(unmapped.cabin_class, "Z");
+        assert_eq!(unmapped.cabin_class_name, None);
+
+        let rows = get_all_decoded_barcodes(&pool, &GetDecodedBarcodesQuery::default(), Pagination::default())
+            .await
+            .unwrap();
+        let name_of = |id: i32| rows.iter().find(|row| row.id == id).unwrap().cabin_class_name.clone();
+        assert_eq!(name_of(economy.id).as_deref(), Some("Economy Class"));
+        assert_eq!(name_of(unmapped.id), None);
+
+        // Koreksi manual ke kelas lain ikut mengembalikan nama barunya
+        let update = UpdateDecodedBarcode { infant_status: None, cabin_class: Some("C".to_string()) };
+        let updated = update_decoded_barcode(&pool, unmapped.id, update).await.unwrap();
+        assert_eq!(updated.cabin_class_name.as_deref(), Some("Business Class"));
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_barcodes_pagination(pool: PgPool) {
+        sqlx::query(
+            r#"
+            INSERT INTO decode_barcode (barcode_value, passenger_name, booking_code, origin, destination,
+                airline_code, flight_number, flight_number_raw, flight_date_julian, cabin_class,
+                seat_number, sequence_number, infant_status)
+            SELECT 'M1TEST/' || n, 'TEST ' || n, 'PNR' || n, 'CGK', 'DPS', 'GA', n, n::text,
+                   '001', 'Y', '001A', n::text, false
+            FROM generate_series(1, 120) AS n
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Default limit 100
+        let query = GetDecodedBarcodesQuery::default();
+        assert_eq!(get_all_decoded_barcodes(&pool, &query, Pagination::default()).await.unwrap().len(), 100);
+        assert_eq!(count_decoded_barcodes(&pool, &query).await.unwrap(), 120);
+
+        // Halaman berurutan tidak tumpang tindih dan mencakup semua baris
+        let mut seen = std::collections::HashSet::new();
+        for page in 0..3 {
+            let pagination = Pagination { limit: 50, offset: page * 50 };
+            for row in get_all_decoded_barcodes(&pool, &query, pagination).await.unwrap() {
+                assert!(seen.insert(row.id));
+            }
+        }
+        assert_eq!(seen.len(), 120);
+
+        // Limit di atas maksimum dipotong menjadi 500
+        let pagination = Pagination::new(Some(10_000), None, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE).unwrap();
+        assert_eq!(pagination.limit, MAX_PAGE_SIZE);
+        assert_eq!(get_all_decoded_barcodes(&pool, &query, pagination).await.unwrap().len(), 120);
+    }
+
+    #[sqlx::test]
+    async fn test_scan_with_decode(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA360").await;
+
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let scan = scan_input(flight_id, barcode, "device-1");
+        let created = create_scan_data_with_decode(
+            &pool,
+            scan,
+            DEFAULT_MAX_BARCODE_LENGTH,
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+            None,
+        )
+        .await
+        .unwrap();
+        let decoded = created.decoded.unwrap();
+        assert_eq!(decoded.scan_data_id, Some(created.scan.id));
+        assert_eq!(decoded.booking_code, "XYZ789");
+        assert!(created.rejection.is_none());
+
+        // Barcode tidak bisa di-parse: scan tetap tersimpan, rejection log dibuat otomatis
+        let scan = scan_input(flight_id, "NOT-A-BOARDING-PASS", "device-1");
+        let created = create_scan_data_with_decode(
+            &pool,
+            scan,
+            DEFAULT_MAX_BARCODE_LENGTH,
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(created.decoded.is_none());
+        let rejection = created.rejection.unwrap();
+        assert_eq!(rejection.reason, "DECODE_FAILED");
+        assert_eq!(rejection.flight_number.as_deref(), Some("GA360"));
+        assert_eq!(rejection.device_id.as_deref(), Some("device-1"));
+
+        let scans: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scan_data WHERE flight_id = $1")
+            .bind(flight_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(scans, 2);
+        let decoded_rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decode_barcode").fetch_one(&pool).await.unwrap();
+        assert_eq!(decoded_rows, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_decode_rejects_replacement_characters(pool: PgPool) {
+        let request = DecodeRequest {
+            barcode_value: "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300\u{FFFD}\u{FFFD}".to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+        let result = decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await;
+        let Err(AppError::InvalidBarcodeEncoding(issue)) = result else {
+            panic!("expected encoding error");
+        };
+        assert_eq!(issue.replacement_chars, 2);
+        let decoded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decode_barcode").fetch_one(&pool).await.unwrap();
+        assert_eq!(decoded, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_decode_rejects_barcode_over_max_length(pool: PgPool) {
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let request = || DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+
+        // Batas diteruskan dari AppConfig (MAX_BARCODE_LENGTH), bukan disimpan di parser
+        let result = decode_barcode_iata(&pool, request(), barcode.len() - 1, None).await;
+        assert!(matches!(result, Err(AppError::InvalidBarcodeFormat)));
+        assert!(decode_barcode_iata(&pool, request(), barcode.len(), None).await.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn test_decode_records_source_format(pool: PgPool) {
+        let flight_ids = [seed_flight(&pool, "GA910").await, seed_flight(&pool, "GA911").await];
+        let barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.";
+
+        // Payload sama: boarding pass kertas (PDF417) dan mobile pass (AZTEC)
+        let mut decoded = Vec::new();
+        for (flight_id, format) in flight_ids.into_iter().zip(["PDF417", "AZTEC"]) {
+            let scan = create_scan_data(&pool, ScanDataInput {
+                barcode_format: format.to_string(),
+                ..scan_input(flight_id, barcode, "device-1")
+            }, DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS)
+            .await
+            .unwrap();
+            let request = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: Some(scan.id),
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            };
+            decoded.push(decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap());
+        }
+
+        assert_eq!(decoded[0].source_format.as_deref(), Some("PDF417"));
+        assert_eq!(decoded[1].source_format.as_deref(), Some("AZTEC"));
+        assert_eq!(decoded[0].passenger_name, decoded[1].passenger_name);
+        assert_eq!(decoded[0].booking_code, decoded[1].booking_code);
+        assert_eq!(decoded[0].seat_number, decoded[1].seat_number);
+
+        // Decode ad-hoc dengan format eksplisit
+        let request = DecodeRequest {
+            barcode_value: barcode.to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: Some("AZTEC".to_string()),
+        };
+        let ad_hoc = decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        assert_eq!(ad_hoc.source_format.as_deref(), Some("AZTEC"));
+    }
+
+    #[sqlx::test]
+    async fn test_decode_diagnostics_reports_strategy(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "ID6473").await;
+        let barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.";
+        let scan_id = create_scan_data(
+            &pool,
+            scan_input(flight_id, barcode, "device-1"),
+            DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+        )
+        .await
+        .unwrap()
+        .id;
+
+        let diagnostics = get_decode_diagnostics(&pool, scan_id).await.unwrap();
+        assert_eq!(diagnostics.strategy.as_deref(), Some("space_delimited"));
+        assert_eq!(diagnostics.tokens, ["ESMMTHQ", "DHXCGKID", "6473", "032Y007A0002", "300."]);
+        let fields = diagnostics.fields.unwrap();
+        assert_eq!(fields.booking_code, "SMMTHQ");
+        assert_eq!(fields.airline_code, "ID");
+        assert_eq!(fields.seat_number, "007A");
+
+        // Diagnostik tidak menyimpan hasil decode
+        let decoded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decode_barcode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(decoded, 0);
+
+        assert!(matches!(get_decode_diagnostics(&pool, scan_id + 1).await, Err(AppError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_reconcile_manifest_partial_match(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "JT610").await;
+        for barcode in [
+            "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.",
+            "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300",
+            "M1MAYZURA/AUFARIZA HANEBJQUJW CGKUPGID 6296 147Y0INF0097 100",
+            "M1PUTRI/AGUS MR       EXYZ789 CGKSUBJT 0610 277Y023C0046 300",
+        ] {
+            let scan = scan_input(flight_id, barcode, "device-1");
+            create_scan_data_with_decode(
+                &pool,
+                scan,
+                DEFAULT_MAX_BARCODE_LENGTH,
+                DEFAULT_SCAN_TIME_MAX_SKEW_SECONDS,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Booking code (beda huruf besar), nama penumpang (spasi berlebih), dan satu yang belum scan
+        let manifest = vec!["smmthq".to_string(), "Ms  Siti Putri".to_string(), "ABC999".to_string()];
+        let result = reconcile_manifest(&pool, flight_id, &manifest).await.unwrap();
+        assert_eq!(result.scanned, vec!["smmthq", "Ms  Siti Putri"]);
+        assert_eq!(result.missing, vec!["ABC999"]);
+        // Penumpang lain pada PNR XYZ789 tidak ikut tertutup oleh entri nama Siti Putri
+        let unexpected: Vec<(&str, &str)> = result
+            .unexpected
+            .iter()
+            .map(|p| (p.booking_code.as_str(), p.passenger_name.as_str()))
+            .collect();
+        assert_eq!(unexpected, vec![("BJQUJW", "Aufariza Han Mayzura"), ("XYZ789", "Mr Agus Putri")]);
+
+        assert!(matches!(
+            reconcile_manifest(&pool, flight_id + 1, &manifest).await,
+            Err(AppError::FlightNotFound)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_decode_rejects_format_without_decoder(pool: PgPool) {
+        let request = DecodeRequest {
+            barcode_value: "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.".to_string(),
+            scan_data_id: None,
+            name_format: NameFormat::Display,
+            barcode_format: Some("QR".to_string()),
+        };
+        match decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await {
+            Err(AppError::UnsupportedBarcodeFormat(format)) => assert_eq!(format, "QR"),
+            other => panic!("expected UnsupportedBarcodeFormat, got {:?}", other),
+        }
+
+        // Tidak ada baris decode yang tersimpan
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decode_barcode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_statistics_by_airline(pool: PgPool) {
+        let query = DecodedStatisticsByAirlineQuery::default();
+        assert!(get_decoded_statistics_by_airline(&pool, &query).await.unwrap().is_empty());
+
+        // GA: 2 valid + 1 julian tidak valid; ID: 1 tanpa cabin class; ID lama di luar rentang
+        for (airline, julian, class, created_at) in [
+            ("GA", "032", "Y", "2025-02-01T08:00:00Z"),
+            ("GA", "033", "C", "2025-02-02T08:00:00Z"),
+            ("GA", "400", "Y", "2025-02-02T09:00:00Z"),
+            ("ID", "032", "", "2025-02-01T10:00:00Z"),
+            ("ID", "001", "Y", "2024-12-31T10:00:00Z"),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO decode_barcode (barcode_value, passenger_name, booking_code, origin, destination,
+                    airline_code, flight_number, flight_number_raw, flight_date_julian, cabin_class,
+                    seat_number, sequence_number, infant_status, created_at)
+                VALUES ('M1TEST', 'TEST', 'PNR001', 'CGK', 'DPS', $1, 312, '0312', $2, $3, '001A', '0001', false, $4::timestamptz)
+                "#,
+            )
+            .bind(airline)
+            .bind(julian)
+            .bind(class)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let query = DecodedStatisticsByAirlineQuery {
+            from: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            to: None,
+        };
+        let stats = get_decoded_statistics_by_airline(&pool, &query).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].airline_code, "GA");
+        assert_eq!((stats[0].total_decoded, stats[0].valid_count, stats[0].invalid_count), (3, 2, 1));
+        assert_eq!(stats[1].airline_code, "ID");
+        assert_eq!((stats[1].total_decoded, stats[1].valid_count, stats[1].invalid_count), (1, 0, 1));
+    }
+
+    #[sqlx::test]
+    async fn test_undecoded_scans_excludes_decoded(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA410").await;
+
+        let decoded_barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.";
+        insert_scan(&pool, flight_id, decoded_barcode).await;
+        insert_scan(&pool, flight_id, "GARBLED-SCAN").await;
+
+        let decoded_scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+            .bind(decoded_barcode)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let request = DecodeRequest {
+            barcode_value: decoded_barcode.to_string(),
+            scan_data_id: Some(decoded_scan_id),
+            name_format: NameFormat::Display,
+            barcode_format: None,
+        };
+        decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+
+        let undecoded = get_undecoded_scans(&pool, flight_id).await.unwrap();
+        assert_eq!(undecoded.len(), 1);
+        assert_eq!(undecoded[0].barcode_value, "GARBLED-SCAN");
+
+        assert!(matches!(get_undecoded_scans(&pool, 999_999).await, Err(AppError::FlightNotFound)));
+    }
+
+    fn no_change() -> UpdateFlight {
+        UpdateFlight {
+            airline: None,
+            aircraft: None,
+            departure_time: None,
+            destination: None,
+            gate: None,
+            is_active: None,
+            capacity: None,
+            clear_fields: Vec::new(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_update_flight_omit_vs_clear(pool: PgPool) {
+        let mut flight = sample_flight("GA722", Utc::now() + Duration::days(1));
+        flight.capacity = Some(180);
+        let created = create_flight(&pool, flight, None).await.unwrap();
+
+        // Field yang tidak dikirim tidak berubah
+        let untouched = update_flight(&pool, created.id, UpdateFlight { airline: Some("Citilink".to_string()), ..no_change() })
+            .await
+            .unwrap();
+        assert_eq!(untouched.airline, "Citilink");
+        assert_eq!(untouched.gate, "A1");
+        assert_eq!(untouched.aircraft, "B738");
+        assert_eq!(untouched.capacity, Some(180));
+
+        // Clear eksplisit: gate kembali ke TBD, capacity NULL, aircraft tetap
+        let clear = UpdateFlight { clear_fields: vec!["gate".to_string(), "capacity".to_string()], ..no_change() };
+        clear.validate().unwrap();
+        let cleared = update_flight(&pool, created.id, clear).await.unwrap();
+        assert_eq!(cleared.gate, "TBD");
+        assert_eq!(cleared.capacity, None);
+        assert_eq!(cleared.aircraft, "B738");
+        assert_eq!(cleared.airline, "Citilink");
+
+        let unknown = UpdateFlight { clear_fields: vec!["airline".to_string()], ..no_change() };
+        assert!(unknown.validate().is_err());
+        let ambiguous = UpdateFlight {
+            gate: Some("B2".to_string()),
+            clear_fields: vec!["gate".to_string()],
+            ..no_change()
+        };
+        assert!(ambiguous.validate().is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_touched_flight_reappears_in_sync(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA420").await;
+
+        // Penerbangan terakhir berubah 1 jam lalu; client sudah sync setelahnya
+        sqlx::query("UPDATE flights SET created_at = NOW() - INTERVAL '1 hour', updated_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(flight_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let last_sync = Utc::now() - Duration::minutes(1);
+        assert!(get_flights_since(&pool, Some(last_sync), true).await.unwrap().is_empty());
+
+        let touched = touch_flight(&pool, flight_id).await.unwrap();
+        assert_eq!(touched.flight_number, "GA420");
+
+        let synced = get_flights_since(&pool, Some(last_sync), true).await.unwrap();
+        assert_eq!(synced.len(), 1);
+        assert_eq!(synced[0].id, flight_id);
+
+        assert!(matches!(touch_flight(&pool, 999_999).await, Err(AppError::FlightNotFound)));
+    }
+
+    #[sqlx::test]
+    async fn test_echoed_updated_at_is_not_synced_again(pool: PgPool) {
+        seed_flight(&pool, "GA722").await;
+        // Kolom menyimpan mikrodetik; response hanya sampai milidetik
+        sqlx::query("UPDATE flights SET updated_at = '2030-01-01T08:00:00.123456Z' WHERE flight_number = 'GA722'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let flight = get_flights_since(&pool, None, true).await.unwrap().remove(0);
+        let echoed: DateTime<Utc> = crate::models::format_timestamp(&flight.updated_at.unwrap()).parse().unwrap();
+        assert_eq!(echoed.to_rfc3339(), "2030-01-01T08:00:00.123+00:00");
+        assert!(get_flights_since(&pool, Some(echoed), true).await.unwrap().is_empty());
+
+        // Perubahan pada milidetik berikutnya tetap ikut sync
+        sqlx::query("UPDATE flights SET updated_at = '2030-01-01T08:00:00.124001Z' WHERE flight_number = 'GA722'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(get_flights_since(&pool, Some(echoed), true).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_soft_deleted_flight_syncs_unless_excluded(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA737").await;
+        seed_flight(&pool, "GA738").await;
+        let last_sync = Utc::now() - Duration::minutes(1);
+        delete_flight(&pool, flight_id).await.unwrap();
+
+        let synced = get_flights_since(&pool, Some(last_sync), true).await.unwrap();
+        let deleted = synced.iter().find(|f| f.id == flight_id).unwrap();
+        assert_eq!(deleted.is_active, Some(false));
+        assert_eq!(synced.len(), 2);
+
+        let active_only = get_flights_since(&pool, Some(last_sync), false).await.unwrap();
+        let numbers: Vec<&str> = active_only.iter().map(|f| f.flight_number.as_str()).collect();
+        assert_eq!(numbers, vec!["GA738"]);
+        assert_eq!(get_flights_since(&pool, None, false).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_never_updated_flight_syncs_in_change_order(pool: PgPool) {
+        let departure = Utc::now() + Duration::days(1);
+        bulk_insert_flights(
+            &pool,
+            vec![sample_flight("GA719", departure), sample_flight("GA720", departure), sample_flight("GA721", departure)],
+            false,
+            BulkConflictMode::Upsert,
+        )
+        .await
+        .unwrap();
+        // GA719: dibuat 40 menit lalu, belum pernah di-update (updated_at NULL)
+        // GA720: dibuat 2 jam lalu, di-update 20 menit lalu
+        // GA721: tidak berubah sejak sebelum last_sync
+        for (flight_number, created, updated) in [
+            ("GA719", "40 minutes", None),
+            ("GA720", "2 hours", Some("20 minutes")),
+            ("GA721", "3 hours", Some("2 hours")),
+        ] {
+            sqlx::query(
+                "UPDATE flights SET created_at = NOW() - $1::interval, updated_at = NOW() - $2::interval WHERE flight_number = $3",
+            )
+            .bind(created)
+            .bind(updated)
+            .bind(flight_number)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let last_sync = Utc::now() - Duration::hours(1);
+        let synced = get_flights_since(&pool, Some(last_sync), true).await.unwrap();
+        let numbers: Vec<&str> = synced.iter().map(|f| f.flight_number.as_str()).collect();
+        assert_eq!(numbers, vec!["GA719", "GA720"]);
+        assert!(synced[0].updated_at.is_none());
+
+        let full: Vec<String> = get_flights_since(&pool, None, true)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.flight_number)
+            .collect();
+        assert_eq!(full, vec!["GA721", "GA719", "GA720"]);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_statistics_batch(pool: PgPool) {
+        let ids = [seed_flight(&pool, "GA430").await, seed_flight(&pool, "GA431").await];
+
+        let barcodes = [
+            (ids[0], "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300."),
+            (ids[0], "M1MAYZURA/AUFARIZA HANEBJQUJW CGKUPGID 6296 147Y0INF0097 100"),
+            (ids[1], "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300"),
+        ];
+        for (flight_id, barcode) in barcodes {
+            insert_scan(&pool, flight_id, barcode).await;
+            let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+                .bind(barcode)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            let request = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: Some(scan_id),
+                name_format: NameFormat::Display,
+                barcode_format: None,
+            };
+            decode_barcode_iata(&pool, request, DEFAULT_MAX_BARCODE_LENGTH, None).await.unwrap();
+        }
+
+        let stats = get_decoded_statistics_batch(&pool, &[ids[0], ids[1], 999_999]).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].flight_id, stats[0].total_decoded, stats[0].infant_count, stats[0].adult_count), (ids[0], 2, 1, 1));
+        assert_eq!((stats[1].flight_id, stats[1].total_decoded, stats[1].infant_count, stats[1].adult_count), (ids[1], 1, 0, 1));
+
+        let single = get_decoded_statistics(&pool, ids[0]).await.unwrap();
+        assert_eq!(single.total_decoded, stats[0].total_decoded);
+    }
+
+    #[test]
+    fn test_resolve_cabin_class_fallback() {
+        assert_eq!(resolve_cabin_class("J".to_string(), Some("Y")), "J");
+        assert_eq!(resolve_cabin_class(String::new(), Some("Y")), "Y");
+        assert_eq!(resolve_cabin_class(String::new(), None), "");
+    }
+
+    #[sqlx::test]
+    async fn test_export_flights_date_range(pool: PgPool) {
+        let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2030, 3, d).unwrap();
+        let at = |d: u32| day(d).and_hms_opt(10, 0, 0).unwrap().and_utc();
+        let flights = vec![
+            sample_flight("GA440", at(1)),
+            sample_flight("GA441", at(2)),
+            sample_flight("GA442", at(3)),
+            sample_flight("GA443", at(5)),
+        ];
+        bulk_insert_flights(&pool, flights, false, BulkConflictMode::Upsert).await.unwrap();
+        sqlx::query("UPDATE flights SET is_active = false WHERE flight_number = 'GA442'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut query = ExportFlightsQuery {
+            from: day(1),
+            to: day(3),
+            format: crate::models::ExportFormat::Json,
+            include_inactive: None,
+        };
+        let numbers = |flights: Vec<Flight>| flights.into_iter().map(|f| f.flight_number).collect::<Vec<_>>();
+        assert_eq!(numbers(export_flights(&pool, &query).await.unwrap()), ["GA440", "GA441"]);
+
+        query.include_inactive = Some(true);
+        assert_eq!(numbers(export_flights(&pool, &query).await.unwrap()), ["GA440", "GA441", "GA442"]);
+
+        let mut rx = stream_flights_for_export(pool.clone(), query);
+        let mut streamed = Vec::new();
+        while let Some(row) = rx.recv().await {
+            streamed.push(row.unwrap().flight_number);
+        }
+        assert_eq!(streamed, ["GA440", "GA441", "GA442"]);
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_statistics_missing_inactive_and_empty_flight(pool: PgPool) {
+        let ids = [seed_flight(&pool, "GA460").await, seed_flight(&pool, "GA461").await];
+
+        // Flight aktif tanpa decode: 200 dengan nilai nol
+        let stats = get_decoded_statistics(&pool, ids[0]).await.unwrap();
+        assert_eq!((stats.total_decoded, stats.infant_count, stats.adult_count), (0, 0, 0));
+
+        // Flight tidak ada
+        assert!(matches!(get_decoded_statistics(&pool, 999_999).await, Err(AppError::FlightNotFound)));
+
+        // Flight non-aktif diperlakukan sama seperti tidak ada
+        delete_flight(&pool, ids[1]).await.unwrap();
+        assert!(matches!(get_decoded_statistics(&pool, ids[1]).await, Err(AppError::FlightNotFound)));
+    }
+
+    #[sqlx::test]
+    async fn test_rejection_logs_by_barcode_newest_first(pool: PgPool) {
+        let rejection = |barcode: &str, reason: &str| CreateRejectionLog {
+            barcode_value: barcode.to_string(),
+            barcode_format: "PDF417".to_string(),
+            reason: reason.to_string(),
+            expected_date: None,
+            actual_date: None,
+            flight_number: None,
+            airline: None,
+            device_id: Some("device-1".to_string()),
+        };
+        let barcode = "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300";
+        let mut ids = Vec::new();
+        for (minutes_ago, reason) in [(30, "date_mismatch"), (10, "invalid_format"), (20, "date_mismatch")] {
+            let log = create_rejection_log(&pool, rejection(barcode, reason)).await.unwrap();
+            sqlx::query("UPDATE rejection_logs SET rejected_at = NOW() - make_interval(mins => $1) WHERE id = $2")
+                .bind(minutes_ago)
+                .bind(log.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            ids.push(log.id);
+        }
+        create_rejection_log(&pool, rejection("OTHER-BARCODE", "date_mismatch")).await.unwrap();
+
+        let logs = get_rejection_logs_by_barcode(&pool, barcode).await.unwrap();
+        let found: Vec<i32> = logs.iter().map(|log| log.id).collect();
+        assert_eq!(found, vec![ids[1], ids[2], ids[0]]);
+        assert!(logs.iter().all(|log| log.barcode_value == barcode));
+
+        assert!(get_rejection_logs_by_barcode(&pool, "NEVER-REJECTED").await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_airport_import_upserts_and_bumps_version_once(pool: PgPool) {
+        let before = get_starter_data_version(&pool).await.unwrap().version;
+        let codes = vec![
+            UpsertAirportCode {
+                code: "CGK".to_string(),
+                name: "Soekarno-Hatta Intl".to_string(),
+                city: "Tangerang".to_string(),
+                country: "Indonesia".to_string(),
+            },
+            UpsertAirportCode {
+                code: "lop".to_string(),
+                name: "Lombok International Airport".to_string(),
+                city: "Praya".to_string(),
+                country: "Indonesia".to_string(),
+            },
+        ];
+
+        let summary = bulk_upsert_airport_codes(&pool, codes).await.unwrap();
+        assert_eq!(summary.inserted, 1);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.starter_data_version, before + 1);
+        assert_eq!(get_starter_data_version(&pool).await.unwrap().version, before + 1);
+
+        let airports = get_airport_codes(&pool).await.unwrap();
+        let cgk = airports.iter().find(|a| a.code == "CGK").unwrap();
+        assert_eq!(cgk.city, "Tangerang");
+        assert_eq!(airports.iter().filter(|a| a.code == "CGK").count(), 1);
+        assert!(airports.iter().any(|a| a.code == "LOP" && a.city == "Praya"));
+    }
+
+    #[sqlx::test]
+    async fn test_decoded_statistics_class_breakdown(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "JT729").await;
+        for (n, class) in ["Y", "C", "Y", "F", "Y", "C"].iter().enumerate() {
+            let barcode = format!("M1PUTRI/SITI MS       EXYZ78{} CGKSUBJT 0610 277{}023B004{} 300", n, class, n);
+            insert_scan(&pool, flight_id, &barcode).await;
+            let scan_id: i32 = sqlx::query_scalar("SELECT id FROM scan_data WHERE barcode_value = $1")
+                .bind(&barcode)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            decode_barcode_iata(
+                &pool,
+                DecodeRequest {
+                    barcode_value: barcode,
+                    scan_data_id: Some(scan_id),
+                    name_format: NameFormat::Display,
+                    barcode_format: None,
+                },
+                DEFAULT_MAX_BARCODE_LENGTH,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let breakdown = |stats: &DecodedStatistics| -> Vec<(String, i64)> {
+            stats.class_breakdown.iter().map(|c| (c.cabin_class.clone(), c.count)).collect()
+        };
+        let expected = vec![("Y".to_string(), 3), ("C".to_string(), 2), ("F".to_string(), 1)];
+
+        let stats = get_decoded_statistics(&pool, flight_id).await.unwrap();
+        assert_eq!(stats.total_decoded, 6);
+        assert_eq!(breakdown(&stats), expected);
+
+        let batch = get_decoded_statistics_batch(&pool, &[flight_id]).await.unwrap();
+        assert_eq!(breakdown(&batch[0]), expected);
+    }
+}
@@ -1,12 +1,15 @@
 use crate::{
+    config::{AppConfig, SessionLimitPolicy},
     errors::AppError,
+    extractors::Pagination,
     models::{
         User, UserWithRole, Role, Permission, RoleWithPermissions,
-        LoginResponse, CreateUserRequest, UpdateUserRequest, ListUsersQuery,
+        LoginResponse, CreateUserRequest, UpdateUserRequest, ListUsersQuery, UserActivityEvent,
+        TokenValidation,
     },
 };
 use sqlx::PgPool;
-use chrono::{Utc, Duration};
+use chrono::{DateTime, Utc, Duration};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use sha2::{Sha256, Digest};
@@ -19,18 +22,71 @@ fn get_jwt_secret() -> String {
     })
 }
 
+/// Token and session settings, parsed once from `AppConfig` at startup
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    /// JWT issuer claim (`iss`), minted into and required on every token
+    pub jwt_issuer: String,
+    /// JWT audience claim (`aud`), minted into and required on every token
+    pub jwt_audience: String,
+    /// Allowed clock skew (seconds) when checking `exp`, so tokens near expiry
+    /// aren't rejected because app instances' clocks differ slightly
+    pub jwt_leeway_seconds: u64,
+    /// Cap on a user's concurrent active sessions (None = unlimited)
+    pub max_sessions_per_user: Option<i64>,
+    /// What a login does when `max_sessions_per_user` is reached
+    pub session_limit_policy: SessionLimitPolicy,
+}
+
+impl AuthSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            jwt_issuer: config.jwt_issuer.clone(),
+            jwt_audience: config.jwt_audience.clone(),
+            jwt_leeway_seconds: config.jwt_leeway_seconds,
+            max_sessions_per_user: config.max_sessions_per_user,
+            session_limit_policy: config.session_limit_policy,
+        }
+    }
+}
+
+/// Build token validation rules: signature, expiry, issuer and audience must all match
+fn jwt_validation(issuer: &str, audience: &str, leeway_seconds: u64) -> Validation {
+    let mut validation = Validation::default();
+    validation.leeway = leeway_seconds;
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    validation
+}
+
+/// Decode and validate JWT claims
+fn decode_claims(
+    token: &str,
+    secret: &str,
+    validation: &Validation,
+) -> Result<crate::models::Claims, AppError> {
+    decode::<crate::models::Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), validation)
+        .map(|data| data.claims)
+        .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))
+}
+
 /// Token expiration duration (7 days)
 const TOKEN_EXPIRATION_DAYS: i64 = 7;
 
 // ==================== AUTHENTICATION FUNCTIONS ====================
 
 /// Authenticate user with username and password
+///
+/// `settings.max_sessions_per_user` caps the user's concurrent active sessions and
+/// `settings.session_limit_policy` decides what happens when the cap is reached.
 pub async fn authenticate_user(
     pool: &PgPool,
     username: &str,
     password: &str,
     device_info: Option<String>,
     ip_address: Option<String>,
+    settings: &AuthSettings,
 ) -> Result<LoginResponse, AppError> {
     // Get user from database
     let user = sqlx::query_as::<_, User>(
@@ -98,6 +154,8 @@ pub async fn authenticate_user(
         permissions: permission_names.clone(),
         exp: expires_at.timestamp(),
         iat: now.timestamp(),
+        iss: settings.jwt_issuer.clone(),
+        aud: settings.jwt_audience.clone(),
     };
 
     let token = encode(
@@ -112,6 +170,17 @@ pub async fn authenticate_user(
     hasher.update(token.as_bytes());
     let token_hash = format!("{:x}", hasher.finalize());
 
+    // Cek limit sesi dan simpan sesi baru dalam satu transaksi; baris user dikunci agar
+    // login paralel user yang sama tidak bisa sama-sama lolos pengecekan limit
+    let mut tx = pool.begin().await?;
+    if let Some(limit) = settings.max_sessions_per_user {
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        enforce_session_limit(&mut tx, user.id, limit, settings.session_limit_policy).await?;
+    }
+
     // Save session to database
     sqlx::query(
         r#"
@@ -124,8 +193,9 @@ pub async fn authenticate_user(
     .bind(&device_info)
     .bind(&ip_address)
     .bind(expires_at)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
+    tx.commit().await?;
 
     // Update last login timestamp
     sqlx::query(
@@ -162,16 +232,39 @@ pub async fn authenticate_user(
 }
 
 /// Verify JWT token and return user_id
-pub async fn verify_token(pool: &PgPool, token: &str) -> Result<i32, AppError> {
-    // Decode JWT
-    let token_data = decode::<crate::models::Claims>(
-        token,
-        &DecodingKey::from_secret(get_jwt_secret().as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
+pub async fn verify_token(pool: &PgPool, token: &str, settings: &AuthSettings) -> Result<i32, AppError> {
+    verify_token_claims(pool, token, settings).await.map(|claims| claims.sub)
+}
 
-    let user_id = token_data.claims.sub;
+/// Check a token without side effects: who it belongs to and when it expires
+pub async fn validate_token(
+    pool: &PgPool,
+    token: &str,
+    settings: &AuthSettings,
+) -> Result<TokenValidation, AppError> {
+    let claims = verify_token_claims(pool, token, settings).await?;
+    let expires_at = DateTime::from_timestamp(claims.exp, 0)
+        .ok_or_else(|| AppError::Unauthorized("Invalid token expiry".to_string()))?;
+
+    Ok(TokenValidation {
+        valid: true,
+        user_id: claims.sub,
+        expires_at,
+    })
+}
+
+/// Verify JWT signature/claims and that its session is still active; read-only
+async fn verify_token_claims(
+    pool: &PgPool,
+    token: &str,
+    settings: &AuthSettings,
+) -> Result<crate::models::Claims, AppError> {
+    // Decode JWT (signature, expiry, issuer, audience)
+    let leeway_seconds = settings.jwt_leeway_seconds;
+    let validation = jwt_validation(&settings.jwt_issuer, &settings.jwt_audience, leeway_seconds);
+    let claims = decode_claims(token, &get_jwt_secret(), &validation)?;
+
+    let user_id = claims.sub;
 
     // Hash token for lookup
     let mut hasher = Sha256::new();
@@ -184,12 +277,13 @@ pub async fn verify_token(pool: &PgPool, token: &str) -> Result<i32, AppError> {
         SELECT id FROM user_sessions
         WHERE token_hash = $1
         AND user_id = $2
-        AND expires_at > NOW()
+        AND expires_at > NOW() - make_interval(secs => $3)
         AND revoked_at IS NULL
         "#,
     )
     .bind(&token_hash)
     .bind(user_id)
+    .bind(leeway_seconds as f64)
     .fetch_optional(pool)
     .await?;
 
@@ -197,7 +291,7 @@ pub async fn verify_token(pool: &PgPool, token: &str) -> Result<i32, AppError> {
         return Err(AppError::Unauthorized("Session expired or revoked".to_string()));
     }
 
-    Ok(user_id)
+    Ok(claims)
 }
 
 /// Revoke session (logout)
@@ -221,6 +315,254 @@ pub async fn revoke_session(pool: &PgPool, token: &str) -> Result<(), AppError>
     Ok(())
 }
 
+/// Revoke all active sessions of a user (logout from every device)
+///
+/// Returns the number of sessions revoked, including the caller's own.
+pub async fn revoke_all_sessions(executor: impl sqlx::PgExecutor<'_>, user_id: i32) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1
+        AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Make room for one more session under `limit` active sessions per user
+///
+/// With `Reject` the login fails with 409 once the limit is reached; with
+/// `RevokeOldest` every active session except the newest `limit - 1` is revoked
+/// in a single statement. Returns the number of sessions revoked. Run it in the
+/// same transaction as the session insert so concurrent logins cannot both pass.
+pub async fn enforce_session_limit(
+    conn: &mut sqlx::PgConnection,
+    user_id: i32,
+    limit: i64,
+    policy: SessionLimitPolicy,
+) -> Result<u64, AppError> {
+    match policy {
+        SessionLimitPolicy::Reject => {
+            let active: i64 = sqlx::query_scalar(
+                r#"
+                SELECT COUNT(*)
+                FROM user_sessions
+                WHERE user_id = $1
+                AND revoked_at IS NULL
+                AND expires_at > NOW()
+                "#,
+            )
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            if active >= limit {
+                return Err(AppError::SessionLimitReached { user_id, limit });
+            }
+            Ok(0)
+        }
+        SessionLimitPolicy::RevokeOldest => {
+            let result = sqlx::query(
+                r#"
+                UPDATE user_sessions
+                SET revoked_at = NOW()
+                WHERE id IN (
+                    SELECT id
+                    FROM user_sessions
+                    WHERE user_id = $1
+                    AND revoked_at IS NULL
+                    AND expires_at > NOW()
+                    ORDER BY created_at DESC, id DESC
+                    OFFSET $2
+                    FOR UPDATE
+                )
+                "#,
+            )
+            .bind(user_id)
+            .bind(limit - 1)
+            .execute(&mut *conn)
+            .await?;
+
+            if result.rows_affected() > 0 {
+                tracing::info!(
+                    user_id = user_id,
+                    revoked_sessions = result.rows_affected(),
+                    "Session limit reached, oldest sessions revoked"
+                );
+            }
+            Ok(result.rows_affected())
+        }
+    }
+}
+
+// (id, device_info, created_at, revoked_at) sesi untuk timeline aktivitas
+type SessionActivityRow = (i32, Option<String>, DateTime<Utc>, Option<DateTime<Utc>>);
+
+/// Timeline of a user's recent activity, newest first
+///
+/// Merges flights the user created, their logins and logouts, users they created,
+/// and scans from devices they were logged in on (matched by session `device_info`
+/// against `scan_data.device_id`, counting only scans taken while that session was
+/// active, i.e. between its login and its revocation or expiry). Returns one page
+/// (`limit` events after skipping `offset`) of the merged timeline.
+pub async fn get_user_activity(
+    pool: &PgPool,
+    user_id: i32,
+    pagination: Pagination,
+) -> Result<Vec<UserActivityEvent>, AppError> {
+    let Pagination { limit, offset } = pagination;
+    // Tiap sumber cukup diambil limit + offset baris teratas: halaman gabungan pasti
+    // berada di dalamnya
+    let window = limit + offset;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    let flights: Vec<(i32, String, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT id, flight_number, created_at
+        FROM flights
+        WHERE created_by = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(window)
+    .fetch_all(pool)
+    .await?;
+
+    let sessions: Vec<SessionActivityRow> = sqlx::query_as(
+        r#"
+        SELECT id, device_info, created_at, revoked_at
+        FROM user_sessions
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(window)
+    .fetch_all(pool)
+    .await?;
+
+    let created_users: Vec<(i32, String, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT id, username, created_at
+        FROM users
+        WHERE created_by = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(window)
+    .fetch_all(pool)
+    .await?;
+
+    let scans: Vec<(i32, String, i32, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT sd.id, sd.device_id, sd.flight_id, sd.scan_time
+        FROM scan_data sd
+        WHERE EXISTS (
+            SELECT 1 FROM user_sessions us
+            WHERE us.user_id = $1
+              AND us.device_info = sd.device_id
+              AND sd.scan_time >= us.created_at
+              AND sd.scan_time < COALESCE(us.revoked_at, us.expires_at)
+        )
+        ORDER BY sd.scan_time DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(window)
+    .fetch_all(pool)
+    .await?;
+
+    let mut events = Vec::new();
+    for (id, flight_number, created_at) in flights {
+        events.push(UserActivityEvent {
+            event_type: "flight_created".to_string(),
+            occurred_at: created_at,
+            description: format!("Created flight {}", flight_number),
+            reference_id: id,
+        });
+    }
+    for (id, device_info, created_at, revoked_at) in sessions {
+        let device = device_info.unwrap_or_else(|| "unknown device".to_string());
+        events.push(UserActivityEvent {
+            event_type: "login".to_string(),
+            occurred_at: created_at,
+            description: format!("Logged in on {}", device),
+            reference_id: id,
+        });
+        if let Some(revoked_at) = revoked_at {
+            events.push(UserActivityEvent {
+                event_type: "logout".to_string(),
+                occurred_at: revoked_at,
+                description: format!("Session on {} ended", device),
+                reference_id: id,
+            });
+        }
+    }
+    for (id, username, created_at) in created_users {
+        events.push(UserActivityEvent {
+            event_type: "user_created".to_string(),
+            occurred_at: created_at,
+            description: format!("Created user {}", username),
+            reference_id: id,
+        });
+    }
+    for (id, device_id, flight_id, scan_time) in scans {
+        events.push(UserActivityEvent {
+            event_type: "scan".to_string(),
+            occurred_at: scan_time,
+            description: format!("Scan on flight {} from {}", flight_id, device_id),
+            reference_id: id,
+        });
+    }
+
+    // Urutkan terbaru lebih dulu; id sebagai tie-breaker agar urutan stabil
+    events.sort_by(|a, b| {
+        b.occurred_at
+            .cmp(&a.occurred_at)
+            .then_with(|| b.reference_id.cmp(&a.reference_id))
+    });
+
+    Ok(events.into_iter().skip(offset as usize).take(limit as usize).collect())
+}
+
+/// Revoke every active session that was opened from the given device
+///
+/// Matches `device_info` as recorded at login, across all users. Returns the
+/// number of sessions revoked.
+pub async fn revoke_sessions_by_device(pool: &PgPool, device_info: &str) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET revoked_at = NOW()
+        WHERE device_info = $1
+        AND revoked_at IS NULL
+        "#,
+    )
+    .bind(device_info)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 /// Change user password
 pub async fn change_password(
     pool: &PgPool,
@@ -303,43 +645,21 @@ pub async fn reset_user_password(
 // ==================== USER MANAGEMENT FUNCTIONS ====================
 
 /// Create new user
+///
+/// Insert dan re-fetch berjalan dalam satu transaksi. Keunikan username/email
+/// dijamin oleh constraint database, bukan pre-check, sehingga request yang
+/// berjalan bersamaan tidak bisa lolos keduanya.
 pub async fn create_user(
     pool: &PgPool,
     data: CreateUserRequest,
     creator_id: i32,
 ) -> Result<UserWithRole, AppError> {
-    // Check if username already exists
-    let existing = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM users WHERE username = $1"
-    )
-    .bind(&data.username)
-    .fetch_one(pool)
-    .await?;
-
-    if existing > 0 {
-        return Err(AppError::ValidationError(
-            validator::ValidationErrors::new()
-        ));
-    }
-
-    // Check if email already exists
-    let existing_email = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM users WHERE email = $1"
-    )
-    .bind(&data.email)
-    .fetch_one(pool)
-    .await?;
-
-    if existing_email > 0 {
-        return Err(AppError::ValidationError(
-            validator::ValidationErrors::new()
-        ));
-    }
-
-    // Hash password
+    // Hash password di luar transaksi (bcrypt lambat, jangan tahan koneksi)
     let password_hash = hash(&data.password, DEFAULT_COST)
         .map_err(|e| AppError::InternalError(format!("Password hashing failed: {}", e)))?;
 
+    let mut tx = pool.begin().await?;
+
     // Insert user
     let user = sqlx::query_as::<_, User>(
         r#"
@@ -355,14 +675,42 @@ pub async fn create_user(
     .bind(&data.full_name)
     .bind(data.role_id)
     .bind(creator_id)
-    .fetch_one(pool)
-    .await?;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_user_constraint_error)?;
+
+    let created = fetch_user_with_role(&mut tx, user.id).await?;
+    tx.commit().await?;
 
-    get_user_with_role(pool, user.id).await
+    Ok(created)
+}
+
+// Terjemahkan pelanggaran unique constraint tabel users menjadi 409 Conflict
+fn map_user_constraint_error(err: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &err
+        && db_err.is_unique_violation()
+    {
+        let message = match db_err.constraint() {
+            Some("users_username_key") => "Username already exists",
+            Some("users_email_key") => "Email already exists",
+            _ => "User already exists",
+        };
+        return AppError::Conflict(message.to_string());
+    }
+    AppError::DatabaseError(err)
 }
 
 /// Get user with role information
 pub async fn get_user_with_role(pool: &PgPool, user_id: i32) -> Result<UserWithRole, AppError> {
+    let mut conn = pool.acquire().await?;
+    fetch_user_with_role(&mut conn, user_id).await
+}
+
+// Ambil user + role memakai koneksi yang sama (bisa berupa transaksi)
+async fn fetch_user_with_role(
+    conn: &mut sqlx::PgConnection,
+    user_id: i32,
+) -> Result<UserWithRole, AppError> {
     let user = sqlx::query_as::<_, User>(
         r#"
         SELECT id, username, email, password_hash, full_name, role_id, is_active,
@@ -372,7 +720,7 @@ pub async fn get_user_with_role(pool: &PgPool, user_id: i32) -> Result<UserWithR
         "#,
     )
     .bind(user_id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?
     .ok_or(AppError::NotFound(format!("User with id {} not found", user_id)))?;
 
@@ -384,7 +732,7 @@ pub async fn get_user_with_role(pool: &PgPool, user_id: i32) -> Result<UserWithR
         "#,
     )
     .bind(user.role_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
     Ok(UserWithRole {
@@ -404,9 +752,9 @@ pub async fn get_user_with_role(pool: &PgPool, user_id: i32) -> Result<UserWithR
 pub async fn list_users(
     pool: &PgPool,
     query: ListUsersQuery,
+    pagination: Pagination,
 ) -> Result<(Vec<UserWithRole>, i64), AppError> {
-    let limit = query.limit.unwrap_or(100).min(500);
-    let offset = query.offset.unwrap_or(0);
+    let Pagination { limit, offset } = pagination;
 
     let mut conditions = Vec::new();
     let mut count_conditions = Vec::new();
@@ -491,6 +839,7 @@ pub async fn update_user(
     pool: &PgPool,
     user_id: i32,
     data: UpdateUserRequest,
+    revoke_sessions_on_role_change: bool,
 ) -> Result<UserWithRole, AppError> {
     let mut updates = Vec::new();
 
@@ -511,6 +860,36 @@ pub async fn update_user(
         return get_user_with_role(pool, user_id).await;
     }
 
+    // Update role dan pencabutan session harus atomik: jangan sampai role berubah tapi token lama tetap aktif
+    let mut tx = pool.begin().await?;
+
+    // Role tidak dikenal adalah kesalahan input (400), bukan pelanggaran foreign key (500)
+    if let Some(role_id) = data.role_id {
+        let role_exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM roles WHERE id = $1)")
+            .bind(role_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !role_exists {
+            let mut error = validator::ValidationError::new("unknown_role");
+            error.message = Some(format!("Role with id {} does not exist", role_id).into());
+            let mut errors = validator::ValidationErrors::new();
+            errors.add("role_id", error);
+            return Err(AppError::ValidationError(errors));
+        }
+    }
+
+    // Simpan role lama untuk mendeteksi perubahan role
+    let previous_role_id: Option<i32> = match data.role_id {
+        Some(_) => Some(
+            sqlx::query_scalar("SELECT role_id FROM users WHERE id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(AppError::NotFound(format!("User with id {} not found", user_id)))?,
+        ),
+        None => None,
+    };
+
     updates.push("updated_at = NOW()".to_string());
 
     let query_str = format!(
@@ -519,7 +898,19 @@ pub async fn update_user(
         user_id
     );
 
-    sqlx::query(&query_str).execute(pool).await?;
+    sqlx::query(&query_str).execute(&mut *tx).await?;
+
+    // Token lama masih membawa role/permission lama; paksa login ulang jika role berubah
+    if previous_role_id.is_some() && previous_role_id != data.role_id && revoke_sessions_on_role_change {
+        let revoked = revoke_all_sessions(&mut *tx, user_id).await?;
+        tracing::info!(
+            user_id = user_id,
+            revoked_sessions = revoked,
+            "Role changed, user sessions revoked"
+        );
+    }
+
+    tx.commit().await?;
 
     get_user_with_role(pool, user_id).await
 }
@@ -557,6 +948,39 @@ pub async fn list_roles(pool: &PgPool) -> Result<Vec<Role>, AppError> {
     Ok(roles)
 }
 
+/// List all permissions (catalog for role editors), optionally filtered by resource
+pub async fn list_permissions(
+    pool: &PgPool,
+    resource: Option<&str>,
+    pagination: Pagination,
+) -> Result<(Vec<Permission>, i64), AppError> {
+    let Pagination { limit, offset } = pagination;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM permissions WHERE ($1::varchar IS NULL OR resource = $1)",
+    )
+    .bind(resource)
+    .fetch_one(pool)
+    .await?;
+
+    let permissions = sqlx::query_as::<_, Permission>(
+        r#"
+        SELECT id, name, description, resource, action, created_at
+        FROM permissions
+        WHERE ($1::varchar IS NULL OR resource = $1)
+        ORDER BY resource, action
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(resource)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((permissions, total))
+}
+
 /// Get role with permissions
 pub async fn get_role_with_permissions(
     pool: &PgPool,
@@ -621,3 +1045,418 @@ pub async fn get_user_permissions(pool: &PgPool, user_id: i32) -> Result<Vec<Str
 
     Ok(permissions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_SECRET: &str = "test_secret";
+
+    fn mint(issuer: &str, audience: &str, exp_offset_secs: i64) -> String {
+        let now = Utc::now();
+        let claims = crate::models::Claims {
+            sub: 1,
+            username: "admin".to_string(),
+            role: "admin".to_string(),
+            permissions: vec![],
+            exp: now.timestamp() + exp_offset_secs,
+            iat: now.timestamp(),
+            iss: issuer.to_string(),
+            aud: audience.to_string(),
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(TEST_SECRET.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_token_with_matching_issuer_and_audience_validates() {
+        let token = mint("falcon-rest-api", "falcon-mobile", 3600);
+        let validation = jwt_validation("falcon-rest-api", "falcon-mobile", 30);
+
+        let claims = decode_claims(&token, TEST_SECRET, &validation).unwrap();
+        assert_eq!(claims.sub, 1);
+        assert_eq!(claims.aud, "falcon-mobile");
+    }
+
+    #[test]
+    fn test_token_with_wrong_audience_or_issuer_is_rejected() {
+        let validation = jwt_validation("falcon-rest-api", "falcon-mobile", 30);
+
+        let other_audience = mint("falcon-rest-api", "other-service", 3600);
+        assert!(matches!(
+            decode_claims(&other_audience, TEST_SECRET, &validation),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        let other_issuer = mint("other-issuer", "falcon-mobile", 3600);
+        assert!(decode_claims(&other_issuer, TEST_SECRET, &validation).is_err());
+    }
+
+    #[test]
+    fn test_expiry_respects_leeway() {
+        let validation = jwt_validation("falcon-rest-api", "falcon-mobile", 30);
+
+        let just_expired = mint("falcon-rest-api", "falcon-mobile", -10);
+        assert!(decode_claims(&just_expired, TEST_SECRET, &validation).is_ok());
+
+        let long_expired = mint("falcon-rest-api", "falcon-mobile", -120);
+        assert!(decode_claims(&long_expired, TEST_SECRET, &validation).is_err());
+    }
+
+    fn settings() -> AuthSettings {
+        AuthSettings::from_config(&AppConfig::for_tests())
+    }
+
+    /// Log in as the seeded superuser with a known test password
+    async fn login_superuser(pool: &PgPool) -> LoginResponse {
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE username = 'superuser'")
+            .bind(hash("test-password", 4).unwrap())
+            .execute(pool)
+            .await
+            .unwrap();
+
+        authenticate_user(pool, "superuser", "test-password", None, None, &settings())
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_logout_all_revokes_every_session(pool: PgPool) {
+        let login = login_superuser(&pool).await;
+        let user_id = login.user.id;
+        assert_eq!(verify_token(&pool, &login.token, &settings()).await.unwrap(), user_id);
+
+        // Sesi kedua dari device lain
+        sqlx::query(
+            "INSERT INTO user_sessions (user_id, token_hash, expires_at) VALUES ($1, 'other-device', NOW() + INTERVAL '1 day')",
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(revoke_all_sessions(&pool, user_id).await.unwrap(), 2);
+        assert!(matches!(
+            verify_token(&pool, &login.token, &settings()).await,
+            Err(AppError::Unauthorized(_))
+        ));
+        assert_eq!(revoke_all_sessions(&pool, user_id).await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_validate_token_valid_expired_and_revoked(pool: PgPool) {
+        let login = login_superuser(&pool).await;
+        let validation = validate_token(&pool, &login.token, &settings()).await.unwrap();
+        assert!(validation.valid);
+        assert_eq!(validation.user_id, login.user.id);
+        assert_eq!(validation.expires_at.timestamp(), login.expires_at.timestamp());
+
+        // Validasi tidak punya efek samping: token tetap bisa dipakai
+        assert_eq!(verify_token(&pool, &login.token, &settings()).await.unwrap(), login.user.id);
+
+        let now = Utc::now();
+        let expired_claims = crate::models::Claims {
+            sub: login.user.id,
+            username: "superuser".to_string(),
+            role: "admin".to_string(),
+            permissions: vec![],
+            exp: now.timestamp() - 3600,
+            iat: now.timestamp() - 7200,
+            iss: settings().jwt_issuer,
+            aud: settings().jwt_audience,
+        };
+        let expired = encode(
+            &Header::default(),
+            &expired_claims,
+            &EncodingKey::from_secret(get_jwt_secret().as_bytes()),
+        )
+        .unwrap();
+        assert!(matches!(validate_token(&pool, &expired, &settings()).await, Err(AppError::Unauthorized(_))));
+
+        revoke_session(&pool, &login.token).await.unwrap();
+        assert!(matches!(validate_token(&pool, &login.token, &settings()).await, Err(AppError::Unauthorized(_))));
+    }
+
+    async fn active_session_hashes(pool: &PgPool, user_id: i32) -> Vec<String> {
+        sqlx::query_scalar(
+            "SELECT token_hash FROM user_sessions WHERE user_id = $1 AND revoked_at IS NULL ORDER BY id",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn insert_session(pool: &PgPool, user_id: i32, token_hash: &str, age_minutes: i32) {
+        sqlx::query(
+            "INSERT INTO user_sessions (user_id, token_hash, expires_at, created_at) VALUES ($1, $2, NOW() + INTERVAL '1 day', NOW() - make_interval(mins => $3))",
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(age_minutes)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_session_limit_reject_policy(pool: PgPool) {
+        let user_id = login_superuser(&pool).await.user.id;
+        revoke_all_sessions(&pool, user_id).await.unwrap();
+        insert_session(&pool, user_id, "phone", 30).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(enforce_session_limit(&mut conn, user_id, 2, SessionLimitPolicy::Reject).await.unwrap(), 0);
+
+        insert_session(&pool, user_id, "tablet", 10).await;
+        assert!(matches!(
+            enforce_session_limit(&mut conn, user_id, 2, SessionLimitPolicy::Reject).await,
+            Err(AppError::SessionLimitReached { limit: 2, .. })
+        ));
+        assert_eq!(active_session_hashes(&pool, user_id).await, vec!["phone", "tablet"]);
+    }
+
+    #[sqlx::test]
+    async fn test_session_limit_revoke_oldest_policy(pool: PgPool) {
+        let user_id = login_superuser(&pool).await.user.id;
+        revoke_all_sessions(&pool, user_id).await.unwrap();
+        insert_session(&pool, user_id, "oldest", 60).await;
+        insert_session(&pool, user_id, "older", 30).await;
+        insert_session(&pool, user_id, "newest", 5).await;
+
+        // Limit 2: dua sesi tertua dicabut agar ada ruang untuk login baru
+        let mut conn = pool.acquire().await.unwrap();
+        let revoked = enforce_session_limit(&mut conn, user_id, 2, SessionLimitPolicy::RevokeOldest)
+            .await
+            .unwrap();
+        assert_eq!(revoked, 2);
+        assert_eq!(active_session_hashes(&pool, user_id).await, vec!["newest"]);
+    }
+
+    #[sqlx::test]
+    async fn test_login_enforces_configured_session_limit(pool: PgPool) {
+        let user_id = login_superuser(&pool).await.user.id;
+        revoke_all_sessions(&pool, user_id).await.unwrap();
+
+        let login = |max_sessions_per_user, session_limit_policy| {
+            let pool = &pool;
+            async move {
+                let settings = AuthSettings { max_sessions_per_user, session_limit_policy, ..settings() };
+                authenticate_user(pool, "superuser", "test-password", None, None, &settings).await
+            }
+        };
+        login(Some(1), SessionLimitPolicy::Reject).await.unwrap();
+        assert!(matches!(
+            login(Some(1), SessionLimitPolicy::Reject).await,
+            Err(AppError::SessionLimitReached { limit: 1, .. })
+        ));
+        // Tanpa limit dari config, login tetap diizinkan
+        login(None, SessionLimitPolicy::Reject).await.unwrap();
+        assert_eq!(active_session_hashes(&pool, user_id).await.len(), 2);
+
+        // Policy revoke_oldest dari config: login berhasil dan sesi lama dicabut
+        login(Some(1), SessionLimitPolicy::RevokeOldest).await.unwrap();
+        assert_eq!(active_session_hashes(&pool, user_id).await.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_user_activity_is_merged_newest_first(pool: PgPool) {
+        let user_id = login_superuser(&pool).await.user.id;
+        sqlx::query("DELETE FROM user_sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO user_sessions (user_id, token_hash, device_info, expires_at, created_at, revoked_at) VALUES ($1, 'h1', 'scanner-01', NOW() + INTERVAL '1 day', NOW() - INTERVAL '3 hours', NOW() - INTERVAL '1 hour')",
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let flight_id: i32 = sqlx::query_scalar(
+            "INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate, created_by, created_at) VALUES ('GA340', 'GA', 'B738', NOW() + INTERVAL '1 day', 'SUB', 'A1', $1, NOW() - INTERVAL '2 hours') RETURNING id",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ('PAX-A', 'PDF417', 'scanner-01', $1, NOW() - INTERVAL '90 minutes')",
+        )
+        .bind(flight_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        // Scan dari device yang sama setelah logout (operator lain) tidak ikut dihitung
+        sqlx::query(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id, scan_time) VALUES ('PAX-B', 'PDF417', 'scanner-01', $1, NOW() - INTERVAL '30 minutes')",
+        )
+        .bind(flight_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let events = get_user_activity(&pool, user_id, Pagination { limit: 10, offset: 0 }).await.unwrap();
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, vec!["logout", "scan", "flight_created", "login"]);
+
+        let limited = get_user_activity(&pool, user_id, Pagination { limit: 2, offset: 0 }).await.unwrap();
+        assert_eq!(limited.len(), 2);
+        assert_eq!(limited[0].event_type, "logout");
+        // Halaman kedua melanjutkan timeline gabungan
+        let second = get_user_activity(&pool, user_id, Pagination { limit: 2, offset: 2 }).await.unwrap();
+        let types: Vec<&str> = second.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, vec!["flight_created", "login"]);
+
+        assert!(matches!(get_user_activity(&pool, 999_999, Pagination::default()).await, Err(AppError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    async fn test_revoke_sessions_by_device(pool: PgPool) {
+        let user_id = login_superuser(&pool).await.user.id;
+        for (token_hash, device) in [("lost-1", "scanner-07"), ("lost-2", "scanner-07"), ("kept", "scanner-08")] {
+            sqlx::query(
+                "INSERT INTO user_sessions (user_id, token_hash, device_info, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 day')",
+            )
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(device)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(revoke_sessions_by_device(&pool, "scanner-07").await.unwrap(), 2);
+        assert_eq!(revoke_sessions_by_device(&pool, "scanner-07").await.unwrap(), 0);
+
+        let active: Vec<String> = sqlx::query_scalar(
+            "SELECT token_hash FROM user_sessions WHERE device_info IS NOT NULL AND revoked_at IS NULL",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(active, vec!["kept".to_string()]);
+    }
+
+    #[sqlx::test]
+    async fn test_create_user_duplicate_maps_to_conflict(pool: PgPool) {
+        let request = |username: &str, email: &str| CreateUserRequest {
+            username: username.to_string(),
+            email: email.to_string(),
+            password: "password123".to_string(),
+            full_name: "Petugas Gate".to_string(),
+            role_id: 1,
+        };
+        let creator = login_superuser(&pool).await.user.id;
+
+        let created = create_user(&pool, request("gate01", "gate01@example.com"), creator)
+            .await
+            .unwrap();
+        assert_eq!(created.username, "gate01");
+
+        let err = create_user(&pool, request("gate01", "other@example.com"), creator)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(ref msg) if msg == "Username already exists"));
+        assert_eq!(
+            axum::response::IntoResponse::into_response(err).status(),
+            axum::http::StatusCode::CONFLICT
+        );
+
+        let err = create_user(&pool, request("gate02", "gate01@example.com"), creator)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(ref msg) if msg == "Email already exists"));
+
+        // Transaksi yang gagal tidak meninggalkan baris setengah jadi
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE username LIKE 'gate%'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_role_change_revokes_sessions(pool: PgPool) {
+        let login = login_superuser(&pool).await;
+        let user_id = login.user.id;
+
+        // Update tanpa perubahan role tidak memutus sesi
+        let same_role = UpdateUserRequest {
+            email: None,
+            full_name: Some("Super User".to_string()),
+            role_id: Some(login.user.role.id),
+            is_active: None,
+        };
+        update_user(&pool, user_id, same_role, true).await.unwrap();
+        assert_eq!(verify_token(&pool, &login.token, &settings()).await.unwrap(), user_id);
+
+        let other_role: i32 = sqlx::query_scalar("SELECT id FROM roles WHERE id <> $1 ORDER BY id LIMIT 1")
+            .bind(login.user.role.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let new_role = UpdateUserRequest {
+            email: None,
+            full_name: None,
+            role_id: Some(other_role),
+            is_active: None,
+        };
+        let updated = update_user(&pool, user_id, new_role, true).await.unwrap();
+        assert_eq!(updated.role.id, other_role);
+        assert!(matches!(
+            verify_token(&pool, &login.token, &settings()).await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_update_user_unknown_role_is_validation_error(pool: PgPool) {
+        let login = login_superuser(&pool).await;
+        let user_id = login.user.id;
+
+        let unknown_role = UpdateUserRequest {
+            email: None,
+            full_name: Some("Ganti Nama".to_string()),
+            role_id: Some(999_999),
+            is_active: None,
+        };
+        let err = update_user(&pool, user_id, unknown_role, true).await.unwrap_err();
+        assert!(matches!(err, AppError::ValidationError(ref errors) if errors.field_errors().contains_key("role_id")));
+        assert_eq!(
+            axum::response::IntoResponse::into_response(err).status(),
+            axum::http::StatusCode::BAD_REQUEST
+        );
+
+        // Tidak ada perubahan yang tersimpan dan sesi tetap aktif
+        let user = get_user_with_role(&pool, user_id).await.unwrap();
+        assert_eq!(user.role.id, login.user.role.id);
+        assert_ne!(user.full_name, "Ganti Nama");
+        assert_eq!(verify_token(&pool, &login.token, &settings()).await.unwrap(), user_id);
+    }
+
+    #[sqlx::test]
+    async fn test_list_permissions_all_and_by_resource(pool: PgPool) {
+        let (all, all_total) = list_permissions(&pool, None, Pagination::default()).await.unwrap();
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM permissions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(all.len() as i64, total);
+        assert_eq!(all_total, total);
+        assert!(all.iter().any(|p| p.name == "users.create"));
+
+        let (users, _) = list_permissions(&pool, Some("users"), Pagination::default()).await.unwrap();
+        assert!(!users.is_empty());
+        assert!(users.len() < all.len());
+        assert!(users.iter().all(|p| p.resource == "users"));
+
+        // Halaman kedua melanjutkan urutan yang sama, total tetap jumlah keseluruhan
+        let (page, page_total) = list_permissions(&pool, None, Pagination { limit: 2, offset: 1 }).await.unwrap();
+        assert_eq!(page_total, total);
+        assert_eq!(page.iter().map(|p| p.id).collect::<Vec<_>>(), all[1..3].iter().map(|p| p.id).collect::<Vec<_>>());
+
+        let (missing, _) = list_permissions(&pool, Some("no-such-resource"), Pagination::default()).await.unwrap();
+        assert!(missing.is_empty());
+    }
+}
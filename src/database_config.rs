@@ -1,4 +1,7 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
 use crate::config::AppConfig;
 use std::time::Duration;
 
@@ -29,6 +32,10 @@ pub struct DatabaseConfig {
     max_lifetime: Option<Duration>,
     /// Whether to test connections on checkout
     test_on_check_out: bool,
+    /// Probe query for the startup pool test and the health check
+    probe_query: String,
+    /// Extra session settings applied after connect, as (name, value)
+    session_settings: Vec<(String, String)>,
 }
 
 impl Default for DatabaseConfig {
@@ -42,6 +49,8 @@ impl Default for DatabaseConfig {
             idle_timeout: Duration::from_secs(600), // 10 minutes
             max_lifetime: Some(Duration::from_secs(1800)), // 30 minutes
             test_on_check_out: true,
+            probe_query: DEFAULT_PROBE_QUERY.to_string(),
+            session_settings: Vec::new(),
         }
     }
 }
@@ -93,6 +102,8 @@ impl DatabaseConfig {
                 .unwrap_or_else(|_| "true".to_string())
                 .parse()
                 .unwrap_or(true),
+            probe_query: DEFAULT_PROBE_QUERY.to_string(),
+            session_settings: Vec::new(),
         }
     }
 
@@ -106,6 +117,8 @@ impl DatabaseConfig {
             idle_timeout: Duration::from_secs(300), // 5 minutes
             max_lifetime: Some(Duration::from_secs(900)), // 15 minutes
             test_on_check_out: true,
+            probe_query: DEFAULT_PROBE_QUERY.to_string(),
+            session_settings: Vec::new(),
         }
     }
 
@@ -119,9 +132,22 @@ impl DatabaseConfig {
             idle_timeout: Duration::from_secs(300), // 5 minutes
             max_lifetime: Some(Duration::from_secs(600)), // 10 minutes
             test_on_check_out: true,
+            probe_query: DEFAULT_PROBE_QUERY.to_string(),
+            session_settings: Vec::new(),
         }
     }
 
+    /// Use a custom probe query and extra session settings (`name=value` entries)
+    /// Entries that are not `name=value` are skipped; AppConfig already rejects them at startup
+    pub fn with_connection_setup(mut self, probe_query: &str, session_settings: &[String]) -> Self {
+        self.probe_query = probe_query.to_string();
+        self.session_settings = session_settings
+            .iter()
+            .filter_map(|setting| parse_session_setting(setting))
+            .collect();
+        self
+    }
+
     /// Get minimum connections
     pub fn min_connections(&self) -> u32 {
         self.min_connections
@@ -131,12 +157,64 @@ impl DatabaseConfig {
     pub fn max_connections(&self) -> u32 {
         self.max_connections
     }
+
+    /// Snapshot of the pool bounds and timeouts (seconds) for reporting
+    pub fn settings(&self) -> DatabasePoolSettings {
+        DatabasePoolSettings {
+            min_connections: self.min_connections,
+            max_connections: self.max_connections,
+            connect_timeout_secs: self.connect_timeout.as_secs(),
+            acquire_timeout_secs: self.acquire_timeout.as_secs(),
+            idle_timeout_secs: self.idle_timeout.as_secs(),
+            max_lifetime_secs: self.max_lifetime.map(|d| d.as_secs()),
+            test_on_check_out: self.test_on_check_out,
+        }
+    }
+}
+
+/// Default connection probe
+pub const DEFAULT_PROBE_QUERY: &str = "SELECT 1";
+
+/// Parse a `name=value` session setting; the name must be a Postgres parameter name
+/// (letters, digits, `_` and `.`), the value is passed as-is to `set_config`
+pub fn parse_session_setting(setting: &str) -> Option<(String, String)> {
+    let (name, value) = setting.split_once('=')?;
+    let name = name.trim();
+    let value = value.trim();
+    let valid_name = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !valid_name || value.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Serializable view of [`DatabaseConfig`]
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabasePoolSettings {
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub connect_timeout_secs: u64,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub max_lifetime_secs: Option<u64>,
+    pub test_on_check_out: bool,
 }
 
 /// Create database connection pool with optimized settings
 pub async fn create_connection_pool(
     database_url: &str,
     config: &DatabaseConfig,
+) -> Result<PgPool, sqlx::Error> {
+    let connect_options: PgConnectOptions = database_url.parse()?;
+    create_connection_pool_with(connect_options, config).await
+}
+
+/// Same as `create_connection_pool`, for connect options that are already parsed
+async fn create_connection_pool_with(
+    connect_options: PgConnectOptions,
+    config: &DatabaseConfig,
 ) -> Result<PgPool, sqlx::Error> {
     tracing::info!(
         min_connections = config.min_connections,
@@ -148,6 +226,8 @@ pub async fn create_connection_pool(
         "Creating database connection pool"
     );
 
+    let session_settings = std::sync::Arc::new(config.session_settings.clone());
+
     // Note: SQLx 0.8 simplified pool options - some methods removed
     let pool: Result<PgPool, sqlx::Error> = PgPoolOptions::new()
         .min_connections(config.min_connections)
@@ -155,20 +235,32 @@ pub async fn create_connection_pool(
         .acquire_timeout(config.acquire_timeout)
         .idle_timeout(Some(config.idle_timeout))
         .max_lifetime(config.max_lifetime)
-        .after_connect(|conn, _meta| Box::pin(async move {
-            // Set connection parameters
-            sqlx::query("SET timezone = 'UTC'")
-                .execute(&mut *conn)
-                .await?;
+        .after_connect(move |conn, _meta| {
+            let session_settings = session_settings.clone();
+            Box::pin(async move {
+                // Set connection parameters
+                sqlx::query("SET timezone = 'UTC'")
+                    .execute(&mut *conn)
+                    .await?;
 
-            sqlx::query("SET application_name = 'falcon-rest-api'")
-                .execute(&mut *conn)
-                .await?;
+                sqlx::query("SET application_name = 'falcon-rest-api'")
+                    .execute(&mut *conn)
+                    .await?;
 
-            tracing::debug!("Database connection established with timezone UTC");
-            Ok(())
-        }))
-        .connect(database_url)
+                // Setting tambahan dari DB_SESSION_SETTINGS (mis. statement_timeout untuk managed Postgres)
+                for (name, value) in session_settings.iter() {
+                    sqlx::query("SELECT set_config($1, $2, false)")
+                        .bind(name)
+                        .bind(value)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+
+                tracing::debug!("Database connection established with timezone UTC");
+                Ok(())
+            })
+        })
+        .connect_with(connect_options)
         .await;
 
     match pool {
@@ -176,7 +268,7 @@ pub async fn create_connection_pool(
             tracing::info!("Database connection pool created successfully");
 
             // Test the pool
-            if let Err(e) = test_pool(&pool).await {
+            if let Err(e) = test_pool(&pool, &config.probe_query).await {
                 tracing::error!("Database pool test failed: {:?}", e);
                 return Err(e);
             }
@@ -192,10 +284,10 @@ pub async fn create_connection_pool(
 }
 
 /// Test database connection pool
-async fn test_pool(pool: &PgPool) -> Result<(), sqlx::Error> {
-    // Test basic query
-    let result = sqlx::query("SELECT 1 as test")
-        .fetch_one(pool)
+async fn test_pool(pool: &PgPool, probe_query: &str) -> Result<(), sqlx::Error> {
+    // Test basic query (DB_PROBE_QUERY, default SELECT 1)
+    let result = sqlx::query(probe_query)
+        .fetch_optional(pool)
         .await;
 
     match result {
@@ -212,21 +304,22 @@ async fn test_pool(pool: &PgPool) -> Result<(), sqlx::Error> {
 
 /// Get database connection pool configuration based on app config
 pub fn get_database_config(app_config: &AppConfig) -> DatabaseConfig {
-    if app_config.is_production() {
+    let config = if app_config.is_production() {
         DatabaseConfig::production()
     } else if app_config.is_development() {
         DatabaseConfig::development()
     } else {
         // Staging - use defaults
         DatabaseConfig::default()
-    }
+    };
+    config.with_connection_setup(&app_config.db_probe_query, &app_config.db_session_settings)
 }
 
 /// Database health check
-pub async fn health_check(pool: &PgPool) -> DatabaseHealth {
+pub async fn health_check(pool: &PgPool, probe_query: &str) -> DatabaseHealth {
     let start = std::time::Instant::now();
     
-    match test_pool(pool).await {
+    match test_pool(pool, probe_query).await {
         Ok(_) => {
             DatabaseHealth {
                 is_healthy: true,
@@ -306,6 +399,26 @@ mod tests {
         assert!(config.test_on_check_out);
     }
 
+    #[sqlx::test]
+    async fn test_custom_session_settings_applied_on_connect(_: PgPoolOptions, connect_options: PgConnectOptions) {
+        let settings = vec!["statement_timeout=1234ms".to_string(), "lock_timeout = 2s".to_string()];
+        let mut config = DatabaseConfig::default().with_connection_setup("SELECT 1 WHERE false", &settings);
+        config.min_connections = 1;
+        config.max_connections = 1;
+
+        // Probe tanpa baris hasil tetap dianggap sehat
+        let pool = create_connection_pool_with(connect_options, &config).await.unwrap();
+        assert!(health_check(&pool, &config.probe_query).await.is_healthy);
+
+        let statement_timeout: String = sqlx::query_scalar("SHOW statement_timeout").fetch_one(&pool).await.unwrap();
+        let lock_timeout: String = sqlx::query_scalar("SHOW lock_timeout").fetch_one(&pool).await.unwrap();
+        assert_eq!(statement_timeout, "1234ms");
+        assert_eq!(lock_timeout, "2s");
+
+        assert_eq!(parse_session_setting("statement_timeout"), None);
+        assert_eq!(parse_session_setting("bad name;DROP=1"), None);
+    }
+
     #[test]
     fn test_min_max_connections() {
         let mut config = DatabaseConfig::from_env();
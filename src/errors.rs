@@ -1,25 +1,52 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use validator::ValidationErrors;
 
+// Saran jeda (detik) sebelum client mencoba lagi saat server menolak karena beban
+// (pool koneksi database habis, database tidak sehat)
+pub const LOAD_SHED_RETRY_AFTER_SECS: u64 = 5;
+
 // Enum untuk menangani berbagai jenis error di aplikasi
 #[derive(Debug)]
 #[allow(dead_code)] // Some variants are reserved for future use
 pub enum AppError {
     DatabaseError(sqlx::Error),
     ValidationError(ValidationErrors),
+    BulkValidationError(Vec<crate::models::BulkValidationFailure>),
     FlightNotFound,
     DuplicateFlight,
     DuplicateScan { barcode: String, flight_id: i32, existing_scan_id: i32 },
+    DeviceNotAllowed { device_id: String, flight_id: i32 },
     InvalidDepartureTime,
+    InvalidScanTime,
+    TimestampSkew { field: String, skew_seconds: i64, max_skew_seconds: i64 },
     InvalidBarcodeFormat,
+    // Input barcode rusak encoding-nya (mis. byte latin-1 dari scanner)
+    InvalidBarcodeEncoding(crate::barcode_parser::EncodingIssue),
+    // Format barcode diterima saat scan, tapi belum ada decoder-nya (mis. QR)
+    UnsupportedBarcodeFormat(String),
+    // Array bulk melebihi MAX_BULK_ITEMS, ditolak sebelum menyentuh database
+    PayloadTooLarge { items: usize, max_items: usize },
+    InvalidPathParam(String),
+    InvalidQueryParam(String),
+    UnknownFields(Vec<String>),
     // Authentication errors
     Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
+    Conflict(String),
+    // Login ditolak karena MAX_SESSIONS_PER_USER tercapai (SESSION_LIMIT_POLICY=reject)
+    SessionLimitReached { user_id: i32, limit: i64 },
+    // Bulk sync dengan on_conflict=fail_on_conflict menemukan flight yang sudah ada
+    BulkConflict(Vec<crate::models::BulkConflict>),
+    // Load shedding: 503 dengan header Retry-After
+    ServiceUnavailable { reason: String, retry_after_secs: u64 },
+    // Handler melebihi REQUEST_TIMEOUT_SECONDS
+    RequestTimeout { timeout_secs: u64 },
     InternalError(String),
     // Tambahkan jenis error lain di sini jika diperlukan
 }
@@ -27,7 +54,14 @@ pub enum AppError {
 // Implementasi konversi dari error lain ke AppError
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
-        AppError::DatabaseError(err)
+        match err {
+            // Semua koneksi sedang dipakai: server kelebihan beban, bukan error internal
+            sqlx::Error::PoolTimedOut => AppError::ServiceUnavailable {
+                reason: "database_pool_exhausted".to_string(),
+                retry_after_secs: LOAD_SHED_RETRY_AFTER_SECS,
+            },
+            err => AppError::DatabaseError(err),
+        }
     }
 }
 
@@ -40,6 +74,10 @@ impl From<ValidationErrors> for AppError {
 // Implementasi bagaimana AppError diubah menjadi HTTP Response
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::ServiceUnavailable { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        };
         let (status, error_message, error_code, details) = match self {
             AppError::DatabaseError(ref e) => {
                 tracing::error!(
@@ -67,6 +105,34 @@ impl IntoResponse for AppError {
                     json!({ "details": e.field_errors() }),
                 )
             }
+            AppError::BulkValidationError(ref failures) => {
+                tracing::warn!(
+                    invalid_count = failures.len(),
+                    invalid_indices = ?failures.iter().map(|f| f.index).collect::<Vec<_>>(),
+                    error_type = "BulkValidationError",
+                    "Bulk request validation failed"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Validation failed for {} item(s)", failures.len()),
+                    "VALIDATION_ERROR".to_string(),
+                    json!({ "errors": failures }),
+                )
+            }
+            AppError::BulkConflict(ref conflicts) => {
+                tracing::warn!(
+                    conflict_count = conflicts.len(),
+                    flight_numbers = ?conflicts.iter().map(|c| c.flight_number.as_str()).collect::<Vec<_>>(),
+                    error_type = "BulkConflict",
+                    "Bulk sync aborted on existing flights"
+                );
+                (
+                    StatusCode::CONFLICT,
+                    format!("{} flight(s) already exist", conflicts.len()),
+                    "BULK_CONFLICT".to_string(),
+                    json!({ "conflicts": conflicts }),
+                )
+            }
             AppError::FlightNotFound => {
                 tracing::warn!(
                     error_type = "FlightNotFound",
@@ -94,7 +160,7 @@ impl IntoResponse for AppError {
             AppError::DuplicateScan { ref barcode, flight_id, existing_scan_id } => {
                 tracing::info!(
                     error_type = "DuplicateScan",
-                    barcode = %barcode,
+                    barcode = %crate::logging::redact_pii(barcode),
                     flight_id = flight_id,
                     existing_scan_id = existing_scan_id,
                     "Duplicate scan detected - barcode already scanned for this flight"
@@ -109,6 +175,23 @@ impl IntoResponse for AppError {
                     }),
                 )
             }
+            AppError::DeviceNotAllowed { ref device_id, flight_id } => {
+                tracing::warn!(
+                    error_type = "DeviceNotAllowed",
+                    device_id = %device_id,
+                    flight_id = flight_id,
+                    "Device is not on the flight's allowlist"
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    "Device is not allowed to scan this flight".to_string(),
+                    "DEVICE_NOT_ALLOWED".to_string(),
+                    json!({
+                        "device_id": device_id,
+                        "flight_id": flight_id
+                    }),
+                )
+            }
             AppError::InvalidDepartureTime => {
                 tracing::warn!(
                     error_type = "InvalidDepartureTime",
@@ -121,6 +204,37 @@ impl IntoResponse for AppError {
                     json!({}),
                 )
             }
+            AppError::InvalidScanTime => {
+                tracing::warn!(
+                    error_type = "InvalidScanTime",
+                    "Scan time in the future received"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Scan time cannot be in the future".to_string(),
+                    "INVALID_SCAN_TIME".to_string(),
+                    json!({}),
+                )
+            }
+            AppError::TimestampSkew { ref field, skew_seconds, max_skew_seconds } => {
+                tracing::warn!(
+                    error_type = "TimestampSkew",
+                    field = %field,
+                    skew_seconds = skew_seconds,
+                    max_skew_seconds = max_skew_seconds,
+                    "Submitted timestamp too far from server time (device clock wrong?)"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("{} is too far from server time; check the device clock", field),
+                    "TIMESTAMP_SKEW".to_string(),
+                    json!({
+                        "field": field,
+                        "skew_seconds": skew_seconds,
+                        "max_skew_seconds": max_skew_seconds
+                    }),
+                )
+            }
             AppError::InvalidBarcodeFormat => {
                 tracing::warn!(
                     error_type = "InvalidBarcodeFormat",
@@ -133,6 +247,92 @@ impl IntoResponse for AppError {
                     json!({}),
                 )
             }
+            AppError::UnsupportedBarcodeFormat(ref format) => {
+                tracing::warn!(
+                    error_type = "UnsupportedBarcodeFormat",
+                    format = %format,
+                    "No decoder for barcode format"
+                );
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("No decoder available for barcode format {}", format),
+                    "UNSUPPORTED_BARCODE_FORMAT".to_string(),
+                    json!({ "format": format }),
+                )
+            }
+            AppError::PayloadTooLarge { items, max_items } => {
+                tracing::warn!(
+                    error_type = "PayloadTooLarge",
+                    items = items,
+                    max_items = max_items,
+                    "Bulk request exceeds item limit"
+                );
+                (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("Bulk request contains {} items, maximum is {}", items, max_items),
+                    "PAYLOAD_TOO_LARGE".to_string(),
+                    json!({ "items": items, "max_items": max_items }),
+                )
+            }
+            AppError::InvalidBarcodeEncoding(ref issue) => {
+                tracing::warn!(
+                    error_type = "InvalidBarcodeEncoding",
+                    replacement_chars = issue.replacement_chars,
+                    dropped_chars = issue.dropped_chars,
+                    first_position = issue.first_position,
+                    "Barcode input has encoding damage"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Barcode contains characters that are not valid text; check the scanner's character encoding (expected UTF-8/ASCII)".to_string(),
+                    "INVALID_BARCODE_ENCODING".to_string(),
+                    json!({
+                        "replacement_chars": issue.replacement_chars,
+                        "dropped_chars": issue.dropped_chars,
+                        "first_position": issue.first_position,
+                        "usable_chars": issue.usable_chars,
+                    }),
+                )
+            }
+            AppError::UnknownFields(ref fields) => {
+                tracing::warn!(
+                    error_type = "UnknownFields",
+                    fields = ?fields,
+                    "Request body contains unknown fields (strict mode)"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Request body contains unknown fields".to_string(),
+                    "UNKNOWN_FIELDS".to_string(),
+                    json!({ "fields": fields }),
+                )
+            }
+            AppError::InvalidPathParam(ref msg) => {
+                tracing::warn!(
+                    error_type = "InvalidPathParam",
+                    message = %msg,
+                    "Invalid path parameter"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Invalid path parameter".to_string(),
+                    "INVALID_PATH_PARAM".to_string(),
+                    json!({ "detail": msg }),
+                )
+            }
+            AppError::InvalidQueryParam(ref msg) => {
+                tracing::warn!(
+                    error_type = "InvalidQueryParam",
+                    message = %msg,
+                    "Invalid query parameter"
+                );
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Invalid query parameter".to_string(),
+                    "INVALID_QUERY_PARAM".to_string(),
+                    json!({ "detail": msg }),
+                )
+            }
             AppError::Unauthorized(ref msg) => {
                 tracing::warn!(
                     error_type = "Unauthorized",
@@ -146,6 +346,19 @@ impl IntoResponse for AppError {
                     json!({}),
                 )
             }
+            AppError::Forbidden(ref msg) => {
+                tracing::warn!(
+                    error_type = "Forbidden",
+                    message = %msg,
+                    "Insufficient permissions"
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    msg.clone(),
+                    "FORBIDDEN".to_string(),
+                    json!({}),
+                )
+            }
             AppError::NotFound(ref msg) => {
                 tracing::warn!(
                     error_type = "NotFound",
@@ -159,6 +372,66 @@ impl IntoResponse for AppError {
                     json!({}),
                 )
             }
+            AppError::Conflict(ref msg) => {
+                tracing::warn!(
+                    error_type = "Conflict",
+                    message = %msg,
+                    "Resource already exists"
+                );
+                (
+                    StatusCode::CONFLICT,
+                    msg.clone(),
+                    "CONFLICT".to_string(),
+                    json!({}),
+                )
+            }
+            AppError::SessionLimitReached { user_id, limit } => {
+                tracing::warn!(
+                    error_type = "SessionLimitReached",
+                    user_id = user_id,
+                    limit = limit,
+                    "Login rejected, active session limit reached"
+                );
+                (
+                    StatusCode::CONFLICT,
+                    format!(
+                        "Maximum of {} active session(s) reached; log out on another device first",
+                        limit
+                    ),
+                    "SESSION_LIMIT_REACHED".to_string(),
+                    json!({ "max_sessions": limit }),
+                )
+            }
+            AppError::ServiceUnavailable { ref reason, retry_after_secs } => {
+                tracing::warn!(
+                    error_type = "ServiceUnavailable",
+                    reason = %reason,
+                    retry_after_secs = retry_after_secs,
+                    "Request shed under load"
+                );
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Service temporarily unavailable, retry later".to_string(),
+                    "SERVICE_UNAVAILABLE".to_string(),
+                    json!({
+                        "reason": reason,
+                        "retry_after_secs": retry_after_secs
+                    }),
+                )
+            }
+            AppError::RequestTimeout { timeout_secs } => {
+                tracing::error!(
+                    error_type = "RequestTimeout",
+                    timeout_secs = timeout_secs,
+                    "Handler exceeded request timeout"
+                );
+                (
+                    StatusCode::GATEWAY_TIMEOUT,
+                    "Request took too long to process".to_string(),
+                    "REQUEST_TIMEOUT".to_string(),
+                    json!({ "timeout_secs": timeout_secs }),
+                )
+            }
             AppError::InternalError(ref msg) => {
                 tracing::error!(
                     error_type = "InternalError",
@@ -181,6 +454,29 @@ impl IntoResponse for AppError {
             "details": details
         }));
 
-        (status, body).into_response()
+        match retry_after {
+            Some(secs) => (status, [(header::RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_timeout_sheds_with_retry_after() {
+        let response = AppError::from(sqlx::Error::PoolTimedOut).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap(),
+            LOAD_SHED_RETRY_AFTER_SECS.to_string().as_str()
+        );
+
+        // Error database lain tetap 500 tanpa Retry-After
+        let response = AppError::from(sqlx::Error::RowNotFound).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
     }
 }
@@ -0,0 +1,333 @@
+use axum::{
+    extract::{rejection::PathRejection, FromRequest, FromRequestParts, OptionalFromRequestParts, Query, Request},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    config::AppConfig,
+    errors::AppError,
+    models::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+};
+
+// Pengganti axum::extract::Path supaya segmen path yang tidak valid (mis. /api/flights/abc)
+// menghasilkan envelope error standar dengan code INVALID_PATH_PARAM
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::InvalidPathParam(rejection.body_text())
+    }
+}
+
+// Parameter limit/offset untuk semua list endpoint. Default dan batas atas limit diambil dari
+// AppConfig (DEFAULT_PAGE_SIZE / MAX_PAGE_SIZE); limit di atas batas dipotong, sedangkan
+// limit < 1 atau offset negatif ditolak dengan 400 INVALID_QUERY_PARAM
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pagination {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaginationParams {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl Pagination {
+    pub fn new(
+        limit: Option<i64>,
+        offset: Option<i64>,
+        default_limit: i64,
+        max_limit: i64,
+    ) -> Result<Self, AppError> {
+        let limit = limit.unwrap_or(default_limit);
+        if limit < 1 {
+            return Err(AppError::InvalidQueryParam(format!("limit must be at least 1, got {}", limit)));
+        }
+        let offset = offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(AppError::InvalidQueryParam(format!("offset must not be negative, got {}", offset)));
+        }
+        Ok(Self { limit: limit.min(max_limit), offset })
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self { limit: DEFAULT_PAGE_SIZE, offset: 0 }
+    }
+}
+
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PaginationParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| AppError::InvalidQueryParam(rejection.body_text()))?;
+
+        let (default_limit, max_limit) = parts
+            .extensions
+            .get::<AppConfig>()
+            .map(|config| (config.default_page_size, config.max_page_size))
+            .unwrap_or((DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE));
+
+        Self::new(params.limit, params.offset, default_limit, max_limit)
+    }
+}
+
+// `Option<Pagination>` untuk endpoint yang secara default tidak dipaginasi (mis. GET /api/flights):
+// None jika limit dan offset sama-sama tidak dikirim, selain itu divalidasi seperti Pagination
+impl<S> OptionalFromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let Query(params) = Query::<PaginationParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| AppError::InvalidQueryParam(rejection.body_text()))?;
+        if params.limit.is_none() && params.offset.is_none() {
+            return Ok(None);
+        }
+
+        <Self as FromRequestParts<S>>::from_request_parts(parts, state).await.map(Some)
+    }
+}
+
+// Pengganti axum::Json untuk endpoint create. Default lenient (field tak dikenal diabaikan);
+// jika STRICT_JSON_FIELDS=true, field yang tidak dikenal model ditolak dengan 400 UNKNOWN_FIELDS
+pub struct CreateJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for CreateJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let strict = req
+            .extensions()
+            .get::<AppConfig>()
+            .map(|config| config.strict_json_fields)
+            .unwrap_or(false);
+
+        if !strict {
+            let Json(payload) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(payload));
+        }
+
+        let Json(value) = Json::<serde_json::Value>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let mut unknown_fields = Vec::new();
+        let payload = serde_ignored::deserialize(value, |path| unknown_fields.push(path.to_string()))
+            .map_err(|e| {
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("Failed to deserialize the JSON body into the target type: {}", e),
+                )
+                    .into_response()
+            })?;
+
+        if !unknown_fields.is_empty() {
+            return Err(AppError::UnknownFields(unknown_fields).into_response());
+        }
+
+        Ok(Self(payload))
+    }
+}
+
+// Body opsional (Option<CreateJson<T>>): request tanpa Content-Type dianggap tanpa body (None),
+// selain itu diparse persis seperti CreateJson
+impl<T, S> axum::extract::OptionalFromRequest<S> for CreateJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !req.headers().contains_key(header::CONTENT_TYPE) {
+            return Ok(None);
+        }
+        <Self as FromRequest<S>>::from_request(req, state).await.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::{Request, StatusCode}, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn echo_id(Path(id): Path<i32>) -> String {
+        id.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_non_numeric_id_returns_standard_error() {
+        let app = Router::new().route("/api/flights/{id}", get(echo_id));
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/flights/abc").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["code"], "INVALID_PATH_PARAM");
+        assert!(json["message"].is_string());
+        assert!(json["details"].is_object());
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct ScanBody {
+        barcode_value: String,
+    }
+
+    async fn post_scan(strict: bool) -> (StatusCode, serde_json::Value) {
+        let config = AppConfig {
+            strict_json_fields: strict,
+            ..AppConfig::for_tests()
+        };
+        let app = Router::new()
+            .route("/api/scan-data", axum::routing::post(|CreateJson(_): CreateJson<ScanBody>| async { "ok" }))
+            .layer(axum::Extension(config));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/scan-data")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"barcodeValue":"M1TEST","confidenceScore":0.9}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields_lenient_vs_strict() {
+        let (status, _) = post_scan(false).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, json) = post_scan(true).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "UNKNOWN_FIELDS");
+        assert_eq!(json["details"]["fields"], serde_json::json!(["confidenceScore"]));
+    }
+
+    #[tokio::test]
+    async fn test_optional_create_json_without_body() {
+        let app = Router::new().route(
+            "/api/admin/purge-operational-data",
+            axum::routing::post(|payload: Option<CreateJson<ScanBody>>| async move {
+                payload.map_or("none".to_string(), |CreateJson(body)| body.barcode_value)
+            }),
+        );
+        let send = |request: Request<Body>| async {
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        };
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/admin/purge-operational-data")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(request).await, (StatusCode::OK, "none".to_string()));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/admin/purge-operational-data")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"barcodeValue":"M1TEST"}"#))
+            .unwrap();
+        assert_eq!(send(request).await, (StatusCode::OK, "M1TEST".to_string()));
+    }
+
+    async fn paginate(uri: &str) -> (StatusCode, String) {
+        let config = AppConfig {
+            max_page_size: 200,
+            ..AppConfig::for_tests()
+        };
+        let app = Router::new()
+            .route("/api/decoded-barcodes", get(|p: Pagination| async move { format!("{},{}", p.limit, p.offset) }))
+            .layer(axum::Extension(config));
+
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pagination_default_and_clamped() {
+        assert_eq!(paginate("/api/decoded-barcodes").await, (StatusCode::OK, "100,0".to_string()));
+        assert_eq!(
+            paginate("/api/decoded-barcodes?limit=25&offset=50&airline_code=GA").await,
+            (StatusCode::OK, "25,50".to_string())
+        );
+        assert_eq!(paginate("/api/decoded-barcodes?limit=10000").await, (StatusCode::OK, "200,0".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_pagination_rejects_invalid_values() {
+        for uri in [
+            "/api/decoded-barcodes?limit=-5",
+            "/api/decoded-barcodes?limit=0",
+            "/api/decoded-barcodes?offset=-1",
+            "/api/decoded-barcodes?limit=abc",
+        ] {
+            let (status, body) = paginate(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(json["code"], "INVALID_QUERY_PARAM");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optional_pagination_only_when_requested() {
+        let app = Router::new()
+            .route("/api/flights", get(|p: Option<Pagination>| async move { format!("{:?}", p.map(|p| (p.limit, p.offset))) }))
+            .layer(axum::Extension(AppConfig::for_tests()));
+        let call = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        assert_eq!(call("/api/flights?date=2030-01-01").await, (StatusCode::OK, "None".to_string()));
+        assert_eq!(call("/api/flights?offset=20").await, (StatusCode::OK, "Some((100, 20))".to_string()));
+        // Validasi sama dengan Pagination biasa
+        assert_eq!(call("/api/flights?limit=0").await.0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,18 +1,35 @@
 use crate::{
+    barcode_parser,
+    config::{AppConfig, EffectiveConfig},
     database,
-    errors::AppError,
+    errors::{AppError, LOAD_SHED_RETRY_AFTER_SECS},
+    extractors::{CreateJson, Pagination, Path},
+    logging,
+    openapi,
+    stats_cache::StatsCache,
     models::{
         ApiResponse, CreateFlight, ScanDataInput, ScanData, Flight, FlightStatistics, GetFlightsQuery,
         GetScanDataQuery, SyncFlightsQuery, UpdateFlight, DecodedBarcode, DecodeRequest,
-        GetDecodedBarcodesQuery, DecodedStatistics, CreateRejectionLog, RejectionLog, RejectionLogQuery,
-        AirportCode, AirlineCode, CabinClassCode,
+        GetDecodedBarcodesQuery, DecodedStatistics, DecodedDuplicate, DecodedStatisticsBatchRequest, CreateRejectionLog, RejectionLog, RejectionLogEntry, RejectionLogQuery,
+        AirportCode, AirlineCode, CabinClassCode, BulkSyncQuery, BulkSyncSummary, BulkConflictMode, BoardingProgress,
+        BulkDuplicate, BulkDuplicateMode, DecodedFlightMismatch,
+        FlightStatisticsQuery, AllowedDevicesRequest, FlightDetail, BulkValidationFailure,
+        ExportFlightsQuery, ExportFormat, DecodedStatisticsByAirlineQuery, AirlineDecodeStatistics, DetectFormatRequest, DetectFormatResponse, ApiVersionInfo,
+        CreateScanQuery, ScanCreated, DeviceFlightStatisticsQuery, DeviceFlightStatistics, UnscannedFlightsQuery, DecodeDiagnostics,
+        DashboardSummaryQuery, DashboardSummary, UpdateDecodedBarcode, FlightLookupQuery,
+        ReconcileRequest, ManifestReconciliation, BoardingTiming, PurgeOperationalDataRequest, PurgeOperationalDataSummary,
+        BarcodeValueQuery, UpsertAirportCode, UpsertAirlineCode, UpsertCabinClassCode, CodeImportSummary, format_timestamp,
     },
 };
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
     Json,
 };
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use validator::Validate;
 
@@ -32,7 +49,9 @@ use validator::Validate;
 )]
 pub async fn create_flight(
     State(pool): State<PgPool>,
-    Json(payload): Json<CreateFlight>,
+    Extension(config): Extension<AppConfig>,
+    Extension(user_id): Extension<i32>,
+    CreateJson(payload): CreateJson<CreateFlight>,
 ) -> Result<(StatusCode, Json<ApiResponse<Flight>>), AppError> {
     tracing::info!(
         flight_number = %payload.flight_number,
@@ -50,7 +69,9 @@ pub async fn create_flight(
         return Err(AppError::ValidationError(validation_errors));
     }
 
-    let new_flight = database::create_flight(&pool, payload).await?;
+    check_flight_timestamps(&payload, "", config.max_timestamp_skew_seconds)?;
+
+    let new_flight = database::create_flight(&pool, payload, Some(user_id)).await?;
 
     tracing::info!(
         flight_id = new_flight.id,
@@ -58,41 +79,163 @@ pub async fn create_flight(
         "Flight created successfully"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Flight created successfully".to_string()),
-        data: Some(new_flight),
-        total: None,
-    };
+    let response = ApiResponse::success(new_flight).with_message("Flight created successfully");
 
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// Page to fetch for a list that is unpaginated by default
+///
+/// An explicit `limit`/`offset` is used as-is. Without one, the whole result is
+/// returned (`None`) unless `total` exceeds the cap, in which case it is cut to
+/// one default page.
+fn list_response_page(
+    endpoint: &str,
+    total: i64,
+    pagination: Option<Pagination>,
+    config: &AppConfig,
+) -> Option<Pagination> {
+    if pagination.is_some() || total <= config.list_response_cap {
+        return pagination;
+    }
+    tracing::warn!(
+        endpoint = endpoint,
+        total = total,
+        cap = config.list_response_cap,
+        page_size = config.default_page_size,
+        "Unpaginated list exceeds LIST_RESPONSE_CAP, returning first page only"
+    );
+    Some(Pagination { limit: config.default_page_size, offset: 0 })
+}
+
 /// Get all flights with optional date filter
+///
+/// A date with no flights is not an error: the response is 200 with `data: []` and `total: 0`.
 #[utoipa::path(
     get,
     path = "/api/flights",
     tag = "Flights",
     params(
-        ("date" = Option<String>, Query, description = "Filter by date (YYYY-MM-DD)")
+        ("date" = Option<String>, Query, description = "Filter by date (YYYY-MM-DD)"),
+        ("count_only" = Option<bool>, Query, description = "Return only the total count, without rows"),
+        ("limit" = Option<i64>, Query, description = "Page size (clamped to MAX_PAGE_SIZE); omit with offset for the whole list"),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination")
     ),
     responses(
-        (status = 200, description = "List of flights", body = Vec<Flight>),
+        (status = 200, description = "List of flights (capped to the first page with hasMore when unpaginated and above LIST_RESPONSE_CAP); empty list with total 0 when nothing matches", body = Vec<Flight>),
+        (status = 400, description = "Invalid limit/offset"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_flights(
     State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    pagination: Option<Pagination>,
     Query(query): Query<GetFlightsQuery>,
 ) -> Result<Json<ApiResponse<Vec<Flight>>>, AppError> {
-    let (flights, total) = database::get_all_flights(&pool, query.date).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(flights),
-        total: Some(total as u64),
-    };
-    Ok(Json(response))
+    let total = database::count_all_flights(&pool, query.date).await?;
+    if query.count_only.unwrap_or(false) {
+        return Ok(Json(ApiResponse::count(total as u64)));
+    }
+    let page = list_response_page("/api/flights", total, pagination, &config);
+    let flights = database::get_all_flights(&pool, query.date, page).await?;
+    let has_more = page.map(|p| p.offset + (flights.len() as i64) < total);
+    Ok(Json(ApiResponse::list(flights, total as u64, has_more)))
+}
+
+/// Export flights departing in a date range as JSON or CSV
+///
+/// `from` and `to` are inclusive (UTC dates). CSV is streamed row by row.
+#[utoipa::path(
+    get,
+    path = "/api/flights/export",
+    tag = "Flights",
+    params(
+        ("from" = String, Query, description = "First departure date (YYYY-MM-DD)"),
+        ("to" = String, Query, description = "Last departure date (YYYY-MM-DD)"),
+        ("format" = Option<ExportFormat>, Query, description = "json (default) or csv"),
+        ("include_inactive" = Option<bool>, Query, description = "Include soft-deleted flights")
+    ),
+    responses(
+        (status = 200, description = "Flights in range (JSON envelope or text/csv)", body = Vec<Flight>),
+        (status = 400, description = "Invalid date range"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn export_flights(
+    State(pool): State<PgPool>,
+    Query(query): Query<ExportFlightsQuery>,
+) -> Result<Response, AppError> {
+    if query.from > query.to {
+        let mut errors = validator::ValidationErrors::new();
+        let mut error = validator::ValidationError::new("range");
+        error.message = Some("'to' must not be before 'from'".into());
+        errors.add("to", error);
+        return Err(AppError::ValidationError(errors));
+    }
+
+    if query.format == ExportFormat::Json {
+        let flights = database::export_flights(&pool, &query).await?;
+        let total = flights.len() as u64;
+        let response = ApiResponse::list(flights, total, None);
+        return Ok(Json(response).into_response());
+    }
+
+    let filename = format!("flights_{}_{}.csv", query.from, query.to);
+    let rows = database::stream_flights_for_export(pool, query);
+
+    let header_line = futures_util::stream::once(async {
+        Ok::<_, sqlx::Error>(FLIGHT_CSV_HEADER.to_string())
+    });
+    let body_lines = futures_util::stream::unfold(rows, |mut rows| async move {
+        let row = rows.recv().await?;
+        Some((row.map(|flight| flight_csv_line(&flight)), rows))
+    });
+    let body = Body::from_stream(futures_util::StreamExt::chain(header_line, body_lines));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+const FLIGHT_CSV_HEADER: &str = "id,flight_number,airline,aircraft,departure_time,destination,gate,is_active,capacity,device_id,created_at,updated_at\n";
+
+// Satu baris CSV per penerbangan (RFC 4180: field dengan koma/kutip/newline diberi tanda kutip)
+// Sel yang diawali = + - @ diberi prefix ' agar tidak dieksekusi sebagai formula di spreadsheet
+fn flight_csv_line(flight: &Flight) -> String {
+    fn field(value: &str) -> String {
+        let value = if value.starts_with(['=', '+', '-', '@']) {
+            format!("'{}", value)
+        } else {
+            value.to_string()
+        };
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value
+        }
+    }
+
+    let columns = [
+        flight.id.to_string(),
+        field(&flight.flight_number),
+        field(&flight.airline),
+        field(&flight.aircraft),
+        format_timestamp(&flight.departure_time),
+        field(&flight.destination),
+        field(&flight.gate),
+        flight.is_active.map(|v| v.to_string()).unwrap_or_default(),
+        flight.capacity.map(|v| v.to_string()).unwrap_or_default(),
+        field(flight.device_id.as_deref().unwrap_or("")),
+        format_timestamp(&flight.created_at),
+        flight.updated_at.as_ref().map(format_timestamp).unwrap_or_default(),
+    ];
+    format!("{}\n", columns.join(","))
 }
 
 /// Get flight by ID
@@ -114,16 +257,49 @@ pub async fn get_flight_by_id(
     Path(id): Path<i32>,
 ) -> Result<Json<ApiResponse<Flight>>, AppError> {
     let flight = database::get_flight_by_id(&pool, id).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(flight),
-        total: None,
-    };
+    let response = ApiResponse::success(flight);
+    Ok(Json(response))
+}
+
+/// Find an active flight by flight number and departure date
+///
+/// The flight number is normalized (case, spaces and dashes ignored) and matched with
+/// the UTC departure date, the same identity as the one-flight-per-day constraint.
+#[utoipa::path(
+    get,
+    path = "/api/flights/lookup",
+    tag = "Flights",
+    params(
+        ("flightNumber" = String, Query, description = "Flight number, e.g. GA312"),
+        ("date" = String, Query, description = "Departure date (YYYY-MM-DD, UTC)")
+    ),
+    responses(
+        (status = 200, description = "Matching flight", body = Flight),
+        (status = 400, description = "Missing or invalid flightNumber/date"),
+        (status = 404, description = "No active flight with that number on that date"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn lookup_flight(
+    State(pool): State<PgPool>,
+    Query(query): Query<FlightLookupQuery>,
+) -> Result<Json<ApiResponse<Flight>>, AppError> {
+    // Normalisasi (spasi/strip, huruf besar) dilakukan di query database
+    if query.flight_number.chars().all(|c| matches!(c, '-' | ' ')) {
+        return Err(AppError::InvalidQueryParam("flightNumber must not be empty".to_string()));
+    }
+    let flight = database::get_flight_by_number_and_date(&pool, &query.flight_number, query.date)
+        .await?
+        .ok_or(AppError::FlightNotFound)?;
+    let response = ApiResponse::success(flight);
     Ok(Json(response))
 }
 
 /// Update flight by ID
+///
+/// Omitted (or null) fields are left unchanged. To clear a field, list it in
+/// `clearFields`: `gate` and `aircraft` reset to `TBD`, `capacity` becomes null.
+/// Setting a field and clearing it in the same request is a validation error.
 #[utoipa::path(
     put,
     path = "/api/flights/{id}",
@@ -141,17 +317,39 @@ pub async fn get_flight_by_id(
 )]
 pub async fn update_flight(
     State(pool): State<PgPool>,
+    Extension(stats_cache): Extension<StatsCache>,
     Path(id): Path<i32>,
     Json(payload): Json<UpdateFlight>,
 ) -> Result<Json<ApiResponse<Flight>>, AppError> {
     payload.validate()?;
     let updated_flight = database::update_flight(&pool, id, payload).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Flight updated successfully".to_string()),
-        data: Some(updated_flight),
-        total: None,
-    };
+    stats_cache.invalidate(id);
+    let response = ApiResponse::success(updated_flight).with_message("Flight updated successfully");
+    Ok(Json(response))
+}
+
+/// Touch flight (force re-sync)
+///
+/// Sets `updated_at` to now without changing any data, so the flight is returned again by `GET /api/sync/flights`.
+#[utoipa::path(
+    post,
+    path = "/api/flights/{id}/touch",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Flight touched", body = Flight),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn touch_flight(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Flight>>, AppError> {
+    let flight = database::touch_flight(&pool, id).await?;
+    let response = ApiResponse::success(flight).with_message("Flight marked for re-sync");
     Ok(Json(response))
 }
 
@@ -171,19 +369,39 @@ pub async fn update_flight(
 )]
 pub async fn delete_flight(
     State(pool): State<PgPool>,
+    Extension(stats_cache): Extension<StatsCache>,
     Path(id): Path<i32>,
 ) -> Result<StatusCode, AppError> {
     database::delete_flight(&pool, id).await?;
+    stats_cache.invalidate(id);
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Drop cached statistics of the flight a decode's scan belongs to (no-op for unlinked decodes)
+async fn invalidate_decoded_flight_stats(
+    pool: &PgPool,
+    stats_cache: &StatsCache,
+    decoded: &DecodedBarcode,
+) -> Result<(), AppError> {
+    if decoded.scan_data_id.is_some()
+        && let Some(flight_id) = database::get_decoded_flight_id(pool, decoded.id).await?
+    {
+        stats_cache.invalidate(flight_id);
+    }
+    Ok(())
+}
+
 /// Get flight scan statistics
+///
+/// Served from a short-lived per-flight cache (`STATS_CACHE_TTL_SECONDS`) that is
+/// cleared whenever the flight, its scans or its decodes change.
 #[utoipa::path(
     get,
     path = "/api/flights/{id}/statistics",
     tag = "Flights",
     params(
-        ("id" = i32, Path, description = "Flight ID")
+        ("id" = i32, Path, description = "Flight ID"),
+        ("zero_fill" = Option<bool>, Query, description = "Fill hours without scans with count 0")
     ),
     responses(
         (status = 200, description = "Flight scan statistics", body = FlightStatistics),
@@ -193,19 +411,27 @@ pub async fn delete_flight(
 )]
 pub async fn get_flight_statistics(
     State(pool): State<PgPool>,
+    Extension(stats_cache): Extension<StatsCache>,
     Path(id): Path<i32>,
+    Query(query): Query<FlightStatisticsQuery>,
 ) -> Result<Json<ApiResponse<FlightStatistics>>, AppError> {
-    let stats = database::get_flight_statistics(&pool, id).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(stats),
-        total: None,
+    let zero_fill = query.zero_fill.unwrap_or(false);
+    let stats = match stats_cache.get(id, zero_fill) {
+        Some(stats) => stats,
+        None => {
+            let stats = database::get_flight_statistics(&pool, id, zero_fill).await?;
+            stats_cache.insert(zero_fill, &stats);
+            stats
+        }
     };
+    let response = ApiResponse::success(stats);
     Ok(Json(response))
 }
 
 /// Get decoded barcode statistics for flight
+///
+/// An active flight with no decoded barcodes returns zero counts. A missing or
+/// inactive (soft-deleted) flight returns 404 `FLIGHT_NOT_FOUND`.
 #[utoipa::path(
     get,
     path = "/api/flights/{id}/decoded-statistics",
@@ -214,8 +440,8 @@ pub async fn get_flight_statistics(
         ("id" = i32, Path, description = "Flight ID")
     ),
     responses(
-        (status = 200, description = "Decoded barcode statistics", body = DecodedStatistics),
-        (status = 404, description = "Flight not found"),
+        (status = 200, description = "Decoded barcode statistics (zero counts if nothing decoded yet)", body = DecodedStatistics),
+        (status = 404, description = "Flight not found or inactive"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -224,33 +450,457 @@ pub async fn get_decoded_statistics(
     Path(id): Path<i32>,
 ) -> Result<Json<ApiResponse<DecodedStatistics>>, AppError> {
     let stats = database::get_decoded_statistics(&pool, id).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(stats),
-        total: None,
-    };
+    let response = ApiResponse::success(stats);
+    Ok(Json(response))
+}
+
+/// Get passengers decoded more than once for a flight
+///
+/// Lists passengers (booking code, name and check-in sequence) with more than one decoded
+/// boarding pass on the flight, with occurrence counts and the scan IDs involved, to help
+/// reconcile the manifest. Other passengers on the same PNR are not duplicates.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/decoded-duplicates",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Passengers decoded more than once", body = Vec<DecodedDuplicate>),
+        (status = 404, description = "Flight not found or inactive"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decoded_duplicates(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<DecodedDuplicate>>>, AppError> {
+    let duplicates = database::get_decoded_duplicates(&pool, id).await?;
+    let total = duplicates.len() as u64;
+    let response = ApiResponse::list(duplicates, total, None);
+    Ok(Json(response))
+}
+
+/// Reconcile a passenger manifest against decoded boarding passes
+///
+/// Each manifest entry is a booking code or a passenger name, matched case-insensitively
+/// against the flight's decoded barcodes. Returns the entries that scanned, the ones still
+/// missing, and the booking codes of decoded passengers not on the manifest.
+/// At most `MAX_BULK_ITEMS` entries per request.
+#[utoipa::path(
+    post,
+    path = "/api/flights/{id}/reconcile",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    request_body = ReconcileRequest,
+    responses(
+        (status = 200, description = "Reconciliation result", body = ManifestReconciliation),
+        (status = 400, description = "Empty manifest"),
+        (status = 404, description = "Flight not found or inactive"),
+        (status = 413, description = "More entries than MAX_BULK_ITEMS"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reconcile_flight_manifest(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    Path(id): Path<i32>,
+    CreateJson(payload): CreateJson<ReconcileRequest>,
+) -> Result<Json<ApiResponse<ManifestReconciliation>>, AppError> {
+    payload.validate()?;
+    check_bulk_size(payload.manifest.len(), config.max_bulk_items)?;
+    let reconciliation = database::reconcile_manifest(&pool, id, &payload.manifest).await?;
+    let message = format!(
+        "{} scanned, {} missing, {} unexpected",
+        reconciliation.scanned.len(),
+        reconciliation.missing.len(),
+        reconciliation.unexpected.len()
+    );
+    let response = ApiResponse::success(reconciliation).with_message(message);
+    Ok(Json(response))
+}
+
+/// Get the latest scan of each boarding pass on a flight
+///
+/// Returns one scan per distinct barcode value (the most recent one), newest first,
+/// so re-taps do not inflate the manifest.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/latest-scans",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Latest scan per barcode", body = Vec<ScanData>),
+        (status = 404, description = "Flight not found or inactive"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_latest_scans_for_flight(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<ScanData>>>, AppError> {
+    let scans = database::get_latest_scans_for_flight(&pool, id).await?;
+    let total = scans.len() as u64;
+    let response = ApiResponse::list(scans, total, None);
+    Ok(Json(response))
+}
+
+/// Get decoded barcode statistics for several flights at once
+///
+/// Flight IDs that do not exist are omitted from the result.
+#[utoipa::path(
+    post,
+    path = "/api/decoded-statistics/batch",
+    tag = "Flights",
+    request_body = DecodedStatisticsBatchRequest,
+    responses(
+        (status = 200, description = "Decoded statistics per existing flight", body = Vec<DecodedStatistics>),
+        (status = 400, description = "Validation error"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decoded_statistics_batch(
+    State(pool): State<PgPool>,
+    Json(payload): Json<DecodedStatisticsBatchRequest>,
+) -> Result<Json<ApiResponse<Vec<DecodedStatistics>>>, AppError> {
+    payload.validate()?;
+    let stats = database::get_decoded_statistics_batch(&pool, &payload.flight_ids).await?;
+    let total = stats.len() as u64;
+    let response = ApiResponse::list(stats, total, None);
+    Ok(Json(response))
+}
+
+/// Get decoded barcode statistics grouped by airline
+///
+/// A decode counts as invalid when its Julian date is outside 001-366 or its cabin
+/// class is blank. Returns an empty list when nothing was decoded in the range.
+#[utoipa::path(
+    get,
+    path = "/api/decoded-statistics/by-airline",
+    tag = "Scanning",
+    params(
+        ("from" = Option<String>, Query, description = "Decoded at or after (ISO 8601)"),
+        ("to" = Option<String>, Query, description = "Decoded at or before (ISO 8601)")
+    ),
+    responses(
+        (status = 200, description = "Decode counts per airline with valid/invalid split", body = Vec<AirlineDecodeStatistics>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decoded_statistics_by_airline(
+    State(pool): State<PgPool>,
+    Query(query): Query<DecodedStatisticsByAirlineQuery>,
+) -> Result<Json<ApiResponse<Vec<AirlineDecodeStatistics>>>, AppError> {
+    let stats = database::get_decoded_statistics_by_airline(&pool, &query).await?;
+    let total = stats.len() as u64;
+    let response = ApiResponse::list(stats, total, None);
+    Ok(Json(response))
+}
+
+/// Get per-flight scan counts for every flight a device scanned on a date
+///
+/// The date is the scan date in UTC and defaults to today. Flights the device did not
+/// scan that day are not listed; an unknown device returns an empty list.
+#[utoipa::path(
+    get,
+    path = "/api/devices/{device_id}/flight-statistics",
+    tag = "Scanning",
+    params(
+        ("device_id" = String, Path, description = "Device ID"),
+        ("date" = Option<String>, Query, description = "Scan date in UTC (YYYY-MM-DD), defaults to today")
+    ),
+    responses(
+        (status = 200, description = "Scan counts per flight for the device", body = Vec<DeviceFlightStatistics>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_device_flight_statistics(
+    State(pool): State<PgPool>,
+    Path(device_id): Path<String>,
+    Query(query): Query<DeviceFlightStatisticsQuery>,
+) -> Result<Json<ApiResponse<Vec<DeviceFlightStatistics>>>, AppError> {
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let stats = database::get_device_flight_statistics(&pool, &device_id, date).await?;
+    let total = stats.len() as u64;
+    let response = ApiResponse::list(stats, total, None);
+    Ok(Json(response))
+}
+
+/// Get active flights a device has never scanned
+///
+/// The date is the departure date in UTC and defaults to today. A flight counts as
+/// scanned if the device recorded any scan for it, regardless of scan time.
+#[utoipa::path(
+    get,
+    path = "/api/devices/{device_id}/unscanned-flights",
+    tag = "Scanning",
+    params(
+        ("device_id" = String, Path, description = "Device ID"),
+        ("date" = Option<String>, Query, description = "Departure date in UTC (YYYY-MM-DD), defaults to today")
+    ),
+    responses(
+        (status = 200, description = "Active flights without scans from the device", body = Vec<Flight>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_unscanned_flights_for_device(
+    State(pool): State<PgPool>,
+    Path(device_id): Path<String>,
+    Query(query): Query<UnscannedFlightsQuery>,
+) -> Result<Json<ApiResponse<Vec<Flight>>>, AppError> {
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let flights = database::get_unscanned_flights_for_device(&pool, &device_id, date).await?;
+    let total = flights.len() as u64;
+    let response = ApiResponse::list(flights, total, None);
+    Ok(Json(response))
+}
+
+/// Get headline counts for the dashboard landing page
+///
+/// Active flights departing on the date, scans, rejections and distinct scanning devices,
+/// all by UTC date (defaults to today). The four counts are queried concurrently.
+#[utoipa::path(
+    get,
+    path = "/api/dashboard/summary",
+    tag = "Scanning",
+    params(
+        ("date" = Option<String>, Query, description = "Date in UTC (YYYY-MM-DD), defaults to today")
+    ),
+    responses(
+        (status = 200, description = "Dashboard counts for the date", body = DashboardSummary),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_dashboard_summary(
+    State(pool): State<PgPool>,
+    Query(query): Query<DashboardSummaryQuery>,
+) -> Result<Json<ApiResponse<DashboardSummary>>, AppError> {
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let summary = database::get_dashboard_summary(&pool, date).await?;
+    let response = ApiResponse::success(summary);
+    Ok(Json(response))
+}
+
+/// Get flight detail (flight, scan statistics and decoded statistics)
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/detail",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Flight with scan and decoded statistics", body = FlightDetail),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_flight_detail(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<FlightDetail>>, AppError> {
+    let detail = database::get_flight_detail(&pool, id).await?;
+    let response = ApiResponse::success(detail);
+    Ok(Json(response))
+}
+
+/// Get passenger boarding progress for flight
+///
+/// `percentage` is null when the flight has no capacity set.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/boarding-progress",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Boarding progress", body = BoardingProgress),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_boarding_progress(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<BoardingProgress>>, AppError> {
+    let progress = database::get_boarding_progress(&pool, id).await?;
+    let response = ApiResponse::success(progress);
+    Ok(Json(response))
+}
+
+/// Get scan counts by timing relative to departure
+///
+/// `early`: scanned more than 60 minutes before `departureTime`; `onTime`: within the
+/// last 60 minutes up to departure (both bounds inclusive); `late`: after departure.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/boarding-timing",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Scan counts per timing bucket", body = BoardingTiming),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_boarding_timing(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<BoardingTiming>>, AppError> {
+    let timing = database::get_boarding_timing(&pool, id).await?;
+    let response = ApiResponse::success(timing);
+    Ok(Json(response))
+}
+
+/// Get scans for flight that have no decode result
+///
+/// Lists scans whose barcode failed to decode (or was never decoded) so staff can retry or investigate.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/undecoded-scans",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Scans without a decode_barcode row", body = Vec<ScanData>),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_undecoded_scans(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<ScanData>>>, AppError> {
+    let scans = database::get_undecoded_scans(&pool, id).await?;
+    let total = scans.len() as u64;
+    let response = ApiResponse::list(scans, total, None);
+    Ok(Json(response))
+}
+
+/// Get device allowlist for flight
+///
+/// An empty list means any device may scan the flight.
+#[utoipa::path(
+    get,
+    path = "/api/flights/{id}/allowed-devices",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    responses(
+        (status = 200, description = "Allowed device IDs", body = Vec<String>),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_flight_allowed_devices(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<String>>>, AppError> {
+    let _ = database::get_flight_by_id(&pool, id).await?;
+    let devices = database::get_flight_allowed_devices(&pool, id).await?;
+    let total = devices.len() as u64;
+    let response = ApiResponse::list(devices, total, None);
     Ok(Json(response))
 }
 
+/// Add devices to flight allowlist
+#[utoipa::path(
+    post,
+    path = "/api/flights/{id}/allowed-devices",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID")
+    ),
+    request_body = AllowedDevicesRequest,
+    responses(
+        (status = 200, description = "Updated allowlist", body = Vec<String>),
+        (status = 400, description = "Validation error"),
+        (status = 404, description = "Flight not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn add_flight_allowed_devices(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Json(payload): Json<AllowedDevicesRequest>,
+) -> Result<Json<ApiResponse<Vec<String>>>, AppError> {
+    if let Err(validation_errors) = payload.validate() {
+        tracing::error!(
+            errors = ?validation_errors.field_errors(),
+            flight_id = id,
+            device_ids = ?payload.device_ids,
+            "Allowed devices validation failed"
+        );
+        return Err(AppError::ValidationError(validation_errors));
+    }
+    let devices = database::add_flight_allowed_devices(&pool, id, payload.device_ids).await?;
+    let total = devices.len() as u64;
+    let response = ApiResponse::list(devices, total, None).with_message("Allowed devices updated");
+    Ok(Json(response))
+}
+
+/// Remove device from flight allowlist
+#[utoipa::path(
+    delete,
+    path = "/api/flights/{id}/allowed-devices/{device_id}",
+    tag = "Flights",
+    params(
+        ("id" = i32, Path, description = "Flight ID"),
+        ("device_id" = String, Path, description = "Device ID")
+    ),
+    responses(
+        (status = 204, description = "Device removed from allowlist"),
+        (status = 404, description = "Device not on allowlist"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn remove_flight_allowed_device(
+    State(pool): State<PgPool>,
+    Path((id, device_id)): Path<(i32, String)>,
+) -> Result<StatusCode, AppError> {
+    database::remove_flight_allowed_device(&pool, id, &device_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // ==================== SCANNING HANDLERS ====================
 
 /// Create new scan data
+///
+/// With `decode=true` the barcode is decoded right after the scan is inserted, in the
+/// same transaction, and the decoded row is returned in `decoded`. If the barcode can't
+/// be parsed the scan is still stored and a rejection log is created (`rejection`).
 #[utoipa::path(
     post,
     path = "/api/scan-data",
     tag = "Scanning",
+    params(
+        ("decode" = Option<bool>, Query, description = "Decode the barcode in the same request")
+    ),
     request_body = ScanDataInput,
     responses(
-        (status = 201, description = "Scan data created successfully", body = ScanData),
+        (status = 201, description = "Scan data created successfully", body = ScanCreated),
         (status = 400, description = "Validation error"),
+        (status = 403, description = "Device not on the flight's allowlist"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn create_scan(
     State(pool): State<PgPool>,
-    Json(payload): Json<ScanDataInput>,
-) -> Result<(StatusCode, Json<ApiResponse<ScanData>>), AppError> {
+    Extension(config): Extension<AppConfig>,
+    Extension(stats_cache): Extension<StatsCache>,
+    Query(query): Query<CreateScanQuery>,
+    CreateJson(payload): CreateJson<ScanDataInput>,
+) -> Result<(StatusCode, Json<ApiResponse<ScanCreated>>), AppError> {
     tracing::info!(
         flight_id = payload.flight_id,
         barcode_format = %payload.barcode_format,
@@ -260,26 +910,52 @@ pub async fn create_scan(
     if let Err(validation_errors) = payload.validate() {
         tracing::error!(
             errors = ?validation_errors.field_errors(),
-            payload = ?payload,
+            barcode_value = %logging::redact_pii(&payload.barcode_value),
+            barcode_format = %payload.barcode_format,
+            device_id = %payload.device_id,
+            flight_id = payload.flight_id,
             "Scan data validation failed"
         );
         return Err(AppError::ValidationError(validation_errors));
     }
 
-    let new_scan = database::create_scan_data(&pool, payload).await?;
+    if let Some(scanned_at) = payload.scanned_at {
+        check_timestamp_skew("scannedAt", scanned_at, Utc::now(), config.max_timestamp_skew_seconds)?;
+    }
+
+    let created = if query.decode.unwrap_or(false) {
+        database::create_scan_data_with_decode(
+            &pool,
+            payload,
+            config.max_barcode_length,
+            config.scan_time_max_skew_seconds,
+            config.cabin_class_fallback.as_deref(),
+        )
+        .await?
+    } else {
+        ScanCreated {
+            scan: database::create_scan_data(&pool, payload, config.scan_time_max_skew_seconds).await?,
+            decoded: None,
+            rejection: None,
+        }
+    };
+    if let Some(flight_id) = created.scan.flight_id {
+        stats_cache.invalidate(flight_id);
+    }
 
     tracing::info!(
-        scan_id = new_scan.id,
-        flight_id = new_scan.flight_id,
+        scan_id = created.scan.id,
+        flight_id = created.scan.flight_id,
+        decoded = created.decoded.is_some(),
         "Scan data created successfully"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Scan data saved successfully".to_string()),
-        data: Some(new_scan),
-        total: None,
+    let message = if created.rejection.is_some() {
+        "Scan data saved; barcode could not be decoded"
+    } else {
+        "Scan data saved successfully"
     };
+    let response = ApiResponse::success(created).with_message(message);
     Ok((StatusCode::CREATED, Json(response)))
 }
 
@@ -290,28 +966,92 @@ pub async fn create_scan(
     tag = "Scanning",
     params(
         ("flight_id" = Option<i32>, Query, description = "Filter by flight ID"),
-        ("date_range" = Option<String>, Query, description = "Date range filter (start,end)")
+        ("date_range" = Option<String>, Query, description = "Date range filter (start,end)"),
+        ("count_only" = Option<bool>, Query, description = "Return only the total count, without rows"),
+        ("limit" = Option<i64>, Query, description = "Page size, newest first (clamped to MAX_PAGE_SIZE); omit with offset for the whole list"),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination")
     ),
     responses(
-        (status = 200, description = "List of scan data", body = Vec<ScanData>),
+        (status = 200, description = "List of scan data (capped to the newest page with hasMore when unpaginated and above LIST_RESPONSE_CAP)", body = Vec<ScanData>),
+        (status = 400, description = "Invalid limit/offset"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_scan_data(
     State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    pagination: Option<Pagination>,
     Query(query): Query<GetScanDataQuery>,
 ) -> Result<Json<ApiResponse<Vec<ScanData>>>, AppError> {
-    let (scans, total) = database::get_scan_data(&pool, query).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(scans),
-        total: Some(total as u64),
-    };
+    let total = database::count_scan_data(&pool, &query).await?;
+    if query.count_only.unwrap_or(false) {
+        return Ok(Json(ApiResponse::count(total as u64)));
+    }
+    let page = list_response_page("/api/scan-data", total, pagination, &config);
+    let scans = database::get_scan_data(&pool, &query, page).await?;
+    let has_more = page.map(|p| p.offset + (scans.len() as i64) < total);
+    Ok(Json(ApiResponse::list(scans, total as u64, has_more)))
+}
+
+/// Get every scan of one barcode value across all flights
+///
+/// Exact match on the raw barcode, newest first, with each scan's flight id and time.
+/// Returns an empty list when the barcode was never scanned.
+#[utoipa::path(
+    get,
+    path = "/api/scan-data/by-barcode",
+    tag = "Scanning",
+    params(
+        ("value" = String, Query, description = "Exact barcode value")
+    ),
+    responses(
+        (status = 200, description = "Scans of the barcode, newest first", body = Vec<ScanData>),
+        (status = 400, description = "Missing or empty value"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_scan_data_by_barcode(
+    State(pool): State<PgPool>,
+    Query(query): Query<BarcodeValueQuery>,
+) -> Result<Json<ApiResponse<Vec<ScanData>>>, AppError> {
+    query.validate()?;
+    let scans = database::get_scan_data_by_barcode(&pool, &query.value).await?;
+    let total = scans.len() as u64;
+    let response = ApiResponse::list(scans, total, None);
+    Ok(Json(response))
+}
+
+/// Re-parse a stored scan and report parser diagnostics
+///
+/// Shows the normalized barcode, the whitespace tokens after the name field, the
+/// strategy that matched and every extracted field. Nothing is persisted.
+#[utoipa::path(
+    get,
+    path = "/api/scan-data/{id}/decode-diagnostics",
+    tag = "Scanning",
+    params(
+        ("id" = i32, Path, description = "Scan data ID")
+    ),
+    responses(
+        (status = 200, description = "Parser diagnostics (strategy and fields null when no strategy matched)", body = DecodeDiagnostics),
+        (status = 404, description = "Scan not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decode_diagnostics(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<DecodeDiagnostics>>, AppError> {
+    let diagnostics = database::get_decode_diagnostics(&pool, id).await?;
+    let response = ApiResponse::success(diagnostics);
     Ok(Json(response))
 }
 
 /// Decode barcode (IATA BCBP format)
+///
+/// Length limits come from the shared parser only: at least 50 characters,
+/// at most `MAX_BARCODE_LENGTH`. With `AUTO_REJECTION_LOGS=true` a barcode that cannot
+/// be parsed is also recorded as an `invalid_format` rejection log.
 #[utoipa::path(
     post,
     path = "/api/decode-barcode",
@@ -319,61 +1059,219 @@ pub async fn get_scan_data(
     request_body = DecodeRequest,
     responses(
         (status = 201, description = "Barcode decoded successfully", body = DecodedBarcode),
-        (status = 400, description = "Invalid barcode format"),
+        (status = 400, description = "Invalid barcode format, or scanDataId missing when REQUIRE_SCAN_DATA_ID is enabled"),
+        (status = 422, description = "barcodeFormat has no decoder (only PDF417 and AZTEC are decoded)"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn decode_barcode(
     State(pool): State<PgPool>,
-    Json(payload): Json<DecodeRequest>,
-) -> Result<(StatusCode, Json<ApiResponse<DecodedBarcode>>), AppError> {
-    payload.validate()?;
-    let decoded = database::decode_barcode_iata(&pool, payload).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Barcode decoded successfully".to_string()),
-        data: Some(decoded),
-        total: None,
-    };
-    Ok((StatusCode::CREATED, Json(response)))
+    Extension(config): Extension<AppConfig>,
+    Extension(stats_cache): Extension<StatsCache>,
+    CreateJson(payload): CreateJson<DecodeRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<DecodedBarcode>>), AppError> {
+    payload.validate()?;
+    payload.check_scan_data_id_policy(config.require_scan_data_id)?;
+    let failure_context = config
+        .auto_rejection_logs
+        .then(|| (payload.barcode_value.clone(), payload.barcode_format.clone(), payload.scan_data_id));
+    let decoded = database::decode_barcode_iata(
+        &pool,
+        payload,
+        config.max_barcode_length,
+        config.cabin_class_fallback.as_deref(),
+    )
+    .await;
+    let decoded = match decoded {
+        Ok(decoded) => decoded,
+        Err(e @ (AppError::InvalidBarcodeFormat | AppError::InvalidBarcodeEncoding(_))) => {
+            if let Some((barcode_value, barcode_format, scan_data_id)) = failure_context {
+                // Pencatatan hanya untuk analitik; kegagalannya tidak boleh menutupi error decode
+                if let Err(log_error) =
+                    database::record_decode_failure(&pool, barcode_value, barcode_format, scan_data_id).await
+                {
+                    tracing::error!(error = ?log_error, "Failed to record decode failure as rejection log");
+                }
+            }
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+    invalidate_decoded_flight_stats(&pool, &stats_cache, &decoded).await?;
+    let response = ApiResponse::success(decoded).with_message("Barcode decoded successfully");
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Detect barcode format
+///
+/// Guesses the symbology of a raw barcode value sent without a declared format.
+/// Currently recognizes IATA boarding passes (`PDF417`); `format` is null otherwise,
+/// including for values longer than `MAX_BARCODE_LENGTH`.
+#[utoipa::path(
+    post,
+    path = "/api/decode-barcode/detect-format",
+    tag = "Scanning",
+    request_body = DetectFormatRequest,
+    responses(
+        (status = 200, description = "Detected format (null if unknown)", body = DetectFormatResponse),
+        (status = 400, description = "Validation error")
+    )
+)]
+pub async fn detect_barcode_format(
+    Extension(config): Extension<AppConfig>,
+    Json(payload): Json<DetectFormatRequest>,
+) -> Result<Json<ApiResponse<DetectFormatResponse>>, AppError> {
+    payload.validate()?;
+    // Input di atas MAX_BARCODE_LENGTH tidak dinormalisasi sama sekali
+    let format = if barcode_parser::exceeds_max_length(&payload.barcode_value, config.max_barcode_length) {
+        None
+    } else {
+        barcode_parser::detect_format(&payload.barcode_value).map(str::to_string)
+    };
+    let response = ApiResponse::success(DetectFormatResponse { format });
+    Ok(Json(response))
+}
+
+/// Get decoded barcodes with optional flight, airline and date-range filters
+#[utoipa::path(
+    get,
+    path = "/api/decoded-barcodes",
+    tag = "Scanning",
+    params(
+        ("flight_id" = Option<i32>, Query, description = "Filter by flight ID"),
+        ("airline_code" = Option<String>, Query, description = "Filter by airline code (e.g. GA)"),
+        ("from" = Option<String>, Query, description = "Decoded at or after (ISO 8601)"),
+        ("to" = Option<String>, Query, description = "Decoded at or before (ISO 8601)"),
+        ("limit" = Option<i64>, Query, description = "Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)"),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination"),
+        ("count_only" = Option<bool>, Query, description = "Return only the total count, without rows")
+    ),
+    responses(
+        (status = 200, description = "Page of decoded barcodes; total counts all matching rows", body = Vec<DecodedBarcode>),
+        (status = 400, description = "Invalid limit/offset"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decoded_barcodes(
+    State(pool): State<PgPool>,
+    pagination: Pagination,
+    Query(query): Query<GetDecodedBarcodesQuery>,
+) -> Result<Json<ApiResponse<Vec<DecodedBarcode>>>, AppError> {
+    if query.count_only.unwrap_or(false) {
+        let total = database::count_decoded_barcodes(&pool, &query).await?;
+        return Ok(Json(ApiResponse::count(total as u64)));
+    }
+
+    let (decoded_list, total) = tokio::try_join!(
+        database::get_all_decoded_barcodes(&pool, &query, pagination),
+        database::count_decoded_barcodes(&pool, &query),
+    )?;
+    let response = ApiResponse::list(decoded_list, total as u64, None);
+    Ok(Json(response))
+}
+
+/// Correct a misparsed decoded barcode
+///
+/// Only `infantStatus` and `cabinClass` can be changed; omitted fields keep their value.
+/// Decoded statistics are computed from the table on every request, so the correction
+/// is reflected by the next statistics fetch.
+#[utoipa::path(
+    patch,
+    path = "/api/decoded-barcodes/{id}",
+    tag = "Scanning",
+    params(
+        ("id" = i32, Path, description = "Decoded barcode ID")
+    ),
+    request_body = UpdateDecodedBarcode,
+    responses(
+        (status = 200, description = "Decoded barcode updated", body = DecodedBarcode),
+        (status = 400, description = "Validation error"),
+        (status = 404, description = "Decoded barcode not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_decoded_barcode(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<i32>,
+    Extension(stats_cache): Extension<StatsCache>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateDecodedBarcode>,
+) -> Result<Json<ApiResponse<DecodedBarcode>>, AppError> {
+    payload.validate()?;
+    let decoded = database::update_decoded_barcode(&pool, id, payload).await?;
+    invalidate_decoded_flight_stats(&pool, &stats_cache, &decoded).await?;
+    tracing::info!(decoded_id = id, user_id = user_id, "Decoded barcode corrected");
+    let response = ApiResponse::success(decoded).with_message("Decoded barcode updated successfully");
+    Ok(Json(response))
+}
+
+/// Get the flight a decoded barcode belongs to
+///
+/// Follows the decoded row's scan link to the scan's flight. Returns 404 when the
+/// decoded barcode doesn't exist, was decoded without a scan, or its scan has no flight.
+#[utoipa::path(
+    get,
+    path = "/api/decoded-barcodes/{id}/flight",
+    tag = "Scanning",
+    params(
+        ("id" = i32, Path, description = "Decoded barcode ID")
+    ),
+    responses(
+        (status = 200, description = "Flight of the decoded barcode's scan", body = Flight),
+        (status = 404, description = "Decoded barcode not found or not linked to a flight"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_decoded_barcode_flight(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Flight>>, AppError> {
+    let flight = database::get_decoded_barcode_flight(&pool, id).await?;
+    let response = ApiResponse::success(flight);
+    Ok(Json(response))
 }
 
-/// Get all decoded barcodes with optional flight filter
+/// Compare a decoded barcode's flight with the flight its scan was recorded against
+///
+/// Checks airline, flight number and date (Julian day vs the flight's UTC departure date)
+/// and lists the fields that differ, e.g. a passenger boarding at the wrong gate. 404 rules
+/// are the same as `GET /api/decoded-barcodes/{id}/flight`.
 #[utoipa::path(
     get,
-    path = "/api/decoded-barcodes",
+    path = "/api/decoded-barcodes/{id}/mismatch",
     tag = "Scanning",
     params(
-        ("flight_id" = Option<i32>, Query, description = "Filter by flight ID")
+        ("id" = i32, Path, description = "Decoded barcode ID")
     ),
     responses(
-        (status = 200, description = "List of decoded barcodes", body = Vec<DecodedBarcode>),
+        (status = 200, description = "Comparison result with mismatched fields", body = DecodedFlightMismatch),
+        (status = 404, description = "Decoded barcode not found or not linked to a flight"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub async fn get_decoded_barcodes(
+pub async fn get_decoded_flight_mismatch(
     State(pool): State<PgPool>,
-    Query(query): Query<GetDecodedBarcodesQuery>,
-) -> Result<Json<ApiResponse<Vec<DecodedBarcode>>>, AppError> {
-    let decoded_list = database::get_all_decoded_barcodes(&pool, query.flight_id).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(decoded_list),
-        total: None,
-    };
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<DecodedFlightMismatch>>, AppError> {
+    let comparison = database::get_decoded_flight_mismatch(&pool, id).await?;
+    let response = ApiResponse::success(comparison);
     Ok(Json(response))
 }
 
 // ==================== SYNC HANDLERS ====================
 
 /// Incremental flight synchronization
+///
+/// Soft-deleted flights are included by default (with `isActive: false`) so clients can
+/// drop them from their local cache. With `include_inactive=false` only active flights are
+/// returned, and deletions are never propagated to that client.
 #[utoipa::path(
     get,
     path = "/api/sync/flights",
     tag = "Sync",
     params(
-        ("last_sync" = Option<String>, Query, description = "Last sync timestamp (ISO 8601)")
+        ("last_sync" = Option<String>, Query, description = "Last sync timestamp (ISO 8601)"),
+        ("include_inactive" = Option<bool>, Query, description = "Include soft-deleted flights (default true)")
     ),
     responses(
         (status = 200, description = "Updated flights since last sync", body = Vec<Flight>),
@@ -384,63 +1282,139 @@ pub async fn sync_flights(
     State(pool): State<PgPool>,
     Query(query): Query<SyncFlightsQuery>,
 ) -> Result<Json<ApiResponse<Vec<Flight>>>, AppError> {
-    let flights = database::get_flights_since(&pool, query.last_sync).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(flights),
-        total: None,
-    };
+    let include_inactive = query.include_inactive.unwrap_or(true);
+    let flights = database::get_flights_since(&pool, query.last_sync, include_inactive).await?;
+    let response = ApiResponse::success(flights);
     Ok(Json(response))
 }
 
 /// Bulk flight synchronization
+///
+/// With `dry_run=true` the upserts are evaluated and summarized but rolled back.
+/// Validation errors for every invalid flight are returned together (with their indices);
+/// nothing is written unless `partial=true`, which syncs the valid flights and reports the rest in `invalid`.
+/// `on_conflict` decides what happens to flights that already exist (same number and UTC departure date):
+/// `upsert` (default) overwrites them, `skip_existing` leaves them untouched, and `fail_on_conflict`
+/// aborts the batch with 409 `BULK_CONFLICT`. Existing flights are listed in `conflicts`.
+/// Payloads with more than `MAX_BULK_ITEMS` flights are rejected with 413 before any database work.
+/// A flight appearing more than once in the payload is listed in `duplicates`; with
+/// `on_duplicate=keep_last` (default) only its last entry is synced, with `reject` the batch fails with 400.
 #[utoipa::path(
     post,
     path = "/api/sync/flights/bulk",
     tag = "Sync",
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Preview the sync without writing to the database"),
+        ("partial" = Option<bool>, Query, description = "Sync valid flights even if some are invalid"),
+        ("on_conflict" = Option<BulkConflictMode>, Query, description = "upsert (default), skip_existing or fail_on_conflict"),
+        ("on_duplicate" = Option<BulkDuplicateMode>, Query, description = "keep_last (default) or reject, for flights repeated within the payload")
+    ),
     request_body = Vec<CreateFlight>,
     responses(
-        (status = 201, description = "Flights synced successfully"),
-        (status = 400, description = "Validation error"),
+        (status = 201, description = "Flights synced successfully", body = BulkSyncSummary),
+        (status = 200, description = "Dry-run summary (nothing persisted)", body = BulkSyncSummary),
+        (status = 400, description = "Validation errors for all invalid flights (or payload duplicates with on_duplicate=reject), with indices"),
+        (status = 409, description = "Existing flights found with on_conflict=fail_on_conflict"),
+        (status = 413, description = "More flights than MAX_BULK_ITEMS"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn sync_flights_bulk(
     State(pool): State<PgPool>,
-    Json(payload): Json<Vec<CreateFlight>>,
-) -> Result<(StatusCode, Json<ApiResponse<usize>>), AppError> {
+    Extension(config): Extension<AppConfig>,
+    Extension(stats_cache): Extension<StatsCache>,
+    Query(query): Query<BulkSyncQuery>,
+    CreateJson(payload): CreateJson<Vec<CreateFlight>>,
+) -> Result<(StatusCode, Json<ApiResponse<BulkSyncSummary>>), AppError> {
+    let dry_run = query.dry_run.unwrap_or(false);
+    let partial = query.partial.unwrap_or(false);
+    let on_conflict = query.on_conflict.unwrap_or_default();
+    let on_duplicate = query.on_duplicate.unwrap_or_default();
+    check_bulk_size(payload.len(), config.max_bulk_items)?;
+
     tracing::info!(
         flight_count = payload.len(),
+        dry_run = dry_run,
+        partial = partial,
+        on_conflict = ?on_conflict,
+        on_duplicate = ?on_duplicate,
         "Bulk sync flights request"
     );
 
-    for (index, p) in payload.iter().enumerate() {
-        if let Err(validation_errors) = p.validate() {
+    // Kumpulkan error validasi semua flight (termasuk jam device yang meleset) agar client
+    // bisa memperbaiki semuanya sekaligus; index adalah posisi asli di payload
+    let mut valid_flights = Vec::with_capacity(payload.len());
+    let mut valid_indices = Vec::with_capacity(payload.len());
+    let mut invalid = Vec::new();
+    let now = Utc::now();
+    for (index, p) in payload.into_iter().enumerate() {
+        let mut validation_errors = p.validate().err().unwrap_or_else(validator::ValidationErrors::new);
+        add_timestamp_skew_errors(&mut validation_errors, &p, now, config.max_timestamp_skew_seconds);
+        if validation_errors.is_empty() {
+            valid_flights.push(p);
+            valid_indices.push(index);
+        } else {
             tracing::error!(
                 index = index,
                 errors = ?validation_errors.field_errors(),
                 flight = ?p,
                 "Bulk sync validation failed"
             );
-            return Err(AppError::ValidationError(validation_errors));
+            invalid.push(BulkValidationFailure { index, errors: validation_errors });
+        }
+    }
+
+    // Default all-or-nothing: satu flight tidak valid membatalkan seluruh batch
+    if !invalid.is_empty() && !partial {
+        return Err(AppError::BulkValidationError(invalid));
+    }
+
+    // Flight yang sama lebih dari sekali dalam payload akan saling menimpa lewat upsert per baris;
+    // deteksi di sini agar client tahu entri mana yang dipakai
+    let duplicates = find_payload_duplicates(&valid_flights, &valid_indices);
+    if !duplicates.is_empty() {
+        tracing::warn!(duplicates = ?duplicates, on_duplicate = ?on_duplicate, "Bulk sync payload contains duplicate flights");
+        if on_duplicate == BulkDuplicateMode::Reject {
+            return Err(AppError::BulkValidationError(duplicate_failures(&duplicates)));
         }
+        let superseded: Vec<usize> = duplicates
+            .iter()
+            .flat_map(|d| d.indices[..d.indices.len() - 1].iter().copied())
+            .collect();
+        valid_flights = valid_flights
+            .into_iter()
+            .zip(&valid_indices)
+            .filter(|(_, index)| !superseded.contains(index))
+            .map(|(flight, _)| flight)
+            .collect();
     }
 
-    let count = database::bulk_insert_flights(&pool, payload).await?;
+    let mut summary =
+        database::bulk_insert_flights(&pool, valid_flights, dry_run, on_conflict).await?;
+    summary.invalid = invalid;
+    summary.duplicates = duplicates;
+    // Flight yang di-upsert bisa siapa saja di cache; id-nya tidak dikembalikan per baris
+    if summary.updated > 0 {
+        stats_cache.clear();
+    }
 
     tracing::info!(
-        synced_count = count,
+        inserted = summary.inserted,
+        updated = summary.updated,
+        skipped = summary.skipped,
+        dry_run = dry_run,
         "Bulk flights synced successfully"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some(format!("{} flights synced successfully", count)),
-        data: Some(count),
-        total: None,
+    let synced = summary.inserted + summary.updated;
+    let (status, message) = if dry_run {
+        (StatusCode::OK, format!("Dry run: {} flights would be synced", synced))
+    } else {
+        (StatusCode::CREATED, format!("{} flights synced successfully", synced))
     };
-    Ok((StatusCode::CREATED, Json(response)))
+
+    let response = ApiResponse::success(summary).with_message(message);
+    Ok((status, Json(response)))
 }
 
 // ==================== REJECTION LOGGING HANDLERS ====================
@@ -459,7 +1433,7 @@ pub async fn sync_flights_bulk(
 )]
 pub async fn create_rejection_log(
     State(pool): State<PgPool>,
-    Json(payload): Json<CreateRejectionLog>,
+    CreateJson(payload): CreateJson<CreateRejectionLog>,
 ) -> Result<(StatusCode, Json<ApiResponse<RejectionLog>>), AppError> {
     tracing::info!(
         barcode_format = %payload.barcode_format,
@@ -483,43 +1457,74 @@ pub async fn create_rejection_log(
         "Rejection log created successfully"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Rejection log saved successfully".to_string()),
-        data: Some(rejection),
-        total: None,
-    };
+    let response = ApiResponse::success(rejection).with_message("Rejection log saved successfully");
     Ok((StatusCode::CREATED, Json(response)))
 }
 
 /// Get rejection logs with filtering
+///
+/// Rejections for format reasons (`invalid_format`, `DECODE_FAILED`) carry `partialDecode`:
+/// whatever fields the parser could still extract from the stored barcode, or null.
 #[utoipa::path(
     get,
     path = "/api/rejection-logs",
     tag = "Logs",
     params(
-        ("limit" = Option<i64>, Query, description = "Limit number of results"),
+        ("limit" = Option<i64>, Query, description = "Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)"),
         ("offset" = Option<i64>, Query, description = "Offset for pagination"),
         ("airline" = Option<String>, Query, description = "Filter by airline code"),
         ("reason" = Option<String>, Query, description = "Filter by rejection reason"),
         ("device_id" = Option<String>, Query, description = "Filter by device ID")
     ),
     responses(
-        (status = 200, description = "List of rejection logs", body = Vec<RejectionLog>),
+        (status = 200, description = "List of rejection logs (partialDecode filled for invalid-format rejections)", body = Vec<RejectionLogEntry>),
+        (status = 400, description = "Invalid limit/offset"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_rejection_logs(
     State(pool): State<PgPool>,
+    pagination: Pagination,
     Query(query): Query<RejectionLogQuery>,
-) -> Result<Json<ApiResponse<Vec<RejectionLog>>>, AppError> {
-    let logs = database::get_rejection_logs(&pool, query).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(logs),
-        total: None,
-    };
+) -> Result<Json<ApiResponse<Vec<RejectionLogEntry>>>, AppError> {
+    let logs: Vec<RejectionLogEntry> = database::get_rejection_logs(&pool, query, pagination)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let response = ApiResponse::success(logs);
+    Ok(Json(response))
+}
+
+/// Get every rejection log for one barcode value
+///
+/// Exact match on the raw barcode, newest first, for investigating why a pass keeps
+/// getting rejected. Returns an empty list when the barcode was never rejected.
+#[utoipa::path(
+    get,
+    path = "/api/rejection-logs/by-barcode",
+    tag = "Logs",
+    params(
+        ("value" = String, Query, description = "Exact barcode value")
+    ),
+    responses(
+        (status = 200, description = "Rejection logs for the barcode, newest first", body = Vec<RejectionLogEntry>),
+        (status = 400, description = "Missing or empty value"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_rejection_logs_by_barcode(
+    State(pool): State<PgPool>,
+    Query(query): Query<BarcodeValueQuery>,
+) -> Result<Json<ApiResponse<Vec<RejectionLogEntry>>>, AppError> {
+    query.validate()?;
+    let logs: Vec<RejectionLogEntry> = database::get_rejection_logs_by_barcode(&pool, &query.value)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let total = logs.len() as u64;
+    let response = ApiResponse::list(logs, total, None);
     Ok(Json(response))
 }
 
@@ -556,12 +1561,7 @@ pub async fn get_airport_codes(
     State(pool): State<PgPool>,
 ) -> Result<Json<ApiResponse<Vec<crate::models::AirportCode>>>, AppError> {
     let codes = database::get_airport_codes(&pool).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(codes),
-        total: None,
-    };
+    let response = ApiResponse::success(codes);
     Ok(Json(response))
 }
 
@@ -579,12 +1579,7 @@ pub async fn get_airline_codes(
     State(pool): State<PgPool>,
 ) -> Result<Json<ApiResponse<Vec<crate::models::AirlineCode>>>, AppError> {
     let codes = database::get_airline_codes(&pool).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(codes),
-        total: None,
-    };
+    let response = ApiResponse::success(codes);
     Ok(Json(response))
 }
 
@@ -602,15 +1597,150 @@ pub async fn get_cabin_class_codes(
     State(pool): State<PgPool>,
 ) -> Result<Json<ApiResponse<Vec<crate::models::CabinClassCode>>>, AppError> {
     let codes = database::get_cabin_class_codes(&pool).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(codes),
-        total: None,
-    };
+    let response = ApiResponse::success(codes);
     Ok(Json(response))
 }
 
+/// Tolak array bulk yang melebihi MAX_BULK_ITEMS sebelum validasi atau transaksi apa pun
+fn check_bulk_size(items: usize, max_items: usize) -> Result<(), AppError> {
+    if items > max_items {
+        return Err(AppError::PayloadTooLarge { items, max_items });
+    }
+    Ok(())
+}
+
+/// Validasi seluruh batch import code sekaligus; satu entri tidak valid membatalkan batch
+fn validate_code_batch<T: Validate>(codes: &[T]) -> Result<(), AppError> {
+    let invalid: Vec<BulkValidationFailure> = codes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, code)| code.validate().err().map(|errors| BulkValidationFailure { index, errors }))
+        .collect();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::BulkValidationError(invalid))
+    }
+}
+
+fn code_import_response(kind: &str, summary: CodeImportSummary) -> (StatusCode, Json<ApiResponse<CodeImportSummary>>) {
+    tracing::info!(
+        kind = kind,
+        inserted = summary.inserted,
+        updated = summary.updated,
+        starter_data_version = summary.starter_data_version,
+        "Code import completed"
+    );
+    let message = format!(
+        "{} {} codes imported ({} new, {} updated)",
+        summary.inserted + summary.updated,
+        kind,
+        summary.inserted,
+        summary.updated
+    );
+    let response = ApiResponse::success(summary).with_message(message);
+    (StatusCode::OK, Json(response))
+}
+
+/// Bulk import airport codes
+///
+/// Upserts on `code` inside one transaction and bumps the starter data version once
+/// for the whole batch. Requires the `system.settings` permission.
+/// At most `MAX_BULK_ITEMS` entries per request.
+#[utoipa::path(
+    post,
+    path = "/api/codes/airports/bulk",
+    tag = "Codes",
+    request_body = Vec<UpsertAirportCode>,
+    responses(
+        (status = 200, description = "Codes imported", body = CodeImportSummary),
+        (status = 400, description = "Validation errors for all invalid entries, with indices"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "More entries than MAX_BULK_ITEMS"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn import_airport_codes(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    CreateJson(payload): CreateJson<Vec<UpsertAirportCode>>,
+) -> Result<(StatusCode, Json<ApiResponse<CodeImportSummary>>), AppError> {
+    check_bulk_size(payload.len(), config.max_bulk_items)?;
+    validate_code_batch(&payload)?;
+    let summary = database::bulk_upsert_airport_codes(&pool, payload).await?;
+    Ok(code_import_response("airport", summary))
+}
+
+/// Bulk import airline codes
+///
+/// Upserts on `code` inside one transaction and bumps the starter data version once
+/// for the whole batch. Requires the `system.settings` permission.
+/// At most `MAX_BULK_ITEMS` entries per request.
+#[utoipa::path(
+    post,
+    path = "/api/codes/airlines/bulk",
+    tag = "Codes",
+    request_body = Vec<UpsertAirlineCode>,
+    responses(
+        (status = 200, description = "Codes imported", body = CodeImportSummary),
+        (status = 400, description = "Validation errors for all invalid entries, with indices"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "More entries than MAX_BULK_ITEMS"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn import_airline_codes(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    CreateJson(payload): CreateJson<Vec<UpsertAirlineCode>>,
+) -> Result<(StatusCode, Json<ApiResponse<CodeImportSummary>>), AppError> {
+    check_bulk_size(payload.len(), config.max_bulk_items)?;
+    validate_code_batch(&payload)?;
+    let summary = database::bulk_upsert_airline_codes(&pool, payload).await?;
+    Ok(code_import_response("airline", summary))
+}
+
+/// Bulk import cabin class codes
+///
+/// Upserts on `code` inside one transaction and bumps the starter data version once
+/// for the whole batch. Requires the `system.settings` permission.
+/// At most `MAX_BULK_ITEMS` entries per request.
+#[utoipa::path(
+    post,
+    path = "/api/codes/classes/bulk",
+    tag = "Codes",
+    request_body = Vec<UpsertCabinClassCode>,
+    responses(
+        (status = 200, description = "Codes imported", body = CodeImportSummary),
+        (status = 400, description = "Validation errors for all invalid entries, with indices"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "More entries than MAX_BULK_ITEMS"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn import_cabin_class_codes(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    CreateJson(payload): CreateJson<Vec<UpsertCabinClassCode>>,
+) -> Result<(StatusCode, Json<ApiResponse<CodeImportSummary>>), AppError> {
+    check_bulk_size(payload.len(), config.max_bulk_items)?;
+    validate_code_batch(&payload)?;
+    let summary = database::bulk_upsert_cabin_class_codes(&pool, payload).await?;
+    Ok(code_import_response("cabin class", summary))
+}
+
 /// Get starter data version
 #[utoipa::path(
     get,
@@ -625,15 +1755,115 @@ pub async fn get_starter_data_version(
     State(pool): State<PgPool>,
 ) -> Result<Json<ApiResponse<crate::models::StarterDataVersion>>, AppError> {
     let version = database::get_starter_data_version(&pool).await?;
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(version),
-        total: None,
-    };
+    let response = ApiResponse::success(version);
+    Ok(Json(response))
+}
+
+// ==================== ADMIN HANDLERS ====================
+
+/// Get effective server configuration (secrets redacted)
+///
+/// Requires a `system.*` permission.
+#[utoipa::path(
+    get,
+    path = "/api/admin/config",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Resolved configuration without secrets", body = EffectiveConfig),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_admin_config(
+    Extension(config): Extension<AppConfig>,
+) -> Result<Json<ApiResponse<EffectiveConfig>>, AppError> {
+    let response = ApiResponse::success(config.effective());
+    Ok(Json(response))
+}
+
+/// Purge operational data (flights, scans, decoded barcodes)
+///
+/// Wipes seeded/test data before go-live in a single transaction; users, roles and
+/// code tables are left intact. Rejection logs are only deleted when
+/// `includeRejectionLogs` is true; the body is optional. Requires a `system.*`
+/// permission and, in production, `ALLOW_DATA_PURGE=true`.
+#[utoipa::path(
+    post,
+    path = "/api/admin/purge-operational-data",
+    tag = "Admin",
+    request_body(content = Option<PurgeOperationalDataRequest>, description = "Optional; defaults to keeping rejection logs"),
+    responses(
+        (status = 200, description = "Operational data deleted", body = PurgeOperationalDataSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Insufficient permissions or purge disabled in production"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn purge_operational_data(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<i32>,
+    Extension(config): Extension<AppConfig>,
+    Extension(stats_cache): Extension<StatsCache>,
+    payload: Option<CreateJson<PurgeOperationalDataRequest>>,
+) -> Result<Json<ApiResponse<PurgeOperationalDataSummary>>, AppError> {
+    // Permission sudah dicek di router (require_permission), jadi caller tanpa hak tidak bisa menebak environment
+    if config.is_production() && !config.allow_data_purge {
+        return Err(AppError::Forbidden(
+            "Data purge is disabled in production (set ALLOW_DATA_PURGE=true)".to_string(),
+        ));
+    }
+    let payload = payload.map(|CreateJson(payload)| payload).unwrap_or_default();
+
+    let summary = database::purge_operational_data(&pool, payload.include_rejection_logs).await?;
+    stats_cache.clear();
+
+    tracing::warn!(
+        admin_id = user_id,
+        deleted_flights = summary.deleted_flights,
+        deleted_scans = summary.deleted_scans,
+        deleted_decoded_barcodes = summary.deleted_decoded_barcodes,
+        deleted_rejection_logs = summary.deleted_rejection_logs,
+        "Operational data purged"
+    );
+
+    let message = format!(
+        "{} flights, {} scans and {} decoded barcodes deleted",
+        summary.deleted_flights, summary.deleted_scans, summary.deleted_decoded_barcodes
+    );
+    let response = ApiResponse::success(summary).with_message(message);
     Ok(Json(response))
 }
 
+/// Get API version and schema hash
+///
+/// `schemaHash` is the SHA-256 of the served OpenAPI spec; clients regenerate
+/// their bindings when it differs from the value they were built against.
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Crate version, OpenAPI schema hash and environment", body = ApiVersionInfo)
+    )
+)]
+pub async fn get_api_version(
+    Extension(config): Extension<AppConfig>,
+) -> Json<ApiResponse<ApiVersionInfo>> {
+    let info = ApiVersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_hash: openapi::schema_hash().to_string(),
+        environment: config.environment,
+    };
+    Json(ApiResponse::success(info))
+}
+
 // ==================== HEALTH CHECK HANDLER ====================
 
 /// Health check endpoint
@@ -645,17 +1875,19 @@ pub async fn get_starter_data_version(
 /// - System uptime
 ///
 /// This endpoint does not require authentication
-/// and can be used by monitoring systems.
+/// and can be used by monitoring systems. An unhealthy (503) response carries
+/// `Retry-After` like every other load-shedding response.
 pub async fn health_check(
     State(pool): State<PgPool>,
-) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
-    let health_info = crate::database_config::health_check(&pool).await;
+    Extension(config): Extension<AppConfig>,
+) -> Result<Response, AppError> {
+    let health_info = crate::database_config::health_check(&pool, &config.db_probe_query).await;
     let status_code = StatusCode::from_u16(health_info.status_code())
         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
     let response = serde_json::json!({
         "status": if health_info.is_healthy { "healthy" } else { "unhealthy" },
-        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "timestamp": format_timestamp(&Utc::now()),
         "database": {
             "is_healthy": health_info.is_healthy,
             "response_time_ms": health_info.response_time.as_millis(),
@@ -669,5 +1901,868 @@ pub async fn health_check(
         "error": health_info.error
     });
 
-    Ok((status_code, Json(response)))
+    if status_code == StatusCode::SERVICE_UNAVAILABLE {
+        let retry_after = [(header::RETRY_AFTER, LOAD_SHED_RETRY_AFTER_SECS.to_string())];
+        return Ok((status_code, retry_after, Json(response)).into_response());
+    }
+    Ok((status_code, Json(response)).into_response())
+}
+
+// Tolak timestamp dari device yang terlalu jauh dari jam server (jam device salah),
+// terpisah dari aturan departure di masa lalu
+fn check_timestamp_skew(
+    field: &str,
+    timestamp: DateTime<Utc>,
+    now: DateTime<Utc>,
+    max_skew_seconds: i64,
+) -> Result<(), AppError> {
+    let skew_seconds = (timestamp - now).num_seconds();
+    if skew_seconds.abs() > max_skew_seconds {
+        return Err(AppError::TimestampSkew {
+            field: field.to_string(),
+            skew_seconds,
+            max_skew_seconds,
+        });
+    }
+    Ok(())
+}
+
+// Waktu terjadwal (departureTime) boleh jauh di masa depan (jadwal dibuat berbulan-bulan
+// sebelumnya); hanya arah masa lalu yang dibatasi MAX_TIMESTAMP_SKEW_SECONDS
+fn check_scheduled_timestamp(
+    field: &str,
+    timestamp: DateTime<Utc>,
+    now: DateTime<Utc>,
+    max_skew_seconds: i64,
+) -> Result<(), AppError> {
+    if timestamp > now {
+        return Ok(());
+    }
+    check_timestamp_skew(field, timestamp, now, max_skew_seconds)
+}
+
+// Kelompokkan flight dengan kunci yang sama seperti unique index flights (nomor + tanggal UTC);
+// indices adalah posisi asli di payload, hanya kelompok dengan lebih dari satu entri yang dikembalikan
+fn find_payload_duplicates(flights: &[CreateFlight], indices: &[usize]) -> Vec<BulkDuplicate> {
+    let mut groups: Vec<BulkDuplicate> = Vec::new();
+    for (flight, &index) in flights.iter().zip(indices) {
+        let departure_date = flight.departure_time.date_naive();
+        match groups
+            .iter_mut()
+            .find(|g| g.flight_number == flight.flight_number && g.departure_date == departure_date)
+        {
+            Some(group) => group.indices.push(index),
+            None => groups.push(BulkDuplicate {
+                flight_number: flight.flight_number.clone(),
+                departure_date,
+                indices: vec![index],
+            }),
+        }
+    }
+    groups.retain(|g| g.indices.len() > 1);
+    groups
+}
+
+// Error validasi per posisi duplikat untuk on_duplicate=reject
+fn duplicate_failures(duplicates: &[BulkDuplicate]) -> Vec<BulkValidationFailure> {
+    let mut failures: Vec<BulkValidationFailure> = duplicates
+        .iter()
+        .flat_map(|d| {
+            d.indices.iter().map(move |&index| {
+                let mut error = validator::ValidationError::new("duplicate_in_payload");
+                error.message = Some(
+                    format!(
+                        "Flight {} on {} appears more than once in the payload (indices {:?})",
+                        d.flight_number, d.departure_date, d.indices
+                    )
+                    .into(),
+                );
+                let mut errors = validator::ValidationErrors::new();
+                errors.add("flight_number", error);
+                BulkValidationFailure { index, errors }
+            })
+        })
+        .collect();
+    failures.sort_by_key(|f| f.index);
+    failures
+}
+
+// Versi bulk dari check_flight_timestamps: skew dicatat sebagai error validasi per flight
+// (code "timestamp_skew") agar ikut dilaporkan bersama error lain dan menghormati partial=true
+fn add_timestamp_skew_errors(
+    errors: &mut validator::ValidationErrors,
+    flight: &CreateFlight,
+    now: DateTime<Utc>,
+    max_skew_seconds: i64,
+) {
+    let checks = [
+        ("departureTime", check_scheduled_timestamp("departureTime", flight.departure_time, now, max_skew_seconds)),
+        ("scannedAt", check_timestamp_skew("scannedAt", flight.scanned_at, now, max_skew_seconds)),
+    ];
+    for (field, check) in checks {
+        if let Err(AppError::TimestampSkew { skew_seconds, .. }) = check {
+            let mut error = validator::ValidationError::new("timestamp_skew");
+            error.message = Some(
+                format!("{} is {}s from server time (max {}s)", field, skew_seconds, max_skew_seconds).into(),
+            );
+            error.add_param("skew_seconds".into(), &skew_seconds);
+            error.add_param("max_skew_seconds".into(), &max_skew_seconds);
+            errors.add(field, error);
+        }
+    }
+}
+
+fn check_flight_timestamps(flight: &CreateFlight, prefix: &str, max_skew_seconds: i64) -> Result<(), AppError> {
+    let now = Utc::now();
+    check_scheduled_timestamp(&format!("{}departureTime", prefix), flight.departure_time, now, max_skew_seconds)?;
+    check_timestamp_skew(&format!("{}scannedAt", prefix), flight.scanned_at, now, max_skew_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[sqlx::test]
+    async fn test_get_flights_count_only_skips_rows(pool: PgPool) {
+        for number in ["GA100", "GA101", "GA102"] {
+            sqlx::query(
+                "INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate) VALUES ($1, 'Garuda Indonesia', 'B738', $2, 'SUB', 'A1')",
+            )
+            .bind(number)
+            .bind(Utc::now() + Duration::days(1))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let query = GetFlightsQuery { date: None, count_only: Some(true) };
+        let Json(response) = get_flights(State(pool), Extension(AppConfig::for_tests()), None, Query(query)).await.unwrap();
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.as_object().unwrap().contains_key("data"));
+        assert!(json["data"].is_null());
+        assert_eq!(json["total"], 3);
+    }
+
+    #[sqlx::test]
+    async fn test_get_flights_caps_large_unpaginated_list(pool: PgPool) {
+        let flights = (0..120).map(|i| bulk_flight(&format!("GA{}", 300 + i), "A1")).collect();
+        database::bulk_insert_flights(&pool, flights, false, BulkConflictMode::Upsert).await.unwrap();
+
+        let query = || GetFlightsQuery { date: None, count_only: None };
+        let Json(response) = get_flights(State(pool.clone()), Extension(AppConfig::for_tests()), None, Query(query()))
+            .await
+            .unwrap();
+        assert_eq!(response.data.unwrap().len(), 120);
+        assert_eq!(response.has_more, None);
+
+        let config = AppConfig { list_response_cap: 110, ..AppConfig::for_tests() };
+        let Json(response) = get_flights(State(pool.clone()), Extension(config.clone()), None, Query(query()))
+            .await
+            .unwrap();
+        assert_eq!(response.data.unwrap().len(), 100);
+        assert_eq!(response.total, Some(120));
+        assert_eq!(response.has_more, Some(true));
+
+        // limit/offset eksplisit dipakai apa adanya, tanpa cap
+        let last_page = Some(Pagination { limit: 50, offset: 100 });
+        let Json(response) = get_flights(State(pool), Extension(config), last_page, Query(query())).await.unwrap();
+        let page = response.data.unwrap();
+        assert_eq!(page.len(), 20);
+        assert_eq!(page[0].flight_number, "GA400");
+        assert_eq!(response.total, Some(120));
+        assert_eq!(response.has_more, Some(false));
+    }
+
+    #[sqlx::test]
+    async fn test_get_flights_for_empty_date_is_clean_success(pool: PgPool) {
+        database::bulk_insert_flights(&pool, vec![bulk_flight("GA370", "A1")], false, BulkConflictMode::Upsert)
+            .await
+            .unwrap();
+
+        let query = GetFlightsQuery {
+            date: Some(chrono::NaiveDate::from_ymd_opt(2001, 1, 1).unwrap()),
+            count_only: None,
+        };
+        let response = get_flights(State(pool), Extension(AppConfig::for_tests()), None, Query(query))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["status"], "success");
+        assert_eq!(json["data"], serde_json::json!([]));
+        assert_eq!(json["total"], 0);
+        assert!(json.get("hasMore").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_api_version_reports_crate_version_and_stable_hash() {
+        let Json(first) = get_api_version(Extension(AppConfig::for_tests())).await;
+        let Json(second) = get_api_version(Extension(AppConfig::for_tests())).await;
+        let (first, second) = (first.data.unwrap(), second.data.unwrap());
+
+        assert_eq!(first.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(first.environment, "development");
+        assert_eq!(first.schema_hash.len(), 64);
+        assert_eq!(first.schema_hash, second.schema_hash);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_database_sets_retry_after() {
+        // Pool ke port yang tidak menerima koneksi: health check gagal dan mengembalikan 503
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(500))
+            .connect_lazy("postgres://falcon@127.0.0.1:1/falcon")
+            .unwrap();
+
+        let response = health_check(State(pool), Extension(AppConfig::for_tests())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap(),
+            LOAD_SHED_RETRY_AFTER_SECS.to_string().as_str()
+        );
+    }
+
+    fn bulk_flight(number: &str, gate: &str) -> CreateFlight {
+        CreateFlight {
+            flight_number: number.to_string(),
+            airline: "Garuda Indonesia".to_string(),
+            aircraft: "B738".to_string(),
+            departure_time: Utc::now() + Duration::days(1),
+            scanned_at: Utc::now(),
+            destination: "SUB".to_string(),
+            gate: gate.to_string(),
+            device_id: None,
+            capacity: None,
+        }
+    }
+
+    // Cache statistik dengan TTL nol, untuk handler yang wajib menerima cache tapi tidak diuji cache-nya
+    fn no_stats_cache() -> Extension<StatsCache> {
+        Extension(StatsCache::new(std::time::Duration::ZERO))
+    }
+
+    // Flight aktif standar (berangkat besok) untuk fixture test; mengembalikan id-nya
+    async fn seed_flight(pool: &PgPool, flight_number: &str) -> i32 {
+        database::bulk_insert_flights(pool, vec![bulk_flight(flight_number, "A1")], false, BulkConflictMode::Upsert)
+            .await
+            .unwrap();
+        sqlx::query_scalar("SELECT id FROM flights WHERE flight_number = $1")
+            .bind(flight_number)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn flight_count(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM flights").fetch_one(pool).await.unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_sync_reports_every_invalid_flight(pool: PgPool) {
+        let payload = || vec![bulk_flight("X", "A1"), bulk_flight("GA200", "A1"), bulk_flight("GA201", "??")];
+
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let config = Extension(AppConfig::for_tests());
+        let result = sync_flights_bulk(
+            State(pool.clone()),
+            config.clone(),
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await;
+        let Err(AppError::BulkValidationError(failures)) = result else {
+            panic!("expected bulk validation error");
+        };
+        assert_eq!(failures.iter().map(|f| f.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert!(failures[0].errors.field_errors().contains_key("flight_number"));
+        assert!(failures[1].errors.field_errors().contains_key("gate"));
+        assert_eq!(flight_count(&pool).await, 0);
+
+        let query = BulkSyncQuery { dry_run: None, partial: Some(true), on_conflict: None, on_duplicate: None };
+        let (status, Json(response)) = sync_flights_bulk(
+            State(pool.clone()),
+            config,
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let summary = response.data.unwrap();
+        assert_eq!(summary.inserted, 1);
+        assert_eq!(summary.invalid.len(), 2);
+        assert_eq!(flight_count(&pool).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_sync_reports_timestamp_skew_per_payload_index(pool: PgPool) {
+        let payload = || {
+            let mut skewed = bulk_flight("GA231", "A1");
+            skewed.scanned_at = Utc::now() - Duration::days(365 * 5);
+            vec![bulk_flight("X", "A1"), bulk_flight("GA230", "A1"), skewed]
+        };
+        let config = Extension(AppConfig::for_tests());
+
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let result = sync_flights_bulk(
+            State(pool.clone()),
+            config.clone(),
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await;
+        let Err(AppError::BulkValidationError(failures)) = result else {
+            panic!("expected bulk validation error");
+        };
+        // Index 2 adalah posisi di payload, bukan di daftar flight yang lolos validasi
+        assert_eq!(failures.iter().map(|f| f.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(failures[1].errors.field_errors()["scannedAt"][0].code, "timestamp_skew");
+        assert_eq!(flight_count(&pool).await, 0);
+
+        let query = BulkSyncQuery { dry_run: None, partial: Some(true), on_conflict: None, on_duplicate: None };
+        let (_, Json(response)) = sync_flights_bulk(
+            State(pool.clone()),
+            config,
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await
+        .unwrap();
+        let summary = response.data.unwrap();
+        assert_eq!(summary.inserted, 1);
+        assert_eq!(summary.invalid.iter().map(|f| f.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(flight_count(&pool).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_sync_rejects_payload_over_max_items(pool: PgPool) {
+        let config = Extension(AppConfig {
+            max_bulk_items: 2,
+            ..AppConfig::for_tests()
+        });
+        let payload = vec![bulk_flight("GA220", "A1"), bulk_flight("GA221", "A2"), bulk_flight("GA222", "A3")];
+
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let result = sync_flights_bulk(
+            State(pool.clone()),
+            config.clone(),
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload),
+        )
+        .await;
+        let Err(error @ AppError::PayloadTooLarge { items: 3, max_items: 2 }) = result else {
+            panic!("expected payload too large");
+        };
+        assert_eq!(error.into_response().status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(flight_count(&pool).await, 0);
+
+        // Tepat di batas tetap diterima
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let payload = vec![bulk_flight("GA220", "A1"), bulk_flight("GA221", "A2")];
+        let (status, _) = sync_flights_bulk(
+            State(pool.clone()),
+            config,
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(flight_count(&pool).await, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_sync_reports_payload_duplicates(pool: PgPool) {
+        let payload = || vec![bulk_flight("GA210", "A1"), bulk_flight("GA211", "B1"), bulk_flight("GA210", "C3")];
+        let config = Extension(AppConfig::for_tests());
+
+        // Default keep_last: entri terakhir yang disimpan, duplikat dilaporkan
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let (_, Json(response)) = sync_flights_bulk(
+            State(pool.clone()),
+            config.clone(),
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await
+        .unwrap();
+        let summary = response.data.unwrap();
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.updated, 0);
+        assert_eq!(summary.duplicates.len(), 1);
+        assert_eq!(summary.duplicates[0].flight_number, "GA210");
+        assert_eq!(summary.duplicates[0].indices, vec![0, 2]);
+        let gate: String = sqlx::query_scalar("SELECT gate FROM flights WHERE flight_number = 'GA210'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(gate, "C3");
+
+        // reject: seluruh batch ditolak dengan posisi setiap duplikat
+        sqlx::query("DELETE FROM flights").execute(&pool).await.unwrap();
+        let query = BulkSyncQuery {
+            dry_run: None,
+            partial: None,
+            on_conflict: None,
+            on_duplicate: Some(BulkDuplicateMode::Reject),
+        };
+        let result = sync_flights_bulk(
+            State(pool.clone()),
+            config,
+            no_stats_cache(),
+            Query(query),
+            CreateJson(payload()),
+        )
+        .await;
+        let Err(AppError::BulkValidationError(failures)) = result else {
+            panic!("expected duplicates to be rejected");
+        };
+        assert_eq!(failures.iter().map(|f| f.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert!(failures[0].errors.field_errors().contains_key("flight_number"));
+        assert_eq!(flight_count(&pool).await, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_new_scan_invalidates_cached_statistics(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA350").await;
+        let cache = StatsCache::new(std::time::Duration::from_secs(60));
+        let total_scans = |pool: PgPool, cache: StatsCache| async move {
+            let query = FlightStatisticsQuery { zero_fill: None };
+            let Json(response) = get_flight_statistics(State(pool), Extension(cache), Path(flight_id), Query(query))
+                .await
+                .unwrap();
+            response.data.unwrap().total_scans
+        };
+        assert_eq!(total_scans(pool.clone(), cache.clone()).await, 0);
+
+        // Scan yang masuk di luar create_scan tidak terlihat selama TTL (masih dari cache)
+        sqlx::query("INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id) VALUES ('PAX-A', 'PDF417', 'device-1', $1)")
+            .bind(flight_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(total_scans(pool.clone(), cache.clone()).await, 0);
+
+        let scan = ScanDataInput {
+            barcode_value: "PAX-B".to_string(),
+            barcode_format: "PDF417".to_string(),
+            device_id: "device-1".to_string(),
+            flight_id,
+            scanned_at: None,
+        };
+        let (status, _) = create_scan(
+            State(pool.clone()),
+            Extension(AppConfig::for_tests()),
+            Extension(cache.clone()),
+            Query(CreateScanQuery::default()),
+            CreateJson(scan),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(total_scans(pool.clone(), cache).await, 2);
+    }
+
+    #[sqlx::test]
+    async fn test_flight_writes_invalidate_cached_statistics(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA351").await;
+        let cache = StatsCache::new(std::time::Duration::from_secs(60));
+        let statistics = |pool: PgPool, cache: StatsCache| async move {
+            let query = FlightStatisticsQuery { zero_fill: None };
+            get_flight_statistics(State(pool), Extension(cache), Path(flight_id), Query(query))
+                .await
+                .map(|Json(response)| response.data.unwrap().total_scans)
+        };
+        assert_eq!(statistics(pool.clone(), cache.clone()).await.unwrap(), 0);
+
+        sqlx::query("INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id) VALUES ('PAX-A', 'PDF417', 'device-1', $1)")
+            .bind(flight_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(statistics(pool.clone(), cache.clone()).await.unwrap(), 0);
+
+        // Update flight membuang cache-nya
+        let payload = UpdateFlight {
+            airline: None,
+            aircraft: None,
+            departure_time: None,
+            destination: None,
+            gate: Some("B2".to_string()),
+            is_active: None,
+            capacity: None,
+            clear_fields: vec![],
+        };
+        let Json(response) = update_flight(State(pool.clone()), Extension(cache.clone()), Path(flight_id), Json(payload))
+            .await
+            .unwrap();
+        assert_eq!(response.data.unwrap().gate, "B2");
+        assert_eq!(statistics(pool.clone(), cache.clone()).await.unwrap(), 1);
+
+        // Flight yang dihapus tidak lagi dilayani dari cache
+        let status = delete_flight(State(pool.clone()), Extension(cache.clone()), Path(flight_id)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(matches!(statistics(pool, cache).await, Err(AppError::FlightNotFound)));
+    }
+
+    async fn export_body(pool: &PgPool, format: ExportFormat) -> (Option<String>, String) {
+        let query = ExportFlightsQuery {
+            from: chrono::NaiveDate::from_ymd_opt(2030, 4, 1).unwrap(),
+            to: chrono::NaiveDate::from_ymd_opt(2030, 4, 2).unwrap(),
+            format,
+            include_inactive: None,
+        };
+        let response = export_flights(State(pool.clone()), Query(query)).await.unwrap();
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (content_type, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[sqlx::test]
+    async fn test_decode_endpoint_accepts_short_infant_barcode(pool: PgPool) {
+        // Shared parser is canonical (minimum 50 chars); the old 58-char gate must not come back
+        for barcode in [
+            "M1MAYZURA/AUFARIZA HANEBJQUJW CGKUPGID 6296 147Y0INF0097 100",
+            "M1MAYZURA/AUFARIZA HANEBJQUJW CGKUPGID 6296 147Y0INF0097",
+        ] {
+            let payload = DecodeRequest {
+                barcode_value: barcode.to_string(),
+                scan_data_id: None,
+                name_format: Default::default(),
+                barcode_format: None,
+            };
+            let (status, Json(response)) = decode_barcode(
+                State(pool.clone()),
+                Extension(AppConfig::for_tests()),
+                no_stats_cache(),
+                CreateJson(payload),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::CREATED);
+            let decoded = response.data.unwrap();
+            assert_eq!(decoded.booking_code, "BJQUJW");
+            assert_eq!(decoded.flight_number_raw, "6296");
+            assert!(decoded.infant_status);
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_decode_endpoint_resolves_flight_date(pool: PgPool) {
+        use chrono::Datelike;
+        let today = Utc::now().date_naive();
+        let payload = DecodeRequest {
+            barcode_value: format!("M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 {:03}Y023B0045 300", today.ordinal()),
+            scan_data_id: None,
+            name_format: Default::default(),
+            barcode_format: None,
+        };
+        let (_, Json(response)) =
+            decode_barcode(State(pool), Extension(AppConfig::for_tests()), no_stats_cache(), CreateJson(payload))
+                .await
+                .unwrap();
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["data"]["flightDate"], today.format("%Y-%m-%d").to_string());
+    }
+
+    #[sqlx::test]
+    async fn test_rejection_logs_include_partial_decode_for_format_rejections(pool: PgPool) {
+        // Boarding pass terpotong setelah nomor flight: parser penuh gagal
+        let barcode = "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473";
+        for reason in ["invalid_format", "date_mismatch"] {
+            let log = CreateRejectionLog {
+                barcode_value: barcode.to_string(),
+                barcode_format: "PDF417".to_string(),
+                reason: reason.to_string(),
+                expected_date: None,
+                actual_date: None,
+                flight_number: None,
+                airline: None,
+                device_id: Some("device-1".to_string()),
+            };
+            database::create_rejection_log(&pool, log).await.unwrap();
+        }
+
+        let query = BarcodeValueQuery { value: barcode.to_string() };
+        let Json(response) = get_rejection_logs_by_barcode(State(pool), Query(query)).await.unwrap();
+        let entries = response.data.unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let invalid_format = entries.iter().find(|e| e.log.reason == "invalid_format").unwrap();
+        let partial = invalid_format.partial_decode.as_ref().unwrap();
+        assert_eq!(partial.booking_code.as_deref(), Some("SMMTHQ"));
+        assert_eq!(partial.airline_code.as_deref(), Some("ID"));
+        assert_eq!(partial.flight_number.as_deref(), Some("6473"));
+        assert_eq!(partial.flight_date_julian, None);
+
+        let date_mismatch = entries.iter().find(|e| e.log.reason == "date_mismatch").unwrap();
+        assert!(date_mismatch.partial_decode.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_failed_decode_creates_rejection_log_when_enabled(pool: PgPool) {
+        let flight_id = seed_flight(&pool, "GA230").await;
+        let scan_id: i32 = sqlx::query_scalar(
+            "INSERT INTO scan_data (barcode_value, barcode_format, device_id, flight_id) VALUES ('NOT-A-BCBP', 'AZTEC', 'device-7', $1) RETURNING id",
+        )
+        .bind(flight_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let payload = || DecodeRequest {
+            barcode_value: "NOT-A-BCBP".to_string(),
+            scan_data_id: Some(scan_id),
+            name_format: Default::default(),
+            barcode_format: None,
+        };
+        let rejection_count = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM rejection_logs").fetch_one(&pool).await.unwrap()
+        };
+
+        // Default off: tidak ada yang dicatat
+        let result = decode_barcode(
+            State(pool.clone()),
+            Extension(AppConfig::for_tests()),
+            no_stats_cache(),
+            CreateJson(payload()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::InvalidBarcodeFormat)));
+        assert_eq!(rejection_count().await, 0);
+
+        let config = AppConfig {
+            auto_rejection_logs: true,
+            ..AppConfig::for_tests()
+        };
+        let result = decode_barcode(
+            State(pool.clone()),
+            Extension(config),
+            no_stats_cache(),
+            CreateJson(payload()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::InvalidBarcodeFormat)));
+        assert_eq!(rejection_count().await, 1);
+
+        let log = &database::get_rejection_logs_by_barcode(&pool, "NOT-A-BCBP").await.unwrap()[0];
+        assert_eq!(log.reason, "invalid_format");
+        assert_eq!(log.barcode_format, "AZTEC");
+        assert_eq!(log.device_id.as_deref(), Some("device-7"));
+        assert_eq!(log.flight_number.as_deref(), Some("GA230"));
+    }
+
+    #[sqlx::test]
+    async fn test_lookup_flight_by_number_and_date(pool: PgPool) {
+        let departure = chrono::NaiveDate::from_ymd_opt(2030, 5, 12).unwrap().and_hms_opt(23, 30, 0).unwrap().and_utc();
+        let flight = CreateFlight {
+            departure_time: departure,
+            ..bulk_flight("GA312", "A1")
+        };
+        // Tersimpan dengan format yang tidak dinormalisasi
+        let formatted = CreateFlight {
+            departure_time: departure,
+            ..bulk_flight("qz-7510", "B2")
+        };
+        database::bulk_insert_flights(&pool, vec![flight, formatted], false, BulkConflictMode::Upsert).await.unwrap();
+
+        let lookup = |flight_number: &str, day: u32| {
+            Query(FlightLookupQuery {
+                flight_number: flight_number.to_string(),
+                date: chrono::NaiveDate::from_ymd_opt(2030, 5, day).unwrap(),
+            })
+        };
+
+        let Json(response) = lookup_flight(State(pool.clone()), lookup(" ga-312", 12)).await.unwrap();
+        let found = response.data.unwrap();
+        assert_eq!(found.flight_number, "GA312");
+        assert_eq!(found.departure_time, departure);
+
+        let Json(response) = lookup_flight(State(pool.clone()), lookup("QZ7510", 12)).await.unwrap();
+        assert_eq!(response.data.unwrap().flight_number, "qz-7510");
+
+        // Tanggal lain (UTC) tidak ditemukan
+        assert!(matches!(
+            lookup_flight(State(pool.clone()), lookup("GA312", 13)).await,
+            Err(AppError::FlightNotFound)
+        ));
+        assert!(matches!(
+            lookup_flight(State(pool), lookup(" - ", 12)).await,
+            Err(AppError::InvalidQueryParam(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_export_flights_json_and_csv(pool: PgPool) {
+        let at = |d: u32, h: u32| chrono::NaiveDate::from_ymd_opt(2030, 4, d).unwrap().and_hms_opt(h, 0, 0).unwrap().and_utc();
+        let mut first = bulk_flight("GA450", "A1");
+        first.departure_time = at(1, 23);
+        first.airline = "Garuda, Indonesia".to_string();
+        let mut second = bulk_flight("GA451", "B2");
+        second.departure_time = at(2, 1);
+        second.airline = "=HYPERLINK(\"http://x\")".to_string();
+        let mut outside = bulk_flight("GA452", "C3");
+        outside.departure_time = at(3, 8);
+        database::bulk_insert_flights(&pool, vec![first, second, outside], false, BulkConflictMode::Upsert).await.unwrap();
+
+        let (content_type, body) = export_body(&pool, ExportFormat::Json).await;
+        assert_eq!(content_type.as_deref(), Some("application/json"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["data"][0]["flightNumber"], "GA450");
+        assert_eq!(json["data"][1]["flightNumber"], "GA451");
+
+        let (content_type, body) = export_body(&pool, ExportFormat::Csv).await;
+        assert_eq!(content_type.as_deref(), Some("text/csv; charset=utf-8"));
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("id,flight_number,airline"));
+        assert!(lines[1].contains(",GA450,\"Garuda, Indonesia\",B738,2030-04-01T23:00:00.000Z,"));
+        assert!(lines[2].contains(",GA451,"));
+        // Formula injection: sel diawali '=' diberi prefix '
+        assert!(lines[2].contains(",\"'=HYPERLINK(\"\"http://x\"\")\","));
+    }
+
+    #[sqlx::test]
+    async fn test_purge_operational_data_keeps_users_and_codes(pool: PgPool) {
+        let count = |table: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let flight_id = seed_flight(&pool, "JT610").await;
+        seed_flight(&pool, "JT611").await;
+        let scan = ScanDataInput {
+            barcode_value: crate::parser_selftest::GOLDEN_SAMPLES[3].barcode.to_string(),
+            barcode_format: "PDF417".to_string(),
+            device_id: "device-1".to_string(),
+            flight_id,
+            scanned_at: None,
+        };
+        let config = AppConfig::for_tests();
+        database::create_scan_data_with_decode(
+            &pool,
+            scan,
+            config.max_barcode_length,
+            config.scan_time_max_skew_seconds,
+            config.cabin_class_fallback.as_deref(),
+        )
+        .await
+            .unwrap();
+        let log = CreateRejectionLog {
+            barcode_value: "NOT-A-BCBP".to_string(),
+            barcode_format: "PDF417".to_string(),
+            reason: "invalid_format".to_string(),
+            expected_date: None,
+            actual_date: None,
+            flight_number: None,
+            airline: None,
+            device_id: None,
+        };
+        database::create_rejection_log(&pool, log).await.unwrap();
+        let superuser: i32 = sqlx::query_scalar("SELECT id FROM users WHERE username = 'superuser'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        // Production tanpa ALLOW_DATA_PURGE: endpoint mati total
+        let production = AppConfig {
+            environment: "production".to_string(),
+            ..AppConfig::for_tests()
+        };
+        let purge = |user_id: i32, config: AppConfig| {
+            purge_operational_data(
+                State(pool.clone()),
+                Extension(user_id),
+                Extension(config),
+                no_stats_cache(),
+                None,
+            )
+        };
+        let Err(AppError::Forbidden(message)) = purge(superuser, production).await else {
+            panic!("expected purge to be disabled in production");
+        };
+        assert!(message.contains("ALLOW_DATA_PURGE"));
+        assert_eq!(count("flights").await, 2);
+
+        let (users, airports) = (count("users").await, count("airport_codes").await);
+
+        let Json(response) = purge(superuser, AppConfig::for_tests()).await.unwrap();
+        let summary = response.data.unwrap();
+        assert_eq!(summary.deleted_flights, 2);
+        assert_eq!(summary.deleted_scans, 1);
+        assert_eq!(summary.deleted_decoded_barcodes, 1);
+        assert_eq!(summary.deleted_rejection_logs, 0);
+
+        assert_eq!(count("flights").await, 0);
+        assert_eq!(count("scan_data").await, 0);
+        assert_eq!(count("decode_barcode").await, 0);
+        assert_eq!(count("rejection_logs").await, 1);
+        assert_eq!(count("users").await, users);
+        assert_eq!(count("airport_codes").await, airports);
+    }
+
+    #[test]
+    fn test_timestamp_skew_limits() {
+        let now = Utc::now();
+        let max_skew = 3600;
+
+        // Jam device sedikit meleset masih diterima
+        assert!(check_timestamp_skew("scannedAt", now - Duration::minutes(30), now, max_skew).is_ok());
+        assert!(check_timestamp_skew("departureTime", now + Duration::minutes(59), now, max_skew).is_ok());
+
+        // Jam device meleset bertahun-tahun ditolak, ke arah mana pun
+        let Err(AppError::TimestampSkew { field, skew_seconds, .. }) =
+            check_timestamp_skew("scannedAt", now - Duration::days(365 * 5), now, max_skew)
+        else {
+            panic!("expected timestamp skew error");
+        };
+        assert_eq!(field, "scannedAt");
+        assert!(skew_seconds < -max_skew);
+        assert!(check_timestamp_skew("scannedAt", now + Duration::days(400), now, max_skew).is_err());
+
+        // Jadwal keberangkatan hanya dibatasi ke arah masa lalu
+        assert!(check_scheduled_timestamp("departureTime", now + Duration::days(400), now, max_skew).is_ok());
+        assert!(check_scheduled_timestamp("departureTime", now - Duration::days(2), now, max_skew).is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_flight_scheduled_45_days_ahead_is_accepted(pool: PgPool) {
+        let config = AppConfig::for_tests();
+        let mut flight = bulk_flight("GA245", "A1");
+        flight.departure_time = Utc::now() + Duration::days(45);
+        assert!(Duration::days(45).num_seconds() > config.max_timestamp_skew_seconds);
+
+        check_flight_timestamps(&flight, "", config.max_timestamp_skew_seconds).unwrap();
+
+        let query = BulkSyncQuery { dry_run: None, partial: None, on_conflict: None, on_duplicate: None };
+        let (status, Json(response)) = sync_flights_bulk(
+            State(pool.clone()),
+            Extension(config),
+            no_stats_cache(),
+            Query(query),
+            CreateJson(vec![flight]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(response.data.unwrap().inserted, 1);
+    }
 }
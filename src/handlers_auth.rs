@@ -1,13 +1,17 @@
 use crate::{
+    config::AppConfig,
     database_auth,
     errors::AppError,
+    extractors::{CreateJson, Pagination, Path},
     models::{
         ApiResponse, LoginRequest, LoginResponse, CreateUserRequest, UpdateUserRequest,
         ChangePasswordRequest, ResetUserPasswordRequest, User, UserWithRole, Role, RoleWithPermissions, ListUsersQuery,
+        LogoutAllResponse, Permission, ListPermissionsQuery, RevokeSessionsByDeviceRequest,
+        RevokeSessionsByDeviceResponse, UserActivityEvent, TokenValidation,
     },
 };
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Query, State},
     http::{StatusCode, HeaderMap},
     Json,
     Extension,
@@ -26,11 +30,13 @@ use validator::Validate;
     responses(
         (status = 200, description = "Login successful", body = LoginResponse),
         (status = 401, description = "Invalid credentials"),
+        (status = 409, description = "Active session limit reached (MAX_SESSIONS_PER_USER, reject policy)"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn login(
     State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
     headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
@@ -54,6 +60,7 @@ pub async fn login(
         &payload.password,
         payload.device_info.clone(),
         ip_address,
+        &database_auth::AuthSettings::from_config(&config),
     )
     .await?;
 
@@ -64,12 +71,7 @@ pub async fn login(
         "Login successful"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Login successful".to_string()),
-        data: Some(login_response),
-        total: None,
-    };
+    let response = ApiResponse::success(login_response).with_message("Login successful");
 
     Ok(Json(response))
 }
@@ -106,12 +108,120 @@ pub async fn logout(
 
     tracing::info!(user_id = user_id, "Logout successful");
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Logout successful".to_string()),
-        data: None,
-        total: None,
-    };
+    let response = ApiResponse::message_only("Logout successful");
+
+    Ok(Json(response))
+}
+
+/// Validate a bearer token
+///
+/// Runs the same checks as protected routes (signature, expiry, active session)
+/// without any side effects. Invalid, expired or revoked tokens get 401.
+#[utoipa::path(
+    get,
+    path = "/api/auth/validate",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "Token is valid", body = TokenValidation),
+        (status = 401, description = "Missing, invalid, expired or revoked token"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn validate_token(
+    State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<TokenValidation>>, AppError> {
+    let token = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized("Missing or invalid token".to_string()))?;
+
+    let settings = database_auth::AuthSettings::from_config(&config);
+    let validation = database_auth::validate_token(&pool, token, &settings).await?;
+
+    let response = ApiResponse::success(validation);
+
+    Ok(Json(response))
+}
+
+/// Logout from all devices
+///
+/// Revokes every active session of the current user, including this one.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout-all",
+    tag = "Authentication",
+    responses(
+        (status = 200, description = "All sessions revoked", body = LogoutAllResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn logout_all(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<ApiResponse<LogoutAllResponse>>, AppError> {
+    tracing::info!(user_id = user_id, "Logout-all request");
+
+    let revoked_sessions = database_auth::revoke_all_sessions(&pool, user_id).await?;
+
+    tracing::info!(user_id = user_id, revoked_sessions = revoked_sessions, "All sessions revoked");
+
+    let response = ApiResponse::success(LogoutAllResponse { revoked_sessions })
+        .with_message(format!("{} sessions revoked", revoked_sessions));
+
+    Ok(Json(response))
+}
+
+/// Revoke all sessions of a lost device
+///
+/// Revokes every active session whose `device_info` matches, for all users.
+/// Requires the `users.update` permission.
+#[utoipa::path(
+    post,
+    path = "/api/admin/sessions/revoke-by-device",
+    tag = "Admin",
+    request_body = RevokeSessionsByDeviceRequest,
+    responses(
+        (status = 200, description = "Sessions revoked", body = RevokeSessionsByDeviceResponse),
+        (status = 400, description = "Validation error"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn revoke_sessions_by_device(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<RevokeSessionsByDeviceRequest>,
+) -> Result<Json<ApiResponse<RevokeSessionsByDeviceResponse>>, AppError> {
+    payload.validate()?;
+
+    let revoked_sessions = database_auth::revoke_sessions_by_device(&pool, &payload.device_info).await?;
+
+    tracing::info!(
+        admin_id = user_id,
+        device_info = %payload.device_info,
+        revoked_sessions = revoked_sessions,
+        "Sessions revoked for device"
+    );
+
+    let response = ApiResponse::success(RevokeSessionsByDeviceResponse {
+        device_info: payload.device_info,
+        revoked_sessions,
+    })
+    .with_message(format!("{} sessions revoked", revoked_sessions));
 
     Ok(Json(response))
 }
@@ -136,12 +246,7 @@ pub async fn get_current_user(
 ) -> Result<Json<ApiResponse<UserWithRole>>, AppError> {
     let user = database_auth::get_user_with_role(&pool, user_id).await?;
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(user),
-        total: None,
-    };
+    let response = ApiResponse::success(user);
 
     Ok(Json(response))
 }
@@ -181,12 +286,7 @@ pub async fn change_password(
 
     tracing::info!(user_id = user_id, "Password changed successfully");
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Password changed successfully".to_string()),
-        data: None,
-        total: None,
-    };
+    let response = ApiResponse::message_only("Password changed successfully");
 
     Ok(Json(response))
 }
@@ -203,6 +303,7 @@ pub async fn change_password(
         (status = 201, description = "User created successfully", body = UserWithRole),
         (status = 400, description = "Validation error"),
         (status = 403, description = "Insufficient permissions"),
+        (status = 409, description = "Username or email already exists"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -212,7 +313,7 @@ pub async fn change_password(
 pub async fn create_user(
     State(pool): State<PgPool>,
     Extension(creator_id): Extension<i32>,
-    Json(payload): Json<CreateUserRequest>,
+    CreateJson(payload): CreateJson<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<ApiResponse<UserWithRole>>), AppError> {
     tracing::info!(
         username = %payload.username,
@@ -231,12 +332,7 @@ pub async fn create_user(
         "User created successfully"
     );
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("User created successfully".to_string()),
-        data: Some(user),
-        total: None,
-    };
+    let response = ApiResponse::success(user).with_message("User created successfully");
 
     Ok((StatusCode::CREATED, Json(response)))
 }
@@ -249,11 +345,12 @@ pub async fn create_user(
     params(
         ("role_id" = Option<i32>, Query, description = "Filter by role ID"),
         ("is_active" = Option<bool>, Query, description = "Filter by active status"),
-        ("limit" = Option<i64>, Query, description = "Limit results"),
+        ("limit" = Option<i64>, Query, description = "Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)"),
         ("offset" = Option<i64>, Query, description = "Offset for pagination")
     ),
     responses(
         (status = 200, description = "List of users", body = Vec<UserWithRole>),
+        (status = 400, description = "Invalid limit/offset"),
         (status = 403, description = "Insufficient permissions"),
         (status = 500, description = "Internal server error")
     ),
@@ -263,16 +360,12 @@ pub async fn create_user(
 )]
 pub async fn list_users(
     State(pool): State<PgPool>,
+    pagination: Pagination,
     Query(query): Query<ListUsersQuery>,
 ) -> Result<Json<ApiResponse<Vec<UserWithRole>>>, AppError> {
-    let (users, total) = database_auth::list_users(&pool, query).await?;
+    let (users, total) = database_auth::list_users(&pool, query, pagination).await?;
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(users),
-        total: Some(total as u64),
-    };
+    let response = ApiResponse::list(users, total as u64, None);
 
     Ok(Json(response))
 }
@@ -301,17 +394,53 @@ pub async fn get_user_by_id(
 ) -> Result<Json<ApiResponse<UserWithRole>>, AppError> {
     let user = database_auth::get_user_with_role(&pool, id).await?;
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(user),
-        total: None,
-    };
+    let response = ApiResponse::success(user);
+
+    Ok(Json(response))
+}
+
+/// Get a user's recent activity
+///
+/// Chronological timeline (newest first) of flights the user created, logins and
+/// logouts, users they created, and scans taken on their devices while they were logged in.
+/// Paginated with `limit`/`offset` like other lists. Requires the `users.read` permission.
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/activity",
+    tag = "Users",
+    params(
+        ("id" = i32, Path, description = "User ID"),
+        ("limit" = Option<i64>, Query, description = "Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)"),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination")
+    ),
+    responses(
+        (status = 200, description = "User activity, newest first", body = Vec<UserActivityEvent>),
+        (status = 400, description = "Invalid limit/offset"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_user_activity(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    pagination: Pagination,
+) -> Result<Json<ApiResponse<Vec<UserActivityEvent>>>, AppError> {
+    let events = database_auth::get_user_activity(&pool, id, pagination).await?;
+    let total = events.len() as u64;
+
+    let response = ApiResponse::list(events, total, None);
 
     Ok(Json(response))
 }
 
 /// Update user
+///
+/// Changing `roleId` revokes the user's sessions (unless `REVOKE_SESSIONS_ON_ROLE_CHANGE=false`),
+/// so existing tokens with the old permissions stop working.
 #[utoipa::path(
     put,
     path = "/api/users/{id}",
@@ -333,6 +462,7 @@ pub async fn get_user_by_id(
 )]
 pub async fn update_user(
     State(pool): State<PgPool>,
+    Extension(config): Extension<AppConfig>,
     Path(id): Path<i32>,
     Json(payload): Json<UpdateUserRequest>,
 ) -> Result<Json<ApiResponse<UserWithRole>>, AppError> {
@@ -340,16 +470,11 @@ pub async fn update_user(
 
     payload.validate()?;
 
-    let user = database_auth::update_user(&pool, id, payload).await?;
+    let user = database_auth::update_user(&pool, id, payload, config.revoke_sessions_on_role_change).await?;
 
     tracing::info!(user_id = id, "User updated successfully");
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("User updated successfully".to_string()),
-        data: Some(user),
-        total: None,
-    };
+    let response = ApiResponse::success(user).with_message("User updated successfully");
 
     Ok(Json(response))
 }
@@ -415,12 +540,7 @@ pub async fn reset_user_password(
 
     tracing::info!(user_id = id, "Password reset successfully by admin");
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: Some("Password reset successfully".to_string()),
-        data: None,
-        total: None,
-    };
+    let response = ApiResponse::message_only("Password reset successfully");
 
     Ok(Json(response))
 }
@@ -445,12 +565,7 @@ pub async fn list_roles(
 ) -> Result<Json<ApiResponse<Vec<Role>>>, AppError> {
     let roles = database_auth::list_roles(&pool).await?;
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(roles),
-        total: None,
-    };
+    let response = ApiResponse::success(roles);
 
     Ok(Json(response))
 }
@@ -478,12 +593,41 @@ pub async fn get_role_by_id(
 ) -> Result<Json<ApiResponse<RoleWithPermissions>>, AppError> {
     let role = database_auth::get_role_with_permissions(&pool, id).await?;
 
-    let response = ApiResponse {
-        status: "success".to_string(),
-        message: None,
-        data: Some(role),
-        total: None,
-    };
+    let response = ApiResponse::success(role);
+
+    Ok(Json(response))
+}
+
+// ==================== PERMISSION CATALOG HANDLERS ====================
+
+/// Get all permissions (catalog for building role editors)
+#[utoipa::path(
+    get,
+    path = "/api/permissions",
+    tag = "Roles",
+    params(
+        ("resource" = Option<String>, Query, description = "Filter by resource (e.g. flights, users)"),
+        ("limit" = Option<i64>, Query, description = "Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)"),
+        ("offset" = Option<i64>, Query, description = "Offset for pagination")
+    ),
+    responses(
+        (status = 200, description = "List of permissions", body = Vec<Permission>),
+        (status = 400, description = "Invalid limit/offset"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_permissions(
+    State(pool): State<PgPool>,
+    pagination: Pagination,
+    Query(query): Query<ListPermissionsQuery>,
+) -> Result<Json<ApiResponse<Vec<Permission>>>, AppError> {
+    let (permissions, total) =
+        database_auth::list_permissions(&pool, query.resource.as_deref(), pagination).await?;
+
+    let response = ApiResponse::list(permissions, total as u64, None);
 
     Ok(Json(response))
 }
@@ -1,17 +1,26 @@
 use axum::{
     extract::{Request, State},
-    http::{StatusCode, HeaderValue},
+    http::StatusCode,
     middleware::Next,
     response::Response,
 };
 use sqlx::PgPool;
 
+use crate::{database_auth::AuthSettings, errors::AppError};
+
+/// State for `jwt_auth_middleware`
+#[derive(Clone)]
+pub struct JwtAuth {
+    pub pool: PgPool,
+    pub settings: AuthSettings,
+}
+
 /// JWT Authentication Middleware
 ///
 /// Extracts and validates JWT token from Authorization header.
 /// Adds user_id to request extensions for use in handlers.
 pub async fn jwt_auth_middleware(
-    State(pool): State<PgPool>,
+    State(auth): State<JwtAuth>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -28,7 +37,7 @@ pub async fn jwt_auth_middleware(
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Verify token and get user_id
-    let user_id = crate::database_auth::verify_token(&pool, token)
+    let user_id = crate::database_auth::verify_token(&auth.pool, token, &auth.settings)
         .await
         .map_err(|e| {
             tracing::warn!("JWT verification failed: {:?}", e);
@@ -38,32 +47,19 @@ pub async fn jwt_auth_middleware(
     // Add user_id to request extensions
     req.extensions_mut().insert(user_id);
 
-    // Add security headers to response
-    let mut response = next.run(req).await;
-    let headers = response.headers_mut();
-
-    headers.insert(
-        "X-Content-Type-Options",
-        HeaderValue::from_static("nosniff"),
-    );
-    headers.insert(
-        "X-Frame-Options",
-        HeaderValue::from_static("SAMEORIGIN"),
-    );
-    headers.insert(
-        "X-XSS-Protection",
-        HeaderValue::from_static("1; mode=block"),
-    );
-
-    Ok(response)
+    Ok(next.run(req).await)
 }
 
+/// Future returned by the `require_permission` middleware
+type PermissionFuture = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Response, AppError>> + Send>>;
+
 /// Permission check middleware
 ///
-/// Verifies that the authenticated user has the required permission.
-pub async fn require_permission(
+/// Verifies that the authenticated user has the required permission; any `system.*`
+/// permission acts as superuser. Apply with `route_layer` inside the JWT-protected router.
+pub fn require_permission(
     permission: String,
-) -> impl Fn(State<PgPool>, Request, Next) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Response, StatusCode>> + Send>> + Clone {
+) -> impl Fn(State<PgPool>, Request, Next) -> PermissionFuture + Clone {
     move |State(pool): State<PgPool>, req: Request, next: Next| {
         let perm = permission.clone();
         Box::pin(async move {
@@ -72,15 +68,10 @@ pub async fn require_permission(
                 .extensions()
                 .get::<i32>()
                 .copied()
-                .ok_or(StatusCode::UNAUTHORIZED)?;
+                .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
             // Get user permissions
-            let permissions = crate::database_auth::get_user_permissions(&pool, user_id)
-                .await
-                .map_err(|e| {
-                    tracing::error!("Failed to get user permissions: {:?}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
+            let permissions = crate::database_auth::get_user_permissions(&pool, user_id).await?;
 
             // Check if user has required permission or is superuser
             let has_permission = permissions.contains(&perm)
@@ -92,10 +83,79 @@ pub async fn require_permission(
                     required_permission = %perm,
                     "Insufficient permissions"
                 );
-                return Err(StatusCode::FORBIDDEN);
+                return Err(AppError::Forbidden("Insufficient permissions".to_string()));
             }
 
             Ok(next.run(req).await)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use axum::{body::Body, http::header};
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn test_permission_checked_before_handler(pool: PgPool) {
+        let production = AppConfig {
+            environment: "production".to_string(),
+            ..AppConfig::for_tests()
+        };
+        let app = crate::router::create_router(pool.clone(), &production);
+
+        // Superuser punya system.*; role user tidak
+        let password_hash = bcrypt::hash("test-password", 4).unwrap();
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE username = 'superuser'")
+            .bind(&password_hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO users (username, email, password_hash, full_name, role_id) \
+             VALUES ('gate01', 'gate01@example.com', $1, 'Petugas Gate', (SELECT id FROM roles WHERE name = 'user'))",
+        )
+        .bind(&password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let settings = AuthSettings::from_config(&production);
+        let login = |username: &'static str| {
+            crate::database_auth::authenticate_user(
+                &pool,
+                username,
+                "test-password",
+                None,
+                None,
+                &settings,
+            )
+        };
+        let superuser = login("superuser").await.unwrap().token;
+        let operator = login("gate01").await.unwrap().token;
+
+        let purge = |token: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/admin/purge-operational-data")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let message = |response: axum::response::Response| async {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            json["message"].as_str().unwrap().to_string()
+        };
+
+        // User tanpa permission ditolak sebelum gate production (environment tidak bocor)
+        let response = app.clone().oneshot(purge(&operator)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(message(response).await, "Insufficient permissions");
+
+        let response = app.oneshot(purge(&superuser)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(message(response).await.contains("ALLOW_DATA_PURGE"));
+    }
+}
@@ -0,0 +1,262 @@
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+
+/// Nama file log error (dirotasi harian)
+const LOG_FILE_NAME: &str = "falcon-errors.log";
+
+/// Mode anonimisasi data penumpang di log; di-set sekali saat startup dari `REDACT_PII_LOGS`
+static REDACT_PII: AtomicBool = AtomicBool::new(false);
+
+/// Key JSON yang memuat data penumpang. Barcode BCBP mentah berisi nama, PNR, dan kursi,
+/// jadi ikut disamarkan - termasuk turunannya di decode diagnostics / partial decode
+/// (`normalized`, `tokens`, `conditionalData`).
+const PII_FIELDS: &[&str] = &[
+    "passengerName",
+    "passengerNameRaw",
+    "bookingCode",
+    "seatNumber",
+    "barcodeValue",
+    "normalized",
+    "tokens",
+    "conditionalData",
+    "passenger_name",
+    "passenger_name_raw",
+    "booking_code",
+    "seat_number",
+    "barcode_value",
+    "conditional_data",
+];
+
+// Override per thread untuk test, agar test tidak mengubah flag global milik test lain
+#[cfg(test)]
+thread_local! {
+    static REDACT_PII_OVERRIDE: std::cell::Cell<Option<bool>> = const { std::cell::Cell::new(None) };
+}
+
+/// Enable or disable PII redaction for all subsequent log output
+pub fn set_pii_redaction(enabled: bool) {
+    REDACT_PII.store(enabled, Ordering::Relaxed);
+}
+
+fn pii_redaction_enabled() -> bool {
+    #[cfg(test)]
+    if let Some(enabled) = REDACT_PII_OVERRIDE.with(std::cell::Cell::get) {
+        return enabled;
+    }
+    REDACT_PII.load(Ordering::Relaxed)
+}
+
+/// Test-only: redaction mode for the current thread, restored when the guard drops
+#[cfg(test)]
+pub struct PiiRedactionGuard(Option<bool>);
+
+#[cfg(test)]
+impl Drop for PiiRedactionGuard {
+    fn drop(&mut self) {
+        REDACT_PII_OVERRIDE.with(|cell| cell.set(self.0));
+    }
+}
+
+#[cfg(test)]
+pub fn override_pii_redaction(enabled: bool) -> PiiRedactionGuard {
+    PiiRedactionGuard(REDACT_PII_OVERRIDE.with(|cell| cell.replace(Some(enabled))))
+}
+
+/// Log-safe form of a passenger value (name, booking code, seat, raw barcode)
+///
+/// Returned unchanged unless redaction is on; then replaced with a short SHA-256
+/// prefix so the same passenger can still be correlated across log lines.
+pub fn redact_pii(value: &str) -> String {
+    if !pii_redaction_enabled() {
+        return value.to_string();
+    }
+    let digest = format!("{:x}", Sha256::digest(value.as_bytes()));
+    format!("sha256:{}", &digest[..12])
+}
+
+/// Request/response body preview for logs, at most `max_len` bytes of the original
+///
+/// With redaction on, PII fields in JSON bodies are replaced via [`redact_pii`] before
+/// truncation; non-JSON bodies are logged as `***`.
+pub fn body_preview(bytes: &[u8], max_len: usize) -> String {
+    if !pii_redaction_enabled() {
+        return String::from_utf8_lossy(&bytes[..bytes.len().min(max_len)]).to_string();
+    }
+    match serde_json::from_slice::<serde_json::Value>(bytes) {
+        Ok(value) => redact_json(value).to_string().chars().take(max_len).collect(),
+        Err(_) => "***".to_string(),
+    }
+}
+
+fn redact_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                if PII_FIELDS.contains(&key.as_str()) {
+                    (key, redact_pii_value(value))
+                } else {
+                    (key, redact_json(value))
+                }
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.into_iter().map(redact_json).collect(),
+        other => other,
+    }
+}
+
+// Nilai di bawah key PII: string disamarkan, termasuk elemen array (mis. `tokens`)
+fn redact_pii_value(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(redact_pii(&s)),
+        serde_json::Value::Array(items) => items.into_iter().map(redact_pii_value).collect(),
+        other => redact_json(other),
+    }
+}
+
+/// Path template for metrics labels and aggregated request logs
+///
+/// Numeric segments are replaced with `{id}` (`/api/flights/42/statistics` →
+/// `/api/flights/{id}/statistics`) so per-resource URLs don't explode label cardinality.
+pub fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Build the log filter from the base level plus optional per-module overrides
+///
+/// `module_levels` uses `RUST_LOG` directive syntax
+/// (`falcon_restapi::middleware=warn,falcon_restapi::handlers=debug`) and is layered over
+/// `base_level`; the most specific directive wins for each module. Returns an error for
+/// an unparsable directive instead of silently dropping it.
+pub fn build_env_filter(base_level: &str, module_levels: Option<&str>) -> Result<EnvFilter, String> {
+    let directives = match module_levels {
+        Some(modules) => format!("{},{}", base_level, modules),
+        None => base_level.to_string(),
+    };
+    EnvFilter::builder()
+        .parse(&directives)
+        .map_err(|e| format!("invalid log directives '{}': {}", directives, e))
+}
+
+/// Build the daily rolling file appender for error logs in `log_dir`
+///
+/// The directory is created if missing. Returns an error instead of panicking
+/// when it can't be created or written (e.g. read-only container filesystem),
+/// so the caller can fall back to console-only logging.
+pub fn file_appender(log_dir: &str) -> Result<RollingFileAppender, String> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_NAME)
+        .build(log_dir)
+        .map_err(|e| format!("cannot use log directory '{}': {}", log_dir, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path_templates_numeric_ids() {
+        assert_eq!(normalize_path("/api/flights/42/statistics"), "/api/flights/{id}/statistics");
+        assert_eq!(normalize_path("/api/users/7"), "/api/users/{id}");
+        assert_eq!(normalize_path("/api/flights/export"), "/api/flights/export");
+        assert_eq!(normalize_path("/api/v2/flights"), "/api/v2/flights");
+        assert_eq!(normalize_path("/"), "/");
+    }
+
+    #[test]
+    fn test_env_filter_applies_module_levels() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        #[derive(Clone, Default)]
+        struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let filter = build_env_filter(
+            "info",
+            Some("falcon_restapi::middleware=warn,falcon_restapi::handlers=debug"),
+        )
+        .unwrap();
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(filter).with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(move || writer.clone())
+                .with_ansi(false),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "falcon_restapi::middleware", "middleware info");
+            tracing::warn!(target: "falcon_restapi::middleware", "middleware warn");
+            tracing::debug!(target: "falcon_restapi::handlers", "handlers debug");
+            tracing::debug!(target: "falcon_restapi::database", "database debug");
+            tracing::info!(target: "falcon_restapi::database", "database info");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("middleware info"));
+        assert!(output.contains("middleware warn"));
+        assert!(output.contains("handlers debug"));
+        assert!(!output.contains("database debug"));
+        assert!(output.contains("database info"));
+
+        assert!(build_env_filter("info", Some("falcon_restapi::handlers=loud")).is_err());
+    }
+
+    #[test]
+    fn test_body_preview_redacts_diagnostics_fields() {
+        let redaction = override_pii_redaction(true);
+        let body = serde_json::json!({
+            "data": {
+                "normalized": "M1BAYU/MUHAMMAD MR    ESMMTHQ",
+                "tokens": ["ESMMTHQ", "DHXCGKID"],
+                "fields": { "passengerNameRaw": "BAYU/MUHAMMAD MR", "conditionalData": "300.", "origin": "DHX" },
+                "partialDecode": { "passengerName": "BAYU/MUHAMMAD MR" }
+            }
+        });
+        let preview = body_preview(body.to_string().as_bytes(), usize::MAX);
+        assert!(!preview.contains("BAYU"));
+        assert!(!preview.contains("SMMTHQ"));
+        assert!(!preview.contains("300."));
+        assert!(preview.contains("DHX"));
+        drop(redaction);
+
+        // Guard mengembalikan mode sebelumnya
+        assert!(!pii_redaction_enabled());
+    }
+
+    #[test]
+    fn test_unwritable_log_dir_falls_back_to_console() {
+        // Direktori di bawah sebuah file biasa tidak mungkin dibuat
+        let blocker = std::env::temp_dir().join(format!("falcon-log-blocker-{}", std::process::id()));
+        std::fs::write(&blocker, b"not a directory").unwrap();
+        let log_dir = blocker.join("logs");
+
+        let result = file_appender(log_dir.to_str().unwrap());
+        std::fs::remove_file(&blocker).unwrap();
+
+        let err = result.expect_err("appender should fail for uncreatable directory");
+        assert!(err.contains("logs"));
+    }
+}
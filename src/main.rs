@@ -1,10 +1,7 @@
 use sqlx::PgPool;
 use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use axum::http::{Method, header, HeaderValue, HeaderName};
 use crate::database_config::{create_connection_pool, get_database_config};
 
 // Impor modul lokal
@@ -14,50 +11,86 @@ mod database;
 mod database_auth;
 mod database_config;
 mod errors;
+mod extractors;
 mod handlers;
 mod handlers_auth;
 mod jwt_middleware;
+mod logging;
 mod middleware;
 mod models;
 mod openapi;
+mod parser_selftest;
 mod router;
+mod startup_report;
+mod stats_cache;
 mod barcode_parser;  // Shared IATA BCBP parser (synchronized with mobile app)
 
 #[tokio::main]
 async fn main() {
-    // Setup file appender for error logs
-    let file_appender = RollingFileAppender::new(
-        Rotation::DAILY,
-        "logs",  // Directory untuk menyimpan log files
-        "falcon-errors.log",  // Nama file log
-    );
-
     // Load konfigurasi dari file .env terlebih dahulu
     dotenvy::dotenv().ok();
     let config = config::AppConfig::from_env();
+    logging::set_pii_redaction(config.redact_pii_logs);
+
+    // Setup file appender for error logs; jika direktori tidak bisa ditulis, gunakan console saja
+    let (file_appender, file_appender_error) = match logging::file_appender(&config.log_dir) {
+        Ok(appender) => (Some(appender), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    // Filter log: RUST_LOG (jika di-set) menang; selain itu log_level + override per modul
+    let (env_filter, log_filter_error) = match tracing_subscriber::EnvFilter::try_from_default_env() {
+        Ok(filter) => (filter, None),
+        Err(_) => match logging::build_env_filter(&config.log_level, config.log_module_levels.as_deref()) {
+            Ok(filter) => (filter, None),
+            Err(e) => (config.log_level.clone().into(), Some(e)),
+        },
+    };
 
     // Inisialisasi logging dengan output ke console dan file menggunakan log_level dari config
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| config.log_level.clone().into()),
-        )
+        .with(env_filter)
         .with(tracing_subscriber::fmt::layer())  // Console output
-        .with(
+        .with(file_appender.map(|appender| {
             tracing_subscriber::fmt::layer()
-                .with_writer(file_appender)
+                .with_writer(appender)
                 .with_ansi(false)  // Disable ANSI colors in file
                 .with_target(true)
                 .with_line_number(true)
-        )
+        }))
         .init();
 
+    if let Some(e) = log_filter_error {
+        tracing::warn!(error = %e, "LOG_MODULE_LEVELS ignored, using LOG_LEVEL only");
+    }
+
+    if let Some(e) = file_appender_error {
+        tracing::warn!(error = %e, "File logging disabled, falling back to console-only logging");
+    }
+
     tracing::info!("Starting FALCON REST API");
     tracing::info!("Environment: {}", config.environment);
     tracing::info!("Server address: {}", config.server_address());
     tracing::info!("Swagger UI: {}", if config.enable_swagger { "enabled" } else { "disabled" });
     tracing::info!("Security: API Key authentication enabled");
     tracing::info!("Security: CORS configured");
+    if config.redact_pii_logs {
+        tracing::info!("Security: passenger data redacted from logs");
+    }
+
+    // Self-test parser barcode (opsional) - batalkan startup jika parser tidak sesuai golden samples
+    if config.parser_selftest {
+        match parser_selftest::run(parser_selftest::GOLDEN_SAMPLES) {
+            Ok(count) => tracing::info!("Parser self-test passed ({} golden samples)", count),
+            Err(mismatches) => {
+                for mismatch in &mismatches {
+                    tracing::error!(mismatch = %mismatch, "Parser self-test mismatch");
+                }
+                tracing::error!("Parser self-test failed with {} mismatch(es), aborting startup", mismatches.len());
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Membuat koneksi pool ke database PostgreSQL dengan konfigurasi optimasi
     let db_config = get_database_config(&config);
@@ -74,7 +107,9 @@ async fn main() {
     };
 
     // Menjalankan migrasi database saat aplikasi dimulai
-    match sqlx::migrate!("./migrations").run(&db_pool).await {
+    let migrator = sqlx::migrate!("./migrations");
+    let migration_version = migrator.iter().map(|migration| migration.version).max();
+    match migrator.run(&db_pool).await {
         Ok(_) => tracing::info!("Database migrations ran successfully"),
         Err(e) => {
             tracing::error!("Failed to run database migrations: {:?}", e);
@@ -82,20 +117,29 @@ async fn main() {
         }
     }
 
-    // Mengkonfigurasi CORS - Allow all origins for simplicity
-    let cors = CorsLayer::permissive()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([
-            header::CONTENT_TYPE,
-            header::ACCEPT,
-            HeaderName::from_static("x-api-key"),
-        ]);
+    // Mengkonfigurasi CORS - semua origin diizinkan kecuali CORS_ALLOWED_ORIGINS di-set
+    let cors = match middleware::cors_layer(&config) {
+        Ok(layer) => layer,
+        Err(e) => {
+            tracing::error!("Invalid CORS configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    tracing::info!("CORS: Permissive mode (all origins allowed)");
+    if config.cors_allowed_origins.is_empty() {
+        tracing::info!("CORS: Permissive mode (all origins allowed), preflight max-age {}s", config.cors_max_age_seconds);
+    } else {
+        tracing::info!(
+            "CORS: {} allowed origin(s), credentials {}, preflight max-age {}s",
+            config.cors_allowed_origins.len(),
+            if config.cors_allow_credentials { "allowed" } else { "not allowed" },
+            config.cors_max_age_seconds
+        );
+    }
 
     // Membuat router utama aplikasi
     // Security: Only API Key authentication (no rate limiting, no IP whitelist)
-    let app = router::create_router(db_pool, config.enable_swagger)
+    let app = router::create_router(db_pool, &config)
         .layer(axum::middleware::from_fn_with_state(config.clone(), auth_middleware::api_key_only_middleware))
         .layer(axum::middleware::from_fn(auth_middleware::security_logging_middleware))
         .layer(TraceLayer::new_for_http())
@@ -110,5 +154,10 @@ async fn main() {
     tracing::info!("Local access: http://127.0.0.1:{}", config.port);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    startup_report::StartupReport::build(&config, &db_config, migration_version).log();
+
+    // ConnectInfo dibutuhkan untuk mengenali peer (TRUSTED_PROXIES, log IP client)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
@@ -1,11 +1,271 @@
 use axum::{
     body::{Body, Bytes},
-    extract::Request,
+    extract::{ConnectInfo, Form, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, Uri},
     middleware::Next,
-    response::Response,
+    response::{Html, IntoResponse, Redirect, Response},
 };
 use http_body_util::BodyExt;
-use std::time::Instant;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::{auth_middleware, config::AppConfig, database_auth, errors::AppError, logging};
+
+/// State untuk proteksi Swagger UI (SWAGGER_PROTECTED)
+#[derive(Clone)]
+pub struct SwaggerAuth {
+    pub pool: PgPool,
+    pub token: Option<String>,
+    pub settings: database_auth::AuthSettings,
+}
+
+/// Cookie yang menyimpan token Swagger setelah login lewat form POST `SWAGGER_LOGIN_PATH`
+const SWAGGER_TOKEN_COOKIE: &str = "swagger_token";
+
+/// Halaman login Swagger; token dikirim di body POST agar tidak pernah muncul di URI yang di-log
+pub const SWAGGER_LOGIN_PATH: &str = "/swagger-login";
+
+/// Middleware untuk route Swagger: hanya `SWAGGER_TOKEN` atau JWT milik user dengan permission
+/// `system.*` yang boleh lewat. Browser tidak bisa mengirim header Bearer saat membuka halaman,
+/// jadi token juga diterima lewat cookie `swagger_token` yang di-set oleh `swagger_login`
+pub async fn swagger_auth_middleware(
+    State(auth): State<SwaggerAuth>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| swagger_cookie_token(req.headers()))
+        .ok_or_else(|| AppError::Unauthorized("Swagger UI requires authentication".to_string()))?;
+
+    authorize_swagger_token(&auth, &token).await?;
+    Ok(next.run(req).await)
+}
+
+/// Form login Swagger untuk browser (GET `SWAGGER_LOGIN_PATH`)
+pub async fn swagger_login_page() -> Html<&'static str> {
+    Html(concat!(
+        "<!doctype html><title>Swagger login</title>",
+        "<form method=\"post\">",
+        "<input type=\"password\" name=\"token\" placeholder=\"Token\" autofocus> ",
+        "<button type=\"submit\">Open Swagger UI</button>",
+        "</form>",
+    ))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SwaggerLogin {
+    token: String,
+}
+
+/// POST `SWAGGER_LOGIN_PATH`: validasi token dari form, simpan di cookie `swagger_token`
+/// (HttpOnly, Secure), lalu redirect ke Swagger UI
+pub async fn swagger_login(
+    State(auth): State<SwaggerAuth>,
+    Form(login): Form<SwaggerLogin>,
+) -> Result<Response, AppError> {
+    authorize_swagger_token(&auth, &login.token).await?;
+
+    let cookie = HeaderValue::from_str(&format!(
+        "{}={}; Path=/; HttpOnly; Secure; SameSite=Strict",
+        SWAGGER_TOKEN_COOKIE, login.token
+    ))
+    .map_err(|_| AppError::Unauthorized("Invalid Swagger token".to_string()))?;
+    let mut response = Redirect::to("/swagger-ui/").into_response();
+    response.headers_mut().insert(header::SET_COOKIE, cookie);
+    Ok(response)
+}
+
+/// Token harus sama dengan `SWAGGER_TOKEN` atau JWT user dengan permission `system.*`
+async fn authorize_swagger_token(auth: &SwaggerAuth, token: &str) -> Result<(), AppError> {
+    if auth.token.as_deref() == Some(token) {
+        return Ok(());
+    }
+    let user_id = database_auth::verify_token(&auth.pool, token, &auth.settings).await?;
+    let permissions = database_auth::get_user_permissions(&auth.pool, user_id).await?;
+    if !permissions.iter().any(|p| p.starts_with("system.")) {
+        tracing::warn!(user_id = user_id, "Swagger UI access denied, no system permission");
+        return Err(AppError::Forbidden("Swagger UI requires a system.* permission".to_string()));
+    }
+    Ok(())
+}
+
+/// Token dari cookie `swagger_token`
+fn swagger_cookie_token(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SWAGGER_TOKEN_COOKIE)
+        .map(|(_, token)| token.to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// State untuk security headers; `hsts_max_age` hanya terisi di production
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    pub hsts_max_age: Option<u64>,
+    pub trusted_proxies: Vec<String>,
+}
+
+impl SecurityHeaders {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let hsts_max_age = Some(config.hsts_max_age_seconds)
+            .filter(|max_age| config.is_production() && *max_age > 0);
+        Self {
+            hsts_max_age,
+            trusted_proxies: config.trusted_proxies.clone(),
+        }
+    }
+}
+
+/// Scheme efektif request: `X-Forwarded-Proto` hanya dipercaya jika peer adalah proxy
+/// di TRUSTED_PROXIES, selain itu scheme koneksi langsung (server ini selalu HTTP)
+pub fn effective_scheme(req: &Request, trusted_proxies: &[String]) -> String {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let from_trusted_proxy = peer.is_some_and(|ip| {
+        trusted_proxies
+            .iter()
+            .any(|proxy| auth_middleware::is_ip_in_network(ip, proxy))
+    });
+
+    let forwarded = req
+        .headers()
+        .get("X-Forwarded-Proto")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+
+    match forwarded {
+        Some(scheme) if from_trusted_proxy => scheme,
+        _ => req.uri().scheme_str().unwrap_or("http").to_string(),
+    }
+}
+
+/// Middleware tunggal untuk security headers di semua response
+///
+/// `Strict-Transport-Security` hanya dikirim di production (HSTS_MAX_AGE_SECONDS).
+/// Dengan TRUSTED_PROXIES, HSTS hanya dikirim jika scheme efektif `https`; tanpa
+/// TRUSTED_PROXIES scheme tidak bisa diketahui (TLS diterminasi di luar aplikasi),
+/// jadi HSTS selalu dikirim di production.
+pub async fn security_headers_middleware(
+    State(security): State<SecurityHeaders>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let secure = security.trusted_proxies.is_empty()
+        || effective_scheme(&req, &security.trusted_proxies) == "https";
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("SAMEORIGIN"));
+    headers.insert(header::X_XSS_PROTECTION, HeaderValue::from_static("1; mode=block"));
+    headers.insert(header::REFERRER_POLICY, HeaderValue::from_static("strict-origin-when-cross-origin"));
+
+    if let Some(max_age) = security.hsts_max_age.filter(|_| secure) {
+        let value = format!("max-age={}; includeSubDomains", max_age);
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(header::STRICT_TRANSPORT_SECURITY, value);
+        }
+    }
+
+    response
+}
+
+/// Header metadata list saat `?envelope=false` (lihat response_envelope_middleware)
+const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+const HAS_MORE_HEADER: HeaderName = HeaderName::from_static("x-has-more");
+
+/// Bangun CorsLayer dari konfigurasi (origin, max-age preflight, credentials)
+///
+/// Tanpa `CORS_ALLOWED_ORIGINS` semua origin diizinkan (`*`). Spesifikasi CORS melarang
+/// credentials dengan origin wildcard, jadi kombinasi itu ditolak di sini agar server
+/// gagal start dengan pesan jelas, bukan panic di dalam tower-http.
+pub fn cors_layer(config: &AppConfig) -> Result<CorsLayer, String> {
+    let layer = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            HeaderName::from_static("x-api-key"),
+        ])
+        .max_age(Duration::from_secs(config.cors_max_age_seconds));
+
+    if config.cors_allowed_origins.is_empty() {
+        if config.cors_allow_credentials {
+            return Err(
+                "CORS_ALLOW_CREDENTIALS=true requires CORS_ALLOWED_ORIGINS (credentials cannot be used with a wildcard origin)"
+                    .to_string(),
+            );
+        }
+        return Ok(layer.allow_origin(Any).expose_headers(Any));
+    }
+
+    let origins = config
+        .cors_allowed_origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).map_err(|_| format!("Invalid CORS origin: {}", origin))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let layer = layer.allow_origin(AllowOrigin::list(origins));
+
+    Ok(if config.cors_allow_credentials {
+        // Wildcard expose-headers juga dilarang bersama credentials, jadi header
+        // pagination untuk `?envelope=false` didaftarkan eksplisit
+        layer
+            .allow_credentials(true)
+            .expose_headers([TOTAL_COUNT_HEADER, HAS_MORE_HEADER])
+    } else {
+        layer.expose_headers(Any)
+    })
+}
+
+/// Endpoint streaming yang boleh berjalan lebih lama dari REQUEST_TIMEOUT_SECONDS
+/// (path, pasangan query yang wajib ada). Export JSON dibangun penuh di memori, jadi
+/// hanya `format=csv` yang benar-benar di-stream dan dikecualikan.
+const TIMEOUT_EXEMPT_ROUTES: &[(&str, &str)] = &[("/api/flights/export", "format=csv")];
+
+fn is_timeout_exempt(uri: &Uri) -> bool {
+    TIMEOUT_EXEMPT_ROUTES.iter().any(|(path, pair)| {
+        uri.path() == *path && uri.query().is_some_and(|q| q.split('&').any(|p| p == *pair))
+    })
+}
+
+/// Middleware timeout per request: handler yang melebihi batas dihentikan dan
+/// dijawab 504 REQUEST_TIMEOUT dengan envelope error standar
+pub async fn request_timeout_middleware(
+    State(timeout): State<Duration>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if timeout.is_zero() || is_timeout_exempt(req.uri()) {
+        return next.run(req).await;
+    }
+
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+    match tokio::time::timeout(timeout, next.run(req)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!(method = %method, uri = %uri, "Request timed out");
+            AppError::RequestTimeout { timeout_secs: timeout.as_secs() }.into_response()
+        }
+    }
+}
 
 /// Middleware untuk logging request dan response, khususnya 4xx errors
 pub async fn logging_middleware(
@@ -27,9 +287,9 @@ pub async fn logging_middleware(
         }
     };
 
-    // Log incoming request dengan body
+    // Log incoming request dengan body (data penumpang disamarkan jika REDACT_PII_LOGS=true)
     let request_body_preview = if !bytes.is_empty() {
-        String::from_utf8_lossy(&bytes[..bytes.len().min(1000)]).to_string()
+        logging::body_preview(&bytes, 1000)
     } else {
         "empty".to_string()
     };
@@ -52,22 +312,30 @@ pub async fn logging_middleware(
     let status = response.status();
     let duration = start.elapsed();
 
-    // Extract response body untuk logging
+    // Extract response body untuk logging. Body non-JSON (mis. CSV export yang di-stream)
+    // diteruskan apa adanya: mem-buffer-nya akan menahan seluruh stream di memori
     let (parts, body) = response.into_parts();
-    let bytes = match body.collect().await {
-        Ok(collected) => collected.to_bytes(),
-        Err(e) => {
-            tracing::error!("Failed to read response body: {:?}", e);
-            Bytes::new()
-        }
-    };
-
-    let response_body = if !bytes.is_empty() {
-        String::from_utf8_lossy(&bytes).to_string()
+    let (body, response_body) = if is_json_response(&parts.headers) {
+        let bytes = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(e) => {
+                tracing::error!("Failed to read response body: {:?}", e);
+                Bytes::new()
+            }
+        };
+        let preview = if !bytes.is_empty() {
+            logging::body_preview(&bytes, usize::MAX)
+        } else {
+            "empty".to_string()
+        };
+        (Body::from(bytes), preview)
     } else {
-        "empty".to_string()
+        (body, "not logged (non-JSON body)".to_string())
     };
 
+    // Route dengan id dinormalisasi agar log bisa diagregasi per endpoint
+    let route = logging::normalize_path(uri.path());
+
     // Log berdasarkan status code
     match status.as_u16() {
         // 4xx Client Errors - Log dengan detail lengkap
@@ -75,6 +343,7 @@ pub async fn logging_middleware(
             tracing::warn!(
                 method = %method,
                 uri = %uri,
+                route = %route,
                 status = %status.as_u16(),
                 status_text = %status.canonical_reason().unwrap_or("Unknown"),
                 duration_ms = ?duration.as_millis(),
@@ -89,6 +358,7 @@ pub async fn logging_middleware(
             tracing::error!(
                 method = %method,
                 uri = %uri,
+                route = %route,
                 status = %status.as_u16(),
                 status_text = %status.canonical_reason().unwrap_or("Unknown"),
                 duration_ms = ?duration.as_millis(),
@@ -103,6 +373,7 @@ pub async fn logging_middleware(
             tracing::info!(
                 method = %method,
                 uri = %uri,
+                route = %route,
                 status = %status.as_u16(),
                 duration_ms = ?duration.as_millis(),
                 response_body = %response_body,
@@ -114,6 +385,7 @@ pub async fn logging_middleware(
             tracing::info!(
                 method = %method,
                 uri = %uri,
+                route = %route,
                 status = %status.as_u16(),
                 duration_ms = ?duration.as_millis(),
                 "Request completed successfully"
@@ -124,6 +396,7 @@ pub async fn logging_middleware(
             tracing::debug!(
                 method = %method,
                 uri = %uri,
+                route = %route,
                 status = %status.as_u16(),
                 duration_ms = ?duration.as_millis(),
                 response_body = %response_body,
@@ -133,5 +406,605 @@ pub async fn logging_middleware(
     }
 
     // Reconstruct response
+    Response::from_parts(parts, body)
+}
+
+// Hanya body JSON yang di-buffer oleh middleware (logging, case, envelope)
+fn is_json_response(headers: &axum::http::HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/json"))
+        .unwrap_or(false)
+}
+
+/// Middleware untuk `?case=snake`: ubah semua key JSON response dari camelCase ke snake_case
+///
+/// Model tetap memakai `#[serde(rename_all = "camelCase")]`; konversi dilakukan sekali
+/// di sini terhadap body yang sudah diserialisasi, sehingga tidak perlu duplikasi model.
+pub async fn response_case_middleware(req: Request, next: Next) -> Response {
+    let wants_snake = req
+        .uri()
+        .query()
+        .map(|q| q.split('&').any(|pair| pair == "case=snake"))
+        .unwrap_or(false);
+
+    let response = next.run(req).await;
+    if !wants_snake {
+        return response;
+    }
+
+    if !is_json_response(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            tracing::error!("Failed to read response body: {:?}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let bytes = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(value) => Bytes::from(serde_json::to_vec(&to_snake_case_keys(value)).unwrap_or_default()),
+        Err(_) => bytes,
+    };
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+
     Response::from_parts(parts, Body::from(bytes))
-}
\ No newline at end of file
+}
+
+/// Middleware untuk `?envelope=false`: kembalikan isi `data` langsung tanpa wrapper `ApiResponse`
+///
+/// Hanya response sukses (2xx dengan `"status": "success"`) yang dibuka; error tetap memakai
+/// format standar. `total` dan `hasMore` dipindah ke header `X-Total-Count` dan `X-Has-More`.
+pub async fn response_envelope_middleware(req: Request, next: Next) -> Response {
+    let wants_raw = req
+        .uri()
+        .query()
+        .map(|q| q.split('&').any(|pair| pair == "envelope=false"))
+        .unwrap_or(false);
+
+    let response = next.run(req).await;
+    if !wants_raw || !response.status().is_success() {
+        return response;
+    }
+
+    if !is_json_response(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            tracing::error!("Failed to read response body: {:?}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let Ok(serde_json::Value::Object(mut envelope)) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if envelope.get("status").and_then(|v| v.as_str()) != Some("success") || !envelope.contains_key("data") {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    if let Some(total) = envelope.get("total").and_then(|v| v.as_u64()) {
+        parts.headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    }
+    if let Some(has_more) = envelope.get("hasMore").and_then(|v| v.as_bool()) {
+        parts.headers.insert(
+            HAS_MORE_HEADER,
+            HeaderValue::from_static(if has_more { "true" } else { "false" }),
+        );
+    }
+    let data = envelope.remove("data").unwrap_or_default();
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    Response::from_parts(parts, Body::from(serde_json::to_vec(&data).unwrap_or_default()))
+}
+
+// Konversi rekursif key object JSON ke snake_case
+fn to_snake_case_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| (camel_to_snake(&key), to_snake_case_keys(value)))
+            .collect(),
+        serde_json::Value::Array(items) => items.into_iter().map(to_snake_case_keys).collect(),
+        other => other,
+    }
+}
+
+fn camel_to_snake(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    for (i, ch) in key.chars().enumerate() {
+        if ch.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Flight;
+    use axum::{Json, Router, routing::get};
+    use tower::ServiceExt;
+
+    fn sample_flight() -> Flight {
+        Flight {
+            id: 7,
+            flight_number: "GA123".to_string(),
+            airline: "GA".to_string(),
+            aircraft: "B738".to_string(),
+            departure_time: "2025-11-05T08:00:00Z".parse().unwrap(),
+            destination: "DPS".to_string(),
+            gate: "A1".to_string(),
+            is_active: Some(true),
+            created_at: "2025-11-01T00:00:00Z".parse().unwrap(),
+            updated_at: None,
+            device_id: Some("GATE-01".to_string()),
+            capacity: Some(180),
+        }
+    }
+
+    async fn fetch(uri: &str) -> serde_json::Value {
+        let app = Router::new()
+            .route("/flight", get(|| async { Json(sample_flight()) }))
+            .layer(axum::middleware::from_fn(response_case_middleware));
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redacted_decode_log_has_no_passenger_name() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let _redaction = logging::override_pii_redaction(true);
+
+        let decoded = serde_json::json!({
+            "status": "success",
+            "data": { "passengerName": "BAYU MUHAMMAD", "bookingCode": "SMMTHQ", "seatNumber": "007A" }
+        });
+        let app = Router::new()
+            .route("/api/decode-barcode", axum::routing::post(move || async move { Json(decoded) }))
+            .layer(axum::middleware::from_fn(logging_middleware));
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/decode-barcode")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"barcodeValue":"M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300."}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(response.status().is_success());
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Incoming request"));
+        assert!(output.contains("sha256:"));
+        assert!(!output.contains("BAYU"));
+        assert!(!output.contains("SMMTHQ"));
+        assert!(!output.contains("007A"));
+    }
+
+    #[tokio::test]
+    async fn test_logging_middleware_does_not_buffer_streaming_body() {
+        let (sender, receiver) = tokio::sync::mpsc::channel::<Result<String, std::io::Error>>(1);
+        let receiver = std::sync::Arc::new(std::sync::Mutex::new(Some(receiver)));
+        let app = Router::new()
+            .route(
+                "/api/flights/export",
+                get(move || async move {
+                    let receiver = receiver.lock().unwrap().take().unwrap();
+                    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+                        receiver.recv().await.map(|chunk| (chunk, receiver))
+                    });
+                    ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], Body::from_stream(stream))
+                }),
+            )
+            .layer(axum::middleware::from_fn(logging_middleware));
+
+        sender.send(Ok("id,flight_number\n".to_string())).await.unwrap();
+        let request = Request::builder().uri("/api/flights/export").body(Body::empty()).unwrap();
+        // Stream belum selesai: middleware yang mem-buffer body akan menggantung di sini
+        let response = tokio::time::timeout(Duration::from_secs(5), app.oneshot(request))
+            .await
+            .expect("logging middleware buffered the streaming body")
+            .unwrap();
+        let mut body = response.into_body();
+        let first = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.into_data().unwrap(), Bytes::from("id,flight_number\n"));
+
+        sender.send(Ok("1,GA312\n".to_string())).await.unwrap();
+        drop(sender);
+        assert_eq!(body.collect().await.unwrap().to_bytes(), Bytes::from("1,GA312\n"));
+    }
+
+    #[test]
+    fn test_cors_credentials_require_explicit_origins() {
+        let mut config = AppConfig::for_tests();
+        config.cors_allow_credentials = true;
+        assert!(cors_layer(&config).unwrap_err().contains("CORS_ALLOWED_ORIGINS"));
+
+        config.cors_allowed_origins = vec!["https://falcon.example.com".to_string()];
+        assert!(cors_layer(&config).is_ok());
+
+        config.cors_allowed_origins = vec!["bad\norigin".to_string()];
+        assert!(cors_layer(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_sends_max_age_and_credentials() {
+        let mut config = AppConfig::for_tests();
+        config.cors_max_age_seconds = 900;
+        config.cors_allow_credentials = true;
+        config.cors_allowed_origins = vec!["https://falcon.example.com".to_string()];
+        let app = Router::new()
+            .route("/api/flights", get(|| async { "ok" }))
+            .layer(cors_layer(&config).unwrap());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/api/flights")
+                    .header("Origin", "https://falcon.example.com")
+                    .header("Access-Control-Request-Method", "PATCH")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let headers = response.headers();
+        // PATCH dipakai koreksi decode (PATCH /api/decoded-barcodes/{id})
+        assert!(headers["access-control-allow-methods"].to_str().unwrap().contains("PATCH"));
+        assert_eq!(headers["access-control-max-age"], "900");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-allow-origin"], "https://falcon.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_cors_credentials_expose_pagination_headers() {
+        let mut config = AppConfig::for_tests();
+        config.cors_allow_credentials = true;
+        config.cors_allowed_origins = vec!["https://falcon.example.com".to_string()];
+        let app = Router::new()
+            .route("/api/flights", get(|| async { "ok" }))
+            .layer(cors_layer(&config).unwrap());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/flights?envelope=false")
+                    .header("Origin", "https://falcon.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let exposed = response.headers()["access-control-expose-headers"].to_str().unwrap().to_string();
+        assert!(exposed.contains("x-total-count"), "{}", exposed);
+        assert!(exposed.contains("x-has-more"), "{}", exposed);
+    }
+
+    fn slow_app(timeout: Duration) -> Router {
+        let slow = || async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            "done"
+        };
+        Router::new()
+            .route("/api/flights", get(slow))
+            .route("/api/flights/export", get(slow))
+            .layer(axum::middleware::from_fn_with_state(timeout, request_timeout_middleware))
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_returns_504() {
+        let app = slow_app(Duration::from_millis(50));
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/flights").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["code"], "REQUEST_TIMEOUT");
+
+        // Export JSON tidak di-stream, jadi tetap kena timeout
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/flights/export?from=2030-01-01").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+
+        // Export CSV streaming dikecualikan
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/flights/export?from=2030-01-01&format=csv")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_protected_swagger_requires_token(pool: PgPool) {
+        let config = AppConfig {
+            enable_swagger: true,
+            swagger_protected: true,
+            swagger_token: Some("swagger-secret".to_string()),
+            ..AppConfig::for_tests()
+        };
+        let app = crate::router::create_router(pool, &config);
+        let request = |token: Option<&str>| {
+            let mut builder = Request::builder().uri("/api-docs/openapi.json");
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(request(Some("wrong-token"))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(request(Some("swagger-secret"))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        // Token di query string tidak diterima (URI ikut tercatat di log)
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/swagger-ui/?token=swagger-secret").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+        // Navigasi browser: login lewat form POST, lalu cookie untuk fetch openapi.json
+        let login = |token: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(SWAGGER_LOGIN_PATH)
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!("token={}", token)))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(login("wrong-token")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(login("swagger-secret")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/swagger-ui/");
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap().to_string();
+        assert!(cookie.starts_with("swagger_token=swagger-secret;"));
+        assert!(cookie.contains("HttpOnly") && cookie.contains("Secure"));
+
+        let with_cookie = |cookie: &str| {
+            Request::builder()
+                .uri("/api-docs/openapi.json")
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(with_cookie("theme=dark; swagger_token=swagger-secret")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let response = app.oneshot(with_cookie("swagger_token=wrong-token")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_protected_swagger_accepts_system_jwt(pool: PgPool) {
+        let config = AppConfig {
+            enable_swagger: true,
+            swagger_protected: true,
+            swagger_token: None,
+            ..AppConfig::for_tests()
+        };
+        let app = crate::router::create_router(pool.clone(), &config);
+
+        // Superuser punya system.*; role user tidak
+        let password_hash = bcrypt::hash("test-password", 4).unwrap();
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE username = 'superuser'")
+            .bind(&password_hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO users (username, email, password_hash, full_name, role_id) \
+             VALUES ('gate01', 'gate01@example.com', $1, 'Petugas Gate', (SELECT id FROM roles WHERE name = 'user'))",
+        )
+        .bind(&password_hash)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let settings = database_auth::AuthSettings::from_config(&config);
+        let login = |username: &'static str| {
+            database_auth::authenticate_user(
+                &pool,
+                username,
+                "test-password",
+                None,
+                None,
+                &settings,
+            )
+        };
+        let superuser = login("superuser").await.unwrap().token;
+        let operator = login("gate01").await.unwrap().token;
+
+        let request = |token: &str| {
+            Request::builder()
+                .uri("/api-docs/openapi.json")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(request(&superuser)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let response = app.clone().oneshot(request(&operator)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = app.oneshot(request("not-a-jwt")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_hsts_only_in_production(pool: PgPool) {
+        let request = || Request::builder().uri("/health").body(Body::empty()).unwrap();
+
+        let production = AppConfig {
+            environment: "production".to_string(),
+            hsts_max_age_seconds: 86400,
+            ..AppConfig::for_tests()
+        };
+        let response = crate::router::create_router(pool.clone(), &production)
+            .oneshot(request())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["strict-transport-security"], "max-age=86400; includeSubDomains");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["referrer-policy"], "strict-origin-when-cross-origin");
+
+        let development = AppConfig {
+            environment: "development".to_string(),
+            ..AppConfig::for_tests()
+        };
+        let response = crate::router::create_router(pool, &development)
+            .oneshot(request())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert!(headers.get("strict-transport-security").is_none());
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+    }
+
+    #[sqlx::test]
+    async fn test_forwarded_proto_trusted_only_from_proxy(pool: PgPool) {
+        let config = AppConfig {
+            environment: "production".to_string(),
+            trusted_proxies: vec!["10.0.0.0/8".to_string()],
+            ..AppConfig::for_tests()
+        };
+        let app = crate::router::create_router(pool, &config);
+        let request = |peer: &str, proto: &str| {
+            let mut req = Request::builder()
+                .uri("/health")
+                .header("X-Forwarded-Proto", proto)
+                .body(Body::empty())
+                .unwrap();
+            req.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+            req
+        };
+
+        let response = app.clone().oneshot(request("10.1.2.3:40000", "https")).await.unwrap();
+        assert!(response.headers().get("strict-transport-security").is_some());
+
+        // Proxy terpercaya tapi koneksi asli HTTP
+        let response = app.clone().oneshot(request("10.1.2.3:40000", "http")).await.unwrap();
+        assert!(response.headers().get("strict-transport-security").is_none());
+
+        // Peer tidak terpercaya tidak bisa memalsukan scheme
+        let response = app.oneshot(request("203.0.113.9:40000", "https")).await.unwrap();
+        assert!(response.headers().get("strict-transport-security").is_none());
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    }
+
+    #[sqlx::test]
+    async fn test_get_flights_envelope_toggle(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO flights (flight_number, airline, aircraft, departure_time, destination, gate) VALUES ('GA123', 'Garuda Indonesia', 'B738', NOW() + INTERVAL '1 day', 'DPS', 'A1')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let app = Router::new()
+            .route("/api/flights", get(crate::handlers::get_flights))
+            .with_state(pool)
+            .layer(axum::Extension(AppConfig::for_tests()))
+            .layer(axum::middleware::from_fn(response_envelope_middleware));
+        let call = |uri: &str| {
+            app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        };
+        let json = |bytes: Bytes| serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+
+        let wrapped = call("/api/flights").await.unwrap();
+        let wrapped = json(wrapped.into_body().collect().await.unwrap().to_bytes());
+        assert_eq!(wrapped["status"], "success");
+        assert_eq!(wrapped["total"], 1);
+
+        let raw = call("/api/flights?envelope=false").await.unwrap();
+        assert_eq!(raw.headers()["x-total-count"], "1");
+        let raw = json(raw.into_body().collect().await.unwrap().to_bytes());
+        assert!(raw.is_array());
+        assert_eq!(raw, wrapped["data"]);
+
+        // Error tidak diubah oleh envelope=false
+        let error = call("/api/flights?date=not-a-date").await.unwrap();
+        let raw_error = call("/api/flights?date=not-a-date&envelope=false").await.unwrap();
+        assert!(raw_error.status().is_client_error());
+        assert_eq!(raw_error.status(), error.status());
+        assert_eq!(
+            raw_error.into_body().collect().await.unwrap().to_bytes(),
+            error.into_body().collect().await.unwrap().to_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flight_response_casing() {
+        let camel = fetch("/flight").await;
+        assert_eq!(camel["flightNumber"], "GA123");
+        assert_eq!(camel["deviceId"], "GATE-01");
+        assert!(camel.get("flight_number").is_none());
+
+        let snake = fetch("/flight?case=snake").await;
+        assert_eq!(snake["flight_number"], "GA123");
+        assert_eq!(snake["device_id"], "GATE-01");
+        assert_eq!(snake["departure_time"], camel["departureTime"]);
+        assert!(snake.get("flightNumber").is_none());
+    }
+}
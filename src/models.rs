@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize, Deserializer};
+use serde::{Deserialize, Serialize, Deserializer, Serializer};
 use validator::Validate;
 use utoipa::ToSchema;
 
@@ -77,6 +77,31 @@ where
     }
 }
 
+// Format timestamp response: RFC 3339 UTC ("Z") dengan presisi milidetik yang selalu sama,
+// sehingga cursor sync (last_sync) stabil saat dikirim balik oleh client
+pub fn format_timestamp(ts: &DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+// Serializer untuk semua field DateTime<Utc> di response
+fn serialize_timestamp<S>(ts: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_timestamp(ts))
+}
+
+// Serializer untuk field Option<DateTime<Utc>> di response (None tetap null)
+fn serialize_optional_timestamp<S>(ts: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match ts {
+        Some(ts) => serializer.serialize_str(&format_timestamp(ts)),
+        None => serializer.serialize_none(),
+    }
+}
+
 // Model utama untuk tabel `flights` yang sesuai dengan skema database
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -85,13 +110,17 @@ pub struct Flight {
     pub flight_number: String,
     pub airline: String,
     pub aircraft: String,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub departure_time: DateTime<Utc>,
     pub destination: String,
     pub gate: String,
     pub is_active: Option<bool>, // Make nullable for SQLX compatibility
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub updated_at: Option<DateTime<Utc>>,
     pub device_id: Option<String>, // Ditambahkan karena ada di database dan bisa NULL
+    pub capacity: Option<i32>, // Jumlah penumpang yang diharapkan, NULL jika belum diketahui
 }
 
 // Model untuk membuat penerbangan baru (Request Body)
@@ -116,11 +145,19 @@ pub struct CreateFlight {
     ))]
     pub gate: String,
     pub device_id: Option<String>,
+    #[validate(range(min = 1))]
+    pub capacity: Option<i32>,
 }
 
+// Field penerbangan yang bisa dikosongkan lewat UpdateFlight.clear_fields.
+// gate dan aircraft NOT NULL, jadi "kosong" berarti kembali ke placeholder TBD; capacity menjadi NULL
+pub const CLEARABLE_FLIGHT_FIELDS: &[&str] = &["gate", "aircraft", "capacity"];
+
 // Model untuk memperbarui penerbangan (Request Body)
+// Field yang tidak dikirim (atau null) tidak berubah; untuk mengosongkan field gunakan clear_fields
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_clear_fields"))]
 pub struct UpdateFlight {
     #[validate(length(min = 2, max = 100))]
     pub airline: Option<String>,
@@ -139,6 +176,50 @@ pub struct UpdateFlight {
     ))]
     pub gate: Option<String>,
     pub is_active: Option<bool>,
+    #[validate(range(min = 1))]
+    pub capacity: Option<i32>,
+    // Field yang dikosongkan secara eksplisit (lihat CLEARABLE_FLIGHT_FIELDS)
+    #[serde(default)]
+    pub clear_fields: Vec<String>,
+}
+
+impl UpdateFlight {
+    /// True jika field diminta dikosongkan lewat clear_fields
+    pub fn clears(&self, field: &str) -> bool {
+        self.clear_fields.iter().any(|f| f == field)
+    }
+}
+
+// clear_fields hanya boleh berisi field yang bisa dikosongkan, dan tidak boleh bersamaan
+// dengan nilai baru untuk field yang sama (ambigu)
+fn validate_clear_fields(update: &UpdateFlight) -> Result<(), validator::ValidationError> {
+    for field in &update.clear_fields {
+        if !CLEARABLE_FLIGHT_FIELDS.contains(&field.as_str()) {
+            let mut error = validator::ValidationError::new("unclearable_field");
+            error.message = Some(
+                format!(
+                    "clearFields entry '{}' must be one of: {}",
+                    field,
+                    CLEARABLE_FLIGHT_FIELDS.join(", ")
+                )
+                .into(),
+            );
+            return Err(error);
+        }
+    }
+
+    let conflicting = [
+        ("gate", update.gate.is_some()),
+        ("aircraft", update.aircraft.is_some()),
+        ("capacity", update.capacity.is_some()),
+    ];
+    if let Some((field, _)) = conflicting.iter().find(|(field, set)| *set && update.clears(field)) {
+        let mut error = validator::ValidationError::new("clear_and_set");
+        error.message = Some(format!("'{}' cannot be both set and listed in clearFields", field).into());
+        return Err(error);
+    }
+
+    Ok(())
 }
 
 // Custom deserializer untuk i32 yang fleksibel (menerima string atau number)
@@ -162,18 +243,77 @@ where
     }
 }
 
+// Format barcode yang dikenali server (bentuk ternormalisasi)
+pub const SUPPORTED_BARCODE_FORMATS: [&str; 5] = ["PDF417", "AZTEC", "QR", "CODE128", "DATAMATRIX"];
+
+// Normalisasi format barcode: "pdf417", "PDF_417", "pdf-417" -> "PDF417"
+pub fn normalize_barcode_format(format: &str) -> String {
+    format
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn deserialize_barcode_format<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    Ok(normalize_barcode_format(&s))
+}
+
+fn deserialize_optional_barcode_format<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = Deserialize::deserialize(deserializer)?;
+    Ok(s.map(|s| normalize_barcode_format(&s)))
+}
+
+fn validate_barcode_format(format: &str) -> Result<(), validator::ValidationError> {
+    if SUPPORTED_BARCODE_FORMATS.contains(&format) {
+        Ok(())
+    } else {
+        let mut error = validator::ValidationError::new("unsupported_barcode_format");
+        error.message = Some(
+            format!("Barcode format must be one of: {}", SUPPORTED_BARCODE_FORMATS.join(", ")).into(),
+        );
+        Err(error)
+    }
+}
+
+// Barcode yang hanya berisi spasi/tab lolos length(min = 1) tetapi tidak bisa di-decode
+// dan mengotori statistik, jadi ditolak setelah di-trim
+fn validate_barcode_value(value: &str) -> Result<(), validator::ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = validator::ValidationError::new("blank_barcode_value");
+        error.message = Some("Barcode value must not be empty or whitespace only".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
 // Struct DIPISAH: Satu untuk input dari user (ScanDataInput)...
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ScanDataInput {
-    #[validate(length(min = 1))]
+    #[validate(length(min = 1), custom(function = "validate_barcode_value"))]
     pub barcode_value: String,
-    #[validate(length(min = 1))]
+    #[serde(deserialize_with = "deserialize_barcode_format")]
+    #[validate(custom(function = "validate_barcode_format"))]
     pub barcode_format: String,
     #[validate(length(min = 1))]
     pub device_id: String,
     #[serde(deserialize_with = "deserialize_flexible_i32")]
     pub flight_id: i32,
+    // Waktu scan sebenarnya di device (untuk upload offline); default: waktu server
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_flexible_datetime"
+    )]
+    pub scanned_at: Option<DateTime<Utc>>,
     // Note: confidenceScore dari request akan diabaikan karena tidak ada di struct
 }
 
@@ -184,17 +324,58 @@ pub struct ScanData {
     pub id: i32,
     pub barcode_value: String,
     pub barcode_format: String,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub scan_time: DateTime<Utc>,
     pub device_id: String,
     pub flight_id: Option<i32>, // Sesuai skema ON DELETE SET NULL
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
+// Query parameters untuk POST /api/scan-data
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateScanQuery {
+    // true = barcode langsung di-decode dalam transaksi yang sama dengan scan
+    pub decode: Option<bool>,
+}
+
+// Response POST /api/scan-data: field scan di level atas (kompatibel dengan response lama),
+// ditambah hasil decode atau rejection log jika decode=true
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanCreated {
+    #[serde(flatten)]
+    pub scan: ScanData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<DecodedBarcode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<RejectionLog>,
+}
 
 // Struktur untuk parameter query di GET /api/flights
 #[derive(Debug, Deserialize)]
 pub struct GetFlightsQuery {
     pub date: Option<chrono::NaiveDate>,
+    pub count_only: Option<bool>,
+}
+
+// Format output untuk GET /api/flights/export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+// Struktur untuk parameter query di GET /api/flights/export (rentang tanggal keberangkatan, inklusif)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportFlightsQuery {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub include_inactive: Option<bool>,
 }
 
 // Struktur untuk parameter query di GET /api/scan-data
@@ -202,22 +383,138 @@ pub struct GetFlightsQuery {
 pub struct GetScanDataQuery {
     pub flight_id: Option<i32>,
     pub date_range: Option<String>, // "start,end" format
+    pub count_only: Option<bool>,
 }
 
-// Struktur untuk parameter query di GET /api/decoded-barcodes
-#[derive(Debug, Deserialize)]
+// Struktur untuk parameter query di GET /api/decoded-barcodes (limit/offset dibaca extractor Pagination)
+#[derive(Debug, Default, Deserialize)]
 pub struct GetDecodedBarcodesQuery {
     pub flight_id: Option<i32>,
+    pub airline_code: Option<String>,
+    pub from: Option<DateTime<Utc>>, // Filter created_at >= from
+    pub to: Option<DateTime<Utc>>,   // Filter created_at <= to
+    pub count_only: Option<bool>,
+}
+
+// Request body untuk POST /api/flights/{id}/allowed-devices
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowedDevicesRequest {
+    #[validate(length(min = 1), custom(function = "validate_device_ids"))]
+    pub device_ids: Vec<String>,
+}
+
+// Setiap device ID harus 1-50 karakter (flight_allowed_devices.device_id VARCHAR(50)),
+// kalau tidak INSERT gagal dengan 500
+fn validate_device_ids(device_ids: &[String]) -> Result<(), validator::ValidationError> {
+    if device_ids.iter().all(|id| (1..=50).contains(&id.chars().count())) {
+        return Ok(());
+    }
+    let mut error = validator::ValidationError::new("length");
+    error.message = Some("Each device ID must be 1-50 characters".into());
+    Err(error)
 }
 
 // Struktur untuk parameter query di GET /api/sync/flights
 #[derive(Debug, Deserialize)]
 pub struct SyncFlightsQuery {
     pub last_sync: Option<DateTime<Utc>>,
+    // Default true: flight yang di-soft delete ikut terkirim (is_active=false) agar penghapusan tersinkron
+    pub include_inactive: Option<bool>,
+}
+
+// Struktur untuk parameter query di GET /api/flights/{id}/statistics
+#[derive(Debug, Deserialize)]
+pub struct FlightStatisticsQuery {
+    pub zero_fill: Option<bool>,
+}
+
+// Struktur untuk parameter query di POST /api/sync/flights/bulk
+#[derive(Debug, Deserialize)]
+pub struct BulkSyncQuery {
+    pub dry_run: Option<bool>,
+    // true = flight valid tetap disimpan, flight tidak valid dilewati dan dilaporkan
+    pub partial: Option<bool>,
+    // Perilaku saat flight (flight_number + tanggal keberangkatan) sudah ada, default upsert
+    pub on_conflict: Option<BulkConflictMode>,
+    // Perilaku saat flight yang sama muncul lebih dari sekali dalam payload, default keep_last
+    pub on_duplicate: Option<BulkDuplicateMode>,
+}
+
+// Mode untuk flight duplikat di dalam satu payload bulk sync
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkDuplicateMode {
+    // Entri terakhir yang disimpan, entri sebelumnya dilewati dan dilaporkan di summary.duplicates
+    #[default]
+    KeepLast,
+    // Tolak seluruh batch (400) dengan posisi setiap duplikat
+    Reject,
+}
+
+// Mode ON CONFLICT untuk bulk sync flight
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkConflictMode {
+    // Timpa data flight yang sudah ada (perilaku lama)
+    #[default]
+    Upsert,
+    // Biarkan flight yang sudah ada (mis. hasil koreksi manual), hitung sebagai skipped
+    SkipExisting,
+    // Batalkan seluruh batch jika ada flight yang sudah ada
+    FailOnConflict,
+}
+
+// Flight di payload bulk sync yang bentrok dengan flight yang sudah ada
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkConflict {
+    pub flight_number: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub departure_time: DateTime<Utc>,
+    pub existing_flight_id: i32,
+}
+
+// Flight (flight_number + tanggal keberangkatan UTC) yang muncul lebih dari sekali dalam payload
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkDuplicate {
+    pub flight_number: String,
+    pub departure_date: chrono::NaiveDate,
+    // Posisi semua kemunculan di payload; pada keep_last hanya yang terakhir disimpan
+    pub indices: Vec<usize>,
+}
+
+// Error validasi satu flight di bulk sync, beserta posisinya di payload
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkValidationFailure {
+    pub index: usize,
+    #[schema(value_type = Object)]
+    pub errors: validator::ValidationErrors,
+}
+
+// Ringkasan hasil bulk sync (juga dikembalikan pada mode dry-run)
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSyncSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub dry_run: bool,
+    // Flight yang dilewati karena tidak valid (hanya pada mode partial)
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub invalid: Vec<BulkValidationFailure>,
+    // Flight yang sudah ada: di-update (upsert) atau dilewati (skip_existing)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<BulkConflict>,
+    // Flight yang muncul lebih dari sekali dalam payload (entri sebelum yang terakhir dilewati)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<BulkDuplicate>,
 }
 
 // Struktur untuk response statistik
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FlightStatistics {
     pub flight_id: i32,
@@ -227,6 +524,47 @@ pub struct FlightStatistics {
     pub duplicate_scans: i64,
     pub scans_by_hour: Vec<ScansByHour>,
     pub top_devices: Vec<TopDevice>,
+    pub format_breakdown: Vec<FormatCount>,
+}
+
+// Penumpang (booking code + nama + sequence) yang ter-decode lebih dari sekali dalam satu penerbangan
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedDuplicate {
+    pub booking_code: String,
+    pub passenger_name: String,
+    pub sequence_number: String,
+    pub occurrences: i64,
+    pub scan_data_ids: Vec<i32>,
+}
+
+// Request body POST /api/flights/{id}/reconcile: manifest dari maskapai,
+// setiap entri berupa booking code atau nama penumpang
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileRequest {
+    #[validate(length(min = 1))]
+    pub manifest: Vec<String>,
+}
+
+// Hasil rekonsiliasi manifest vs barcode ter-decode pada satu penerbangan
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestReconciliation {
+    // Entri manifest yang cocok dengan penumpang ter-decode (apa adanya seperti dikirim)
+    pub scanned: Vec<String>,
+    // Entri manifest tanpa penumpang ter-decode yang cocok
+    pub missing: Vec<String>,
+    // Penumpang ter-decode yang tidak ada di manifest
+    pub unexpected: Vec<UnexpectedPassenger>,
+}
+
+// Penumpang ter-decode yang tidak tercantum di manifest
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnexpectedPassenger {
+    pub booking_code: String,
+    pub passenger_name: String,
 }
 
 // Struktur untuk response decoded barcode statistics
@@ -238,32 +576,231 @@ pub struct DecodedStatistics {
     pub total_decoded: i64,
     pub infant_count: i64,
     pub adult_count: i64,
+    pub class_breakdown: Vec<CabinClassCount>,
 }
 
+// Jumlah penumpang ter-decode per cabin class (kelas kosong = byte kelas tidak valid di barcode)
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
+pub struct CabinClassCount {
+    pub cabin_class: String,
+    pub count: i64,
+}
+
+// Request body untuk POST /api/decoded-statistics/batch
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedStatisticsBatchRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub flight_ids: Vec<i32>,
+}
+
+// Parameter query GET /api/decoded-statistics/by-airline (rentang created_at, opsional)
+#[derive(Debug, Default, Deserialize)]
+pub struct DecodedStatisticsByAirlineQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+// Statistik decode per airline_code. Valid = julian date 001-366 dan cabin class terisi
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AirlineDecodeStatistics {
+    pub airline_code: String,
+    pub total_decoded: i64,
+    pub valid_count: i64,
+    pub invalid_count: i64,
+}
+
+// Parameter query GET /api/devices/{device_id}/flight-statistics (tanggal scan UTC, default hari ini)
+#[derive(Debug, Default, Deserialize)]
+pub struct DeviceFlightStatisticsQuery {
+    pub date: Option<chrono::NaiveDate>,
+}
+
+// Parameter query GET /api/devices/{device_id}/unscanned-flights (tanggal keberangkatan UTC, default hari ini)
+#[derive(Debug, Default, Deserialize)]
+pub struct UnscannedFlightsQuery {
+    pub date: Option<chrono::NaiveDate>,
+}
+
+// Jumlah scan satu device per penerbangan pada tanggal tertentu
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceFlightStatistics {
+    pub flight_id: i32,
+    pub flight_number: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub departure_time: DateTime<Utc>,
+    pub total_scans: i64,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub first_scan: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub last_scan: DateTime<Utc>,
+}
+
+// Struktur untuk response GET /api/flights/{id}/detail
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightDetail {
+    pub flight: Flight,
+    pub statistics: FlightStatistics,
+    pub decoded_statistics: DecodedStatistics,
+}
+
+// Parameter query GET /api/dashboard/summary (tanggal UTC, default hari ini)
+#[derive(Debug, Default, Deserialize)]
+pub struct DashboardSummaryQuery {
+    pub date: Option<chrono::NaiveDate>,
+}
+
+// Angka utama halaman dashboard untuk satu tanggal (UTC)
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardSummary {
+    pub date: chrono::NaiveDate,
+    pub active_flights: i64,
+    pub total_scans: i64,
+    pub total_rejections: i64,
+    pub active_devices: i64,
+}
+
+// Struktur untuk response progress boarding penerbangan
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardingProgress {
+    pub flight_id: i32,
+    pub flight_number: String,
+    pub boarded: i64,
+    pub expected: Option<i32>,
+    pub percentage: Option<f64>,
+}
+
+// Jumlah scan per kategori waktu relatif terhadap departure_time:
+// early (> 60 menit sebelum), on_time (60 menit terakhir), late (setelah keberangkatan)
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardingTiming {
+    pub flight_id: i32,
+    pub early: i64,
+    pub on_time: i64,
+    pub late: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ScansByHour {
     pub hour: String,
     pub count: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TopDevice {
     pub device_id: String,
     pub scan_count: i64,
 }
 
+// Jumlah scan per format barcode (format dinormalisasi ke huruf besar)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatCount {
+    pub format: String,
+    pub count: i64,
+}
+
+// Ukuran halaman default untuk list endpoint (juga dipakai saat response di-cap)
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+// Batas atas limit per halaman; limit yang lebih besar dipotong
+pub const MAX_PAGE_SIZE: i64 = 500;
+
 // Format response API standar
-#[derive(Debug, Serialize)]
+// Field None tidak diserialisasi, kecuali `data` pada response count_only yang ditulis sebagai null
+#[derive(Debug)]
 pub struct ApiResponse<T> {
     pub status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub total: Option<u64>,
+    // true jika baris dipotong karena melebihi LIST_RESPONSE_CAP tanpa pagination
+    pub has_more: Option<bool>,
+    // true untuk response count_only: `data` tetap ada sebagai null
+    null_data: bool,
+}
+
+impl<T: Serialize> Serialize for ApiResponse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("status", &self.status)?;
+        if let Some(message) = &self.message {
+            map.serialize_entry("message", message)?;
+        }
+        if self.data.is_some() || self.null_data {
+            map.serialize_entry("data", &self.data)?;
+        }
+        if let Some(total) = self.total {
+            map.serialize_entry("total", &total)?;
+        }
+        if let Some(has_more) = self.has_more {
+            map.serialize_entry("hasMore", &has_more)?;
+        }
+        map.end()
+    }
+}
+
+impl<T> ApiResponse<T> {
+    /// Response sukses dengan satu objek data
+    pub fn success(data: T) -> Self {
+        Self {
+            status: "success".to_string(),
+            message: None,
+            data: Some(data),
+            total: None,
+            has_more: None,
+            null_data: false,
+        }
+    }
+
+    /// Response sukses untuk list; has_more hanya diisi untuk list yang dipaginasi/di-cap
+    pub fn list(data: T, total: u64, has_more: Option<bool>) -> Self {
+        Self {
+            total: Some(total),
+            has_more,
+            ..Self::success(data)
+        }
+    }
+
+    /// Response count_only: `data: null` dan total
+    pub fn count(total: u64) -> Self {
+        Self {
+            status: "success".to_string(),
+            message: None,
+            data: None,
+            total: Some(total),
+            has_more: None,
+            null_data: true,
+        }
+    }
+
+    /// Response sukses yang hanya berisi pesan (mis. logout, ganti password)
+    pub fn message_only(message: impl Into<String>) -> Self {
+        Self {
+            status: "success".to_string(),
+            message: Some(message.into()),
+            data: None,
+            total: None,
+            has_more: None,
+            null_data: false,
+        }
+    }
+
+    /// Tambahkan pesan untuk ditampilkan ke user
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
 }
 
 // Model untuk tabel decode_barcode (sesuai dengan decode.json)
@@ -278,15 +815,149 @@ pub struct DecodedBarcode {
     pub destination: String,
     pub airline_code: String,
     pub flight_number: i32,  // Integer sesuai decode.json
+    pub flight_number_raw: String,  // Persis seperti di barcode, leading zero tetap ada ("0312")
     pub flight_date_julian: String,
+    pub flight_date: Option<chrono::NaiveDate>,  // Julian date dikonversi ke tanggal kalender saat decode
     pub cabin_class: String,
+    pub cabin_class_name: Option<String>,  // Nama dari cabin_class_codes; null jika huruf kelas tidak terdaftar
     pub seat_number: String,
     pub sequence_number: String,
     pub infant_status: bool,
     pub scan_data_id: Option<i32>,
+    pub source_format: Option<String>, // PDF417 / AZTEC / ...; null untuk decode ad-hoc lama
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
+// Response GET /api/decoded-barcodes/{id}/mismatch: data penerbangan menurut barcode vs flight
+// tempat scan dicatat. mismatched_fields berisi "airline", "flightNumber" dan/atau "flightDate"
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedFlightMismatch {
+    pub decoded_barcode_id: i32,
+    pub flight_id: i32,
+    pub matches: bool,
+    pub mismatched_fields: Vec<String>,
+    pub decoded_airline_code: String,
+    pub decoded_flight_number: i32,
+    pub decoded_flight_date_julian: String,
+    pub flight_number: String,
+    pub flight_airline: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub flight_departure_time: DateTime<Utc>,
+}
+
+// Request body PATCH /api/decoded-barcodes/{id}: koreksi manual field hasil decode yang salah parse
+// (kolom ticket_status sudah diganti infant_status, lihat migrasi 20251009000001)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDecodedBarcode {
+    pub infant_status: Option<bool>,
+    #[validate(length(equal = 1))]
+    pub cabin_class: Option<String>,
+}
+
+// Response GET /api/version: versi crate, hash spesifikasi OpenAPI, dan environment server
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiVersionInfo {
+    pub version: String,
+    pub schema_hash: String,
+    pub environment: String,
+}
+
+// Request body untuk POST /api/decode-barcode/detect-format
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectFormatRequest {
+    #[validate(length(min = 1))]
+    pub barcode_value: String,
+}
+
+// Response detect-format: format null jika tidak dikenali
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectFormatResponse {
+    pub format: Option<String>,
+}
+
+// Response GET /api/scan-data/{id}/decode-diagnostics: hasil parse ulang tanpa disimpan
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeDiagnostics {
+    pub scan_data_id: i32,
+    pub barcode_value: String,
+    pub barcode_format: String,
+    pub normalized: String,
+    pub normalized_length: usize,
+    // "space_delimited" / "strict_iata"; null jika tidak ada strategi yang cocok
+    pub strategy: Option<String>,
+    pub tokens: Vec<String>,
+    pub fields: Option<DiagnosticFields>,
+}
+
+// Field hasil parser apa adanya (nama mentah, nomor flight belum dikonversi)
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticFields {
+    pub passenger_name_raw: String,
+    pub e_ticket_indicator: String,
+    pub booking_code: String,
+    pub origin: String,
+    pub destination: String,
+    pub airline_code: String,
+    pub flight_number: String,
+    pub flight_date_julian: String,
+    pub cabin_class: String,
+    pub seat_number: String,
+    pub sequence_number: String,
+    pub infant_status: bool,
+    pub conditional_data: Option<String>,
+}
+
+impl From<crate::barcode_parser::PDF417Data> for DiagnosticFields {
+    fn from(data: crate::barcode_parser::PDF417Data) -> Self {
+        Self {
+            passenger_name_raw: data.passenger_name_raw,
+            e_ticket_indicator: data.e_ticket_indicator,
+            booking_code: data.booking_code,
+            origin: data.origin,
+            destination: data.destination,
+            airline_code: data.airline_code,
+            flight_number: data.flight_number,
+            flight_date_julian: data.flight_date_julian,
+            cabin_class: data.cabin_class,
+            seat_number: data.seat_number,
+            sequence_number: data.sequence_number,
+            infant_status: data.infant_status,
+            conditional_data: data.conditional_data,
+        }
+    }
+}
+
+// Gaya nama penumpang pada hasil decode; dipetakan ke NameFormat milik parser
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum NameFormat {
+    /// "Title Firstname Lastname" (e.g., "Ms Siti Putri")
+    #[default]
+    Display,
+    /// Name field exactly as encoded in the barcode (e.g., "PUTRI/SITI MS")
+    Raw,
+    /// IATA "LAST/FIRST TITLE" order in title case (e.g., "Putri/Siti Ms")
+    LastFirst,
+}
+
+impl From<NameFormat> for crate::barcode_parser::NameFormat {
+    fn from(format: NameFormat) -> Self {
+        match format {
+            NameFormat::Display => Self::Display,
+            NameFormat::Raw => Self::Raw,
+            NameFormat::LastFirst => Self::LastFirst,
+        }
+    }
+}
+
 // Model untuk input decode barcode
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -294,6 +965,29 @@ pub struct DecodeRequest {
     #[validate(length(min = 1))]
     pub barcode_value: String,
     pub scan_data_id: Option<i32>,
+    #[serde(default)]
+    pub name_format: NameFormat,
+    // Simbologi asal (PDF417 kertas / AZTEC mobile); jika kosong diambil dari scan yang terhubung
+    #[serde(default, deserialize_with = "deserialize_optional_barcode_format")]
+    #[validate(custom(function = "validate_barcode_format"))]
+    pub barcode_format: Option<String>,
+}
+
+impl DecodeRequest {
+    // Kebijakan REQUIRE_SCAN_DATA_ID: decode wajib terhubung ke scan yang tersimpan
+    pub fn check_scan_data_id_policy(
+        &self,
+        required: bool,
+    ) -> Result<(), validator::ValidationErrors> {
+        if required && self.scan_data_id.is_none() {
+            let mut errors = validator::ValidationErrors::new();
+            let mut error = validator::ValidationError::new("required");
+            error.message = Some("scanDataId is required by server policy".into());
+            errors.add("scan_data_id", error);
+            return Err(errors);
+        }
+        Ok(())
+    }
 }
 
 // Regex untuk validasi format gate
@@ -315,9 +1009,66 @@ pub struct RejectionLog {
     pub flight_number: Option<String>,
     pub airline: Option<String>,
     pub device_id: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub rejected_at: DateTime<Utc>,
 }
 
+// Field yang masih terbaca dari barcode yang ditolak parser lengkap (best-effort)
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialDecodeFields {
+    pub passenger_name_raw: Option<String>,
+    pub booking_code: Option<String>,
+    pub origin: Option<String>,
+    pub destination: Option<String>,
+    pub airline_code: Option<String>,
+    pub flight_number: Option<String>,
+    pub flight_date_julian: Option<String>,
+    pub cabin_class: Option<String>,
+    pub seat_number: Option<String>,
+    pub sequence_number: Option<String>,
+}
+
+impl From<crate::barcode_parser::PartialBcbp> for PartialDecodeFields {
+    fn from(partial: crate::barcode_parser::PartialBcbp) -> Self {
+        Self {
+            passenger_name_raw: partial.passenger_name_raw,
+            booking_code: partial.booking_code,
+            origin: partial.origin,
+            destination: partial.destination,
+            airline_code: partial.airline_code,
+            flight_number: partial.flight_number,
+            flight_date_julian: partial.flight_date_julian,
+            cabin_class: partial.cabin_class,
+            seat_number: partial.seat_number,
+            sequence_number: partial.sequence_number,
+        }
+    }
+}
+
+// Rejection log pada response list: untuk alasan format, ditambah hasil parse best-effort
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectionLogEntry {
+    #[serde(flatten)]
+    pub log: RejectionLog,
+    pub partial_decode: Option<PartialDecodeFields>,
+}
+
+impl From<RejectionLog> for RejectionLogEntry {
+    fn from(log: RejectionLog) -> Self {
+        // "invalid_format" dari device, "DECODE_FAILED" dari decode otomatis saat scan
+        let format_rejection = log.reason.to_ascii_lowercase().contains("invalid_format")
+            || log.reason == "DECODE_FAILED";
+        let partial_decode = if format_rejection {
+            crate::barcode_parser::parse_partial_bcbp(&log.barcode_value).map(Into::into)
+        } else {
+            None
+        };
+        Self { log, partial_decode }
+    }
+}
+
 // Model untuk input rejection log
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -339,13 +1090,27 @@ pub struct CreateRejectionLog {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RejectionLogQuery {
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
     pub airline: Option<String>,
     pub reason: Option<String>,
     pub device_id: Option<String>,
 }
 
+// Query parameter GET /api/flights/lookup: identitas flight yang dikenal client
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightLookupQuery {
+    pub flight_number: String,
+    pub date: chrono::NaiveDate,
+}
+
+// Query parameter lookup per barcode (exact match barcode_value):
+// GET /api/rejection-logs/by-barcode dan GET /api/scan-data/by-barcode
+#[derive(Debug, Deserialize, Validate)]
+pub struct BarcodeValueQuery {
+    #[validate(length(min = 1))]
+    pub value: String,
+}
+
 // ============= Translation/Code Mapping Models =============
 
 // Model untuk airport codes
@@ -357,7 +1122,9 @@ pub struct AirportCode {
     pub name: String,
     pub city: String,
     pub country: String,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -369,7 +1136,9 @@ pub struct AirlineCode {
     pub code: String,
     pub name: String,
     pub country: String,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -381,7 +1150,9 @@ pub struct CabinClassCode {
     pub code: String,
     pub name: String,
     pub description: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -391,9 +1162,57 @@ pub struct CabinClassCode {
 pub struct StarterDataVersion {
     pub id: i32,
     pub version: i32,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
+// Input untuk bulk import airport codes (upsert berdasarkan code)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertAirportCode {
+    #[validate(length(equal = 3))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1, max = 100))]
+    pub city: String,
+    #[validate(length(min = 1, max = 100))]
+    pub country: String,
+}
+
+// Input untuk bulk import airline codes (upsert berdasarkan code)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertAirlineCode {
+    #[validate(length(min = 2, max = 3))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1, max = 100))]
+    pub country: String,
+}
+
+// Input untuk bulk import cabin class codes (upsert berdasarkan code)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertCabinClassCode {
+    #[validate(length(equal = 1))]
+    pub code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+}
+
+// Ringkasan hasil bulk import code; starter_data_version naik sekali per batch
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeImportSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub starter_data_version: i32,
+}
+
 // ============= Authentication Models =============
 
 // Model untuk user (response)
@@ -408,8 +1227,11 @@ pub struct User {
     pub full_name: String,
     pub role_id: i32,
     pub is_active: bool,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub last_login_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub updated_at: Option<DateTime<Utc>>,
     pub created_by: Option<i32>,
 }
@@ -424,8 +1246,11 @@ pub struct UserWithRole {
     pub full_name: String,
     pub role: Role,
     pub is_active: bool,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub last_login_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -436,7 +1261,9 @@ pub struct Role {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -448,7 +1275,9 @@ pub struct RoleWithPermissions {
     pub name: String,
     pub description: Option<String>,
     pub permissions: Vec<Permission>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -461,6 +1290,7 @@ pub struct Permission {
     pub description: Option<String>,
     pub resource: String,
     pub action: String,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -482,9 +1312,74 @@ pub struct LoginResponse {
     pub token: String,
     pub user: UserWithRole,
     pub permissions: Vec<String>, // List of permission names
+    #[serde(serialize_with = "serialize_timestamp")]
     pub expires_at: DateTime<Utc>,
 }
 
+// Model untuk response POST /api/auth/logout-all
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutAllResponse {
+    pub revoked_sessions: u64,
+}
+
+// Model untuk response GET /api/auth/validate (token valid; token tidak valid dijawab 401)
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenValidation {
+    pub valid: bool,
+    pub user_id: i32,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub expires_at: DateTime<Utc>,
+}
+
+// Request body untuk POST /api/admin/sessions/revoke-by-device (device hilang)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeSessionsByDeviceRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub device_info: String,
+}
+
+// Model untuk response POST /api/admin/sessions/revoke-by-device
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeSessionsByDeviceResponse {
+    pub device_info: String,
+    pub revoked_sessions: u64,
+}
+
+// Model untuk request POST /api/admin/purge-operational-data
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeOperationalDataRequest {
+    /// Also delete rejection logs (default: false)
+    #[serde(default)]
+    pub include_rejection_logs: bool,
+}
+
+// Model untuk response POST /api/admin/purge-operational-data
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeOperationalDataSummary {
+    pub deleted_flights: u64,
+    pub deleted_scans: u64,
+    pub deleted_decoded_barcodes: u64,
+    pub deleted_rejection_logs: u64,
+}
+
+// Satu kejadian di timeline aktivitas user (flight dibuat, login, logout, scan, user dibuat)
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserActivityEvent {
+    pub event_type: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub occurred_at: DateTime<Utc>,
+    pub description: String,
+    // Id entitas terkait (flight, sesi, scan, atau user), sesuai event_type
+    pub reference_id: i32,
+}
+
 // Model untuk create user request
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -539,8 +1434,11 @@ pub struct UserSession {
     pub token_hash: String,
     pub device_info: Option<String>,
     pub ip_address: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub expires_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_timestamp")]
     pub revoked_at: Option<DateTime<Utc>>,
 }
 
@@ -553,6 +1451,8 @@ pub struct Claims {
     pub permissions: Vec<String>,
     pub exp: i64,              // Expiration time (unix timestamp)
     pub iat: i64,              // Issued at (unix timestamp)
+    pub iss: String,           // Issuer (JWT_ISSUER)
+    pub aud: String,           // Audience (JWT_AUDIENCE)
 }
 
 // Query parameters untuk list users
@@ -561,6 +1461,126 @@ pub struct Claims {
 pub struct ListUsersQuery {
     pub role_id: Option<i32>,
     pub is_active: Option<bool>,
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
-}
\ No newline at end of file
+}
+
+// Query parameters untuk GET /api/permissions
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPermissionsQuery {
+    pub resource: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_input(barcode_format: &str) -> ScanDataInput {
+        serde_json::from_value(serde_json::json!({
+            "barcodeValue": "M1PUTRI/SITI MS",
+            "barcodeFormat": barcode_format,
+            "deviceId": "device-1",
+            "flightId": 1
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_timestamps_serialize_with_millisecond_precision() {
+        use chrono::SubsecRound;
+
+        // Presisi mikrodetik dari Postgres; detik bulat juga harus tetap .000
+        let scan_time: DateTime<Utc> = "2025-11-05T08:15:30.123456Z".parse().unwrap();
+        let created_at: DateTime<Utc> = "2025-11-05T08:15:31Z".parse().unwrap();
+        let scan = ScanData {
+            id: 1,
+            barcode_value: "M1PUTRI/SITI MS".to_string(),
+            barcode_format: "PDF417".to_string(),
+            scan_time,
+            device_id: "device-1".to_string(),
+            flight_id: None,
+            created_at,
+        };
+        let json = serde_json::to_value(&scan).unwrap();
+        assert_eq!(json["scanTime"], "2025-11-05T08:15:30.123Z");
+        assert_eq!(json["createdAt"], "2025-11-05T08:15:31.000Z");
+
+        let serialized = json["scanTime"].as_str().unwrap();
+        let reparsed: DateTime<Utc> = serialized.parse().unwrap();
+        assert_eq!(reparsed, scan_time.trunc_subsecs(3));
+        assert_eq!(format_timestamp(&reparsed), serialized);
+
+        let flight: serde_json::Value = serde_json::to_value(Flight {
+            id: 1,
+            flight_number: "GA123".to_string(),
+            airline: "GA".to_string(),
+            aircraft: "B738".to_string(),
+            departure_time: created_at,
+            destination: "DPS".to_string(),
+            gate: "A1".to_string(),
+            is_active: Some(true),
+            created_at: scan_time,
+            updated_at: None,
+            device_id: None,
+            capacity: None,
+        })
+        .unwrap();
+        assert_eq!(flight["createdAt"], "2025-11-05T08:15:30.123Z");
+        assert!(flight["updatedAt"].is_null());
+    }
+
+    #[test]
+    fn test_barcode_format_is_normalized() {
+        for raw in ["pdf417", "PDF_417", "Pdf-417", "PDF417"] {
+            let input = scan_input(raw);
+            assert_eq!(input.barcode_format, "PDF417");
+            assert!(input.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_whitespace_only_barcode_value_is_rejected() {
+        let mut input = scan_input("PDF417");
+        input.barcode_value = " \t  ".to_string();
+        let errors = input.validate().unwrap_err();
+        let barcode_errors = &errors.field_errors()["barcode_value"];
+        assert_eq!(barcode_errors[0].code, "blank_barcode_value");
+
+        let input = scan_input("PDF417");
+        assert!(input.validate().is_ok());
+    }
+
+    #[test]
+    fn test_device_ids_are_validated_per_element() {
+        let request = |device_ids: Vec<String>| AllowedDevicesRequest { device_ids };
+        assert!(request(vec!["scanner-07".to_string(), "x".repeat(50)]).validate().is_ok());
+
+        for device_ids in [vec![], vec!["scanner-07".to_string(), String::new()], vec!["x".repeat(51)]] {
+            let errors = request(device_ids).validate().unwrap_err();
+            assert!(errors.field_errors().contains_key("device_ids"));
+        }
+    }
+
+    #[test]
+    fn test_unknown_barcode_format_is_rejected() {
+        let input = scan_input("EAN13");
+        let errors = input.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("barcode_format"));
+    }
+
+    #[test]
+    fn test_scan_data_id_policy() {
+        let ad_hoc = DecodeRequest {
+            barcode_value: "M1PUTRI/SITI MS".to_string(),
+            scan_data_id: None,
+            name_format: Default::default(),
+            barcode_format: None,
+        };
+        assert!(ad_hoc.check_scan_data_id_policy(false).is_ok());
+        let errors = ad_hoc.check_scan_data_id_policy(true).unwrap_err();
+        assert!(errors.field_errors().contains_key("scan_data_id"));
+
+        let linked = DecodeRequest { scan_data_id: Some(42), ..ad_hoc };
+        assert!(linked.check_scan_data_id_policy(true).is_ok());
+        assert!(linked.check_scan_data_id_policy(false).is_ok());
+    }
+}
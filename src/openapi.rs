@@ -1,5 +1,10 @@
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use utoipa::openapi::server::ServerBuilder;
 use utoipa::OpenApi;
 
+use crate::config::AppConfig;
+
 /// OpenAPI documentation for FALCON REST API
 ///
 /// This module provides comprehensive API documentation using OpenAPI 3.0 specification.
@@ -25,47 +30,114 @@ use utoipa::OpenApi;
             url = "https://opensource.org/licenses/MIT"
         )
     ),
-    servers(
-        (url = "http://localhost:3000", description = "Local development server")
-    ),
     paths(
         crate::handlers::create_flight,
         crate::handlers::get_flights,
+        crate::handlers::export_flights,
         crate::handlers::get_flight_by_id,
+        crate::handlers::lookup_flight,
         crate::handlers::update_flight,
         crate::handlers::delete_flight,
+        crate::handlers::touch_flight,
         crate::handlers::get_flight_statistics,
         crate::handlers::get_decoded_statistics,
+        crate::handlers::get_decoded_duplicates,
+        crate::handlers::get_latest_scans_for_flight,
+        crate::handlers::reconcile_flight_manifest,
+        crate::handlers::get_decoded_statistics_batch,
+        crate::handlers::get_decoded_statistics_by_airline,
+        crate::handlers::get_device_flight_statistics,
+        crate::handlers::get_unscanned_flights_for_device,
+        crate::handlers::get_dashboard_summary,
+        crate::handlers::get_flight_detail,
+        crate::handlers::get_boarding_progress,
+        crate::handlers::get_boarding_timing,
+        crate::handlers::get_undecoded_scans,
+        crate::handlers::get_flight_allowed_devices,
+        crate::handlers::add_flight_allowed_devices,
+        crate::handlers::remove_flight_allowed_device,
         crate::handlers::create_scan,
         crate::handlers::get_scan_data,
+        crate::handlers::get_scan_data_by_barcode,
+        crate::handlers::get_decode_diagnostics,
         crate::handlers::decode_barcode,
+        crate::handlers::detect_barcode_format,
         crate::handlers::get_decoded_barcodes,
+        crate::handlers::update_decoded_barcode,
+        crate::handlers::get_decoded_barcode_flight,
+        crate::handlers::get_decoded_flight_mismatch,
         crate::handlers::sync_flights,
         crate::handlers::sync_flights_bulk,
         crate::handlers::create_rejection_log,
         crate::handlers::get_rejection_logs,
         crate::handlers::get_rejection_stats,
+        crate::handlers::get_rejection_logs_by_barcode,
         crate::handlers::get_airport_codes,
         crate::handlers::get_airline_codes,
         crate::handlers::get_cabin_class_codes,
+        crate::handlers::import_airport_codes,
+        crate::handlers::import_airline_codes,
+        crate::handlers::import_cabin_class_codes,
         crate::handlers::get_starter_data_version,
+        crate::handlers::get_admin_config,
+        crate::handlers::purge_operational_data,
+        crate::handlers::get_api_version,
     ),
     components(
         schemas(
             crate::models::Flight,
             crate::models::CreateFlight,
             crate::models::UpdateFlight,
+            crate::models::ExportFormat,
             crate::models::FlightStatistics,
             crate::models::DecodedStatistics,
+            crate::models::CabinClassCount,
+            crate::models::DecodedDuplicate,
+            crate::models::ReconcileRequest,
+            crate::models::ManifestReconciliation,
+            crate::models::UnexpectedPassenger,
+            crate::models::DecodedStatisticsBatchRequest,
+            crate::models::AirlineDecodeStatistics,
+            crate::models::DeviceFlightStatistics,
+            crate::models::FlightDetail,
+            crate::models::DashboardSummary,
+            crate::models::BoardingProgress,
+            crate::models::BoardingTiming,
+            crate::models::AllowedDevicesRequest,
             crate::models::ScanData,
             crate::models::ScanDataInput,
             crate::models::DecodedBarcode,
+            crate::models::UpdateDecodedBarcode,
+            crate::models::DecodedFlightMismatch,
             crate::models::DecodeRequest,
+            crate::models::DetectFormatRequest,
+            crate::models::DetectFormatResponse,
+            crate::models::DecodeDiagnostics,
+            crate::models::DiagnosticFields,
+            crate::models::NameFormat,
             crate::models::RejectionLog,
+            crate::models::RejectionLogEntry,
+            crate::models::PartialDecodeFields,
             crate::models::CreateRejectionLog,
             crate::models::AirportCode,
             crate::models::AirlineCode,
             crate::models::CabinClassCode,
+            crate::models::UpsertAirportCode,
+            crate::models::UpsertAirlineCode,
+            crate::models::UpsertCabinClassCode,
+            crate::models::CodeImportSummary,
+            crate::models::BulkSyncSummary,
+            crate::models::ScanCreated,
+            crate::models::BulkConflictMode,
+            crate::models::BulkDuplicateMode,
+            crate::models::BulkDuplicate,
+            crate::models::BulkConflict,
+            crate::models::BulkValidationFailure,
+            crate::config::EffectiveConfig,
+            crate::models::PurgeOperationalDataRequest,
+            crate::models::PurgeOperationalDataSummary,
+            crate::models::ApiVersionInfo,
+            crate::database_config::DatabasePoolSettings,
         )
     ),
     tags(
@@ -73,11 +145,42 @@ use utoipa::OpenApi;
         (name = "Scanning", description = "Barcode scanning and decoding"),
         (name = "Sync", description = "Data synchronization"),
         (name = "Codes", description = "Code translation and mapping"),
-        (name = "Logs", description = "Rejection and error logs")
+        (name = "Logs", description = "Rejection and error logs"),
+        (name = "Admin", description = "Administrative and operational endpoints")
     )
 )]
 pub struct ApiDoc;
 
+/// OpenAPI spec as served at `/api-docs/openapi.json`
+///
+/// The `servers` list points at this deployment (`PUBLIC_BASE_URL`, falling back
+/// to the configured host/port) so generated clients target the right host.
+pub fn spec(config: &AppConfig) -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    openapi.servers = Some(vec![
+        ServerBuilder::new()
+            .url(config.public_base_url())
+            .description(Some(format!("{} server", config.environment)))
+            .build(),
+    ]);
+    openapi
+}
+
+/// SHA-256 (hex) of the serialized OpenAPI spec, computed once per process
+///
+/// Changes whenever a path, schema or description in `ApiDoc` changes, so clients
+/// can compare it to know when to regenerate their bindings. The deployment-specific
+/// `servers` list is not part of the hash.
+pub fn schema_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let spec = ApiDoc::openapi()
+            .to_json()
+            .expect("OpenAPI spec must serialize to JSON");
+        format!("{:x}", Sha256::digest(spec.as_bytes()))
+    })
+}
+
 /// Create Swagger UI configuration
 pub fn create_swagger_config() -> utoipa_swagger_ui::Config<'static> {
     utoipa_swagger_ui::Config::default()
@@ -86,3 +189,26 @@ pub fn create_swagger_config() -> utoipa_swagger_ui::Config<'static> {
         .show_extensions(true)
         .filter(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_servers_follow_public_base_url() {
+        let config = AppConfig {
+            public_base_url: Some("https://falcon.injourneydhx.id".to_string()),
+            environment: "production".to_string(),
+            ..AppConfig::for_tests()
+        };
+        let servers = spec(&config).servers.unwrap();
+        assert_eq!(servers[0].url, "https://falcon.injourneydhx.id");
+
+        let fallback = AppConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+            ..AppConfig::for_tests()
+        };
+        assert_eq!(spec(&fallback).servers.unwrap()[0].url, "http://localhost:8080");
+    }
+}
@@ -0,0 +1,133 @@
+// Startup self-test untuk shared IATA BCBP parser
+// Parser disinkronkan dengan mobile app; self-test ini menangkap regresi saat parser diedit.
+// Aktifkan dengan PARSER_SELFTEST=true - server batal start jika ada mismatch.
+
+use crate::barcode_parser::parse_iata_bcbp;
+
+/// Golden barcode beserta field yang diharapkan dari parser
+pub struct GoldenSample {
+    pub barcode: &'static str,
+    pub booking_code: &'static str,
+    pub origin: &'static str,
+    pub destination: &'static str,
+    pub airline_code: &'static str,
+    pub flight_number: &'static str,
+    pub flight_date_julian: &'static str,
+    pub seat_number: &'static str,
+    pub sequence_number: &'static str,
+    pub infant_status: bool,
+}
+
+/// Boarding pass nyata dari maskapai yang dilayani (lihat juga test barcode_parser)
+pub const GOLDEN_SAMPLES: &[GoldenSample] = &[
+    GoldenSample {
+        barcode: "M1PRASETYO/YUDHA DWI  EE6UVIL CGKSUBGA 0312 260Y045C0120 348>5180  5259B1A              2A12621429493830 GA                        N",
+        booking_code: "E6UVIL",
+        origin: "CGK",
+        destination: "SUB",
+        airline_code: "GA",
+        flight_number: "0312",
+        flight_date_julian: "260",
+        seat_number: "045C",
+        sequence_number: "0120",
+        infant_status: false,
+    },
+    GoldenSample {
+        barcode: "M1BAYU/MUHAMMAD MR    ESMMTHQ DHXCGKID 6473 032Y007A0002 300.",
+        booking_code: "SMMTHQ",
+        origin: "DHX",
+        destination: "CGK",
+        airline_code: "ID",
+        flight_number: "6473",
+        flight_date_julian: "032",
+        seat_number: "007A",
+        sequence_number: "0002",
+        infant_status: false,
+    },
+    GoldenSample {
+        barcode: "M1MAYZURA/AUFARIZA HANEBJQUJW CGKUPGID 6296 147Y0INF0097 100",
+        booking_code: "BJQUJW",
+        origin: "CGK",
+        destination: "UPG",
+        airline_code: "ID",
+        flight_number: "6296",
+        flight_date_julian: "147",
+        seat_number: "",
+        sequence_number: "0097",
+        infant_status: true,
+    },
+    GoldenSample {
+        barcode: "M1PUTRI/SITI MS       EXYZ789 CGKSUBJT 0610 277Y023B0045 300",
+        booking_code: "XYZ789",
+        origin: "CGK",
+        destination: "SUB",
+        airline_code: "JT",
+        flight_number: "0610",
+        flight_date_julian: "277",
+        seat_number: "023B",
+        sequence_number: "0045",
+        infant_status: false,
+    },
+];
+
+/// Jalankan parser terhadap semua sample; kembalikan jumlah sample yang lolos
+/// atau daftar mismatch (satu entri per field yang berbeda)
+pub fn run(samples: &[GoldenSample]) -> Result<usize, Vec<String>> {
+    let mut mismatches = Vec::new();
+
+    for (index, sample) in samples.iter().enumerate() {
+        let Some(data) = parse_iata_bcbp(sample.barcode) else {
+            mismatches.push(format!("sample #{}: barcode could not be parsed", index));
+            continue;
+        };
+
+        let infant_status = data.infant_status.to_string();
+        let expected_infant = sample.infant_status.to_string();
+        let fields = [
+            ("booking_code", data.booking_code.as_str(), sample.booking_code),
+            ("origin", data.origin.as_str(), sample.origin),
+            ("destination", data.destination.as_str(), sample.destination),
+            ("airline_code", data.airline_code.as_str(), sample.airline_code),
+            ("flight_number", data.flight_number.as_str(), sample.flight_number),
+            ("flight_date_julian", data.flight_date_julian.as_str(), sample.flight_date_julian),
+            ("seat_number", data.seat_number.as_str(), sample.seat_number),
+            ("sequence_number", data.sequence_number.as_str(), sample.sequence_number),
+            ("infant_status", infant_status.as_str(), expected_infant.as_str()),
+        ];
+        for (field, actual, expected) in fields {
+            if actual != expected {
+                mismatches.push(format!(
+                    "sample #{}: {} expected '{}' but parser returned '{}'",
+                    index, field, expected, actual
+                ));
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(samples.len())
+    } else {
+        Err(mismatches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_samples_pass() {
+        assert_eq!(run(GOLDEN_SAMPLES), Ok(GOLDEN_SAMPLES.len()));
+    }
+
+    #[test]
+    fn test_wrong_expectation_fails() {
+        let wrong = [GoldenSample {
+            flight_number: "9999",
+            ..GOLDEN_SAMPLES[0]
+        }];
+        let mismatches = run(&wrong).unwrap_err();
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("flight_number"));
+    }
+}
@@ -1,11 +1,12 @@
-use crate::{handlers, handlers_auth, middleware, openapi, jwt_middleware};
+use crate::{config::AppConfig, database_auth::AuthSettings, handlers, handlers_auth, middleware, openapi, jwt_middleware, stats_cache::StatsCache};
 use axum::{
     middleware as axum_middleware,
-    routing::{get, post, put, delete},
+    routing::{get, post, put, patch, delete},
+    Extension,
     Router,
 };
 use sqlx::PgPool;
-use utoipa::OpenApi;
+use std::time::Duration;
 use utoipa_swagger_ui::SwaggerUi;
 
 /// Create the main application router with optional Swagger UI
@@ -13,28 +14,55 @@ use utoipa_swagger_ui::SwaggerUi;
 /// # Arguments
 ///
 /// * `db_pool` - PostgreSQL connection pool
-/// * `enable_swagger` - Enable Swagger UI documentation endpoint
+/// * `config` - Application configuration (Swagger toggle, exposed to handlers as an extension)
 ///
 /// # Returns
 ///
 /// Configured Axum router with all API routes
-pub fn create_router(db_pool: PgPool, enable_swagger: bool) -> Router {
+pub fn create_router(db_pool: PgPool, config: &AppConfig) -> Router {
     // Public routes (no authentication required)
     let public_routes = Router::new()
         // Health check endpoint
         .route("/health", get(handlers::health_check))
         // Authentication endpoints
         .route("/api/auth/login", post(handlers_auth::login))
+        .route("/api/auth/validate", get(handlers_auth::validate_token))
         // Starter data and code translation (used for offline capability and app startup)
         .route("/api/starter-data/version", get(handlers::get_starter_data_version))
+        .route("/api/version", get(handlers::get_api_version))
         .route("/api/codes/airports", get(handlers::get_airport_codes))
         .route("/api/codes/airlines", get(handlers::get_airline_codes))
         .route("/api/codes/classes", get(handlers::get_cabin_class_codes));
 
+    // Routes dengan permission tertentu; require_permission berjalan setelah JWT middleware di bawah
+    let permission = |permission: &str| {
+        axum_middleware::from_fn_with_state(db_pool.clone(), jwt_middleware::require_permission(permission.to_string()))
+    };
+    let user_read_routes = Router::new()
+        .route("/api/users/{id}/activity", get(handlers_auth::get_user_activity))
+        .route_layer(permission("users.read"));
+    let user_update_routes = Router::new()
+        .route("/api/admin/sessions/revoke-by-device", post(handlers_auth::revoke_sessions_by_device))
+        .route_layer(permission("users.update"));
+    let system_settings_routes = Router::new()
+        // Rute untuk Admin (memerlukan permission system.*)
+        .route("/api/admin/config", get(handlers::get_admin_config))
+        .route("/api/admin/purge-operational-data", post(handlers::purge_operational_data))
+        // Bulk import code translation (upsert, starter data version naik sekali per batch)
+        .route("/api/codes/airports/bulk", post(handlers::import_airport_codes))
+        .route("/api/codes/airlines/bulk", post(handlers::import_airline_codes))
+        .route("/api/codes/classes/bulk", post(handlers::import_cabin_class_codes))
+        .route_layer(permission("system.settings"));
+
     // Protected routes (JWT authentication required)
+    let jwt_auth = jwt_middleware::JwtAuth {
+        pool: db_pool.clone(),
+        settings: AuthSettings::from_config(config),
+    };
     let protected_routes = Router::new()
         // Auth endpoints (authenticated users)
         .route("/api/auth/logout", post(handlers_auth::logout))
+        .route("/api/auth/logout-all", post(handlers_auth::logout_all))
         .route("/api/auth/me", get(handlers_auth::get_current_user))
         .route("/api/auth/change-password", post(handlers_auth::change_password))
         // User management endpoints (require users.* permissions)
@@ -49,31 +77,67 @@ pub fn create_router(db_pool: PgPool, enable_swagger: bool) -> Router {
         // Role management endpoints
         .route("/api/roles", get(handlers_auth::list_roles))
         .route("/api/roles/{id}", get(handlers_auth::get_role_by_id))
+        .route("/api/permissions", get(handlers_auth::list_permissions))
         // Rute untuk Manajemen Penerbangan
         .route("/api/flights", get(handlers::get_flights).post(handlers::create_flight))
+        .route("/api/flights/export", get(handlers::export_flights))
+        .route("/api/flights/lookup", get(handlers::lookup_flight))
         .route(
             "/api/flights/{id}",
             get(handlers::get_flight_by_id)
                 .put(handlers::update_flight)
                 .delete(handlers::delete_flight),
         )
+        .route("/api/flights/{id}/touch", post(handlers::touch_flight))
         .route("/api/flights/{id}/statistics", get(handlers::get_flight_statistics))
         .route("/api/flights/{id}/decoded-statistics", get(handlers::get_decoded_statistics))
+        .route("/api/flights/{id}/decoded-duplicates", get(handlers::get_decoded_duplicates))
+        .route("/api/flights/{id}/latest-scans", get(handlers::get_latest_scans_for_flight))
+        .route("/api/flights/{id}/reconcile", post(handlers::reconcile_flight_manifest))
+        .route("/api/decoded-statistics/batch", post(handlers::get_decoded_statistics_batch))
+        .route("/api/decoded-statistics/by-airline", get(handlers::get_decoded_statistics_by_airline))
+        .route("/api/devices/{device_id}/flight-statistics", get(handlers::get_device_flight_statistics))
+        .route("/api/devices/{device_id}/unscanned-flights", get(handlers::get_unscanned_flights_for_device))
+        .route("/api/dashboard/summary", get(handlers::get_dashboard_summary))
+        .route("/api/flights/{id}/detail", get(handlers::get_flight_detail))
+        .route("/api/flights/{id}/boarding-progress", get(handlers::get_boarding_progress))
+        .route("/api/flights/{id}/boarding-timing", get(handlers::get_boarding_timing))
+        .route("/api/flights/{id}/undecoded-scans", get(handlers::get_undecoded_scans))
+        .route(
+            "/api/flights/{id}/allowed-devices",
+            get(handlers::get_flight_allowed_devices).post(handlers::add_flight_allowed_devices),
+        )
+        .route(
+            "/api/flights/{id}/allowed-devices/{device_id}",
+            delete(handlers::remove_flight_allowed_device),
+        )
         // Rute untuk endpoint flights_decoder sesuai plan
         .route("/api/flights_decoder", get(handlers::get_flights))
         // Rute untuk Data Scan
         .route("/api/scan-data", get(handlers::get_scan_data).post(handlers::create_scan))
+        .route("/api/scan-data/by-barcode", get(handlers::get_scan_data_by_barcode))
+        .route("/api/scan-data/{id}/decode-diagnostics", get(handlers::get_decode_diagnostics))
         // Rute untuk Barcode Decoder
         .route("/api/decode-barcode", post(handlers::decode_barcode))
+        .route("/api/decode-barcode/detect-format", post(handlers::detect_barcode_format))
         .route("/api/decoded-barcodes", get(handlers::get_decoded_barcodes))
+        .route("/api/decoded-barcodes/{id}", patch(handlers::update_decoded_barcode))
+        .route("/api/decoded-barcodes/{id}/flight", get(handlers::get_decoded_barcode_flight))
+        .route("/api/decoded-barcodes/{id}/mismatch", get(handlers::get_decoded_flight_mismatch))
         // Rute untuk Sinkronisasi
         .route("/api/sync/flights", get(handlers::sync_flights))
         .route("/api/sync/flights/bulk", post(handlers::sync_flights_bulk))
         // Rute untuk Rejection Logging
         .route("/api/rejection-logs", get(handlers::get_rejection_logs).post(handlers::create_rejection_log))
         .route("/api/rejection-logs/stats", get(handlers::get_rejection_stats))
+        .route("/api/rejection-logs/by-barcode", get(handlers::get_rejection_logs_by_barcode))
+        .merge(user_read_routes)
+        .merge(user_update_routes)
+        .merge(system_settings_routes)
         // Apply JWT authentication middleware to all protected routes
-        .layer(axum_middleware::from_fn_with_state(db_pool.clone(), jwt_middleware::jwt_auth_middleware));
+        .layer(axum_middleware::from_fn_with_state(jwt_auth, jwt_middleware::jwt_auth_middleware));
+
+    let swagger_pool = db_pool.clone();
 
     // Combine public and protected routes
     let mut router = Router::new()
@@ -81,19 +145,57 @@ pub fn create_router(db_pool: PgPool, enable_swagger: bool) -> Router {
         .merge(protected_routes)
         // Menyediakan state (koneksi database) ke semua handler
         .with_state(db_pool)
+        // Menyediakan konfigurasi aplikasi ke handler yang membutuhkannya
+        .layer(Extension(config.clone()))
+        // Cache statistik per flight, di-invalidate setiap flight/scan/decode-nya berubah
+        .layer(Extension(StatsCache::new(Duration::from_secs(config.stats_cache_ttl_seconds))))
+        // Batas waktu handler (REQUEST_TIMEOUT_SECONDS), kecuali export streaming
+        .layer(axum_middleware::from_fn_with_state(
+            Duration::from_secs(config.request_timeout_seconds),
+            middleware::request_timeout_middleware,
+        ))
+        // Opsi `?envelope=false` untuk integrasi yang hanya membutuhkan isi `data`
+        .layer(axum_middleware::from_fn(middleware::response_envelope_middleware))
+        // Opsi `?case=snake` untuk partner integrasi yang membutuhkan snake_case
+        .layer(axum_middleware::from_fn(middleware::response_case_middleware))
         // Tambahkan logging middleware untuk mencatat semua request/response termasuk 4xx errors
         .layer(axum_middleware::from_fn(middleware::logging_middleware));
 
     // Conditionally add Swagger UI
-    if enable_swagger {
-        let openapi = openapi::ApiDoc::openapi();
-        router = router.merge(
-            SwaggerUi::new("/swagger-ui")
-                .url("/api-docs/openapi.json", openapi)
-                .config(openapi::create_swagger_config())
-        );
-        tracing::info!("Swagger UI enabled at /swagger-ui");
+    if config.enable_swagger {
+        let openapi = openapi::spec(config);
+        let swagger: Router = SwaggerUi::new("/swagger-ui")
+            .url("/api-docs/openapi.json", openapi)
+            .config(openapi::create_swagger_config())
+            .into();
+        if config.swagger_protected {
+            // Hanya user system.* (JWT) atau pemegang SWAGGER_TOKEN
+            let auth = middleware::SwaggerAuth {
+                pool: swagger_pool,
+                token: config.swagger_token.clone(),
+                settings: AuthSettings::from_config(config),
+            };
+            // Form login berada di luar logging_middleware sehingga body berisi token tidak di-log
+            let login = Router::new()
+                .route(
+                    middleware::SWAGGER_LOGIN_PATH,
+                    get(middleware::swagger_login_page).post(middleware::swagger_login),
+                )
+                .with_state(auth.clone());
+            router = router.merge(login).merge(swagger.layer(axum_middleware::from_fn_with_state(
+                auth,
+                middleware::swagger_auth_middleware,
+            )));
+            tracing::info!("Swagger UI enabled at /swagger-ui (authentication required)");
+        } else {
+            router = router.merge(swagger);
+            tracing::info!("Swagger UI enabled at /swagger-ui");
+        }
     }
 
-    router
+    // Security headers (termasuk HSTS di production) untuk semua response
+    router.layer(axum_middleware::from_fn_with_state(
+        middleware::SecurityHeaders::from_config(config),
+        middleware::security_headers_middleware,
+    ))
 }
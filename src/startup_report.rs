@@ -0,0 +1,104 @@
+// Ringkasan startup dalam satu event log terstruktur ("startup_complete")
+// State config, pool, migrasi, dan fitur sebelumnya tersebar di banyak baris log saat boot;
+// event ini merangkum semuanya agar mudah dicari di log aggregator.
+
+use serde::Serialize;
+
+use crate::{config::AppConfig, database_config::DatabaseConfig};
+
+/// Snapshot of the server state once startup has finished
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupReport {
+    pub version: &'static str,
+    pub environment: String,
+    pub listen_address: String,
+    pub pool_min_connections: u32,
+    pub pool_max_connections: u32,
+    /// Latest applied migration version (None if the migrator has no migrations)
+    pub migration_version: Option<i64>,
+    /// Names of enabled optional features, in a fixed order
+    pub enabled_features: Vec<&'static str>,
+}
+
+impl StartupReport {
+    /// Build the report from the resolved configuration
+    pub fn build(config: &AppConfig, db_config: &DatabaseConfig, migration_version: Option<i64>) -> Self {
+        let features = [
+            ("swagger", config.enable_swagger),
+            ("swagger_protected", config.enable_swagger && config.swagger_protected),
+            ("parser_selftest", config.parser_selftest),
+            ("strict_json_fields", config.strict_json_fields),
+            ("require_scan_data_id", config.require_scan_data_id),
+            ("redact_pii_logs", config.redact_pii_logs),
+            ("auto_rejection_logs", config.auto_rejection_logs),
+            ("stats_cache", config.stats_cache_ttl_seconds > 0),
+            ("request_timeout", config.request_timeout_seconds > 0),
+            ("cors_allowlist", !config.cors_allowed_origins.is_empty()),
+            ("allow_data_purge", config.allow_data_purge),
+        ];
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            environment: config.environment.clone(),
+            listen_address: config.server_address(),
+            pool_min_connections: db_config.min_connections(),
+            pool_max_connections: db_config.max_connections(),
+            migration_version,
+            enabled_features: features
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name)
+                .collect(),
+        }
+    }
+
+    /// Emit the report as one structured `startup_complete` event
+    pub fn log(&self) {
+        tracing::info!(
+            event = "startup_complete",
+            version = self.version,
+            environment = %self.environment,
+            listen_address = %self.listen_address,
+            pool_min_connections = self.pool_min_connections,
+            pool_max_connections = self.pool_max_connections,
+            migration_version = ?self.migration_version,
+            enabled_features = %self.enabled_features.join(","),
+            "startup_complete"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database_config::get_database_config;
+
+    #[test]
+    fn test_startup_report_fields_from_config() {
+        let config = AppConfig {
+            environment: "production".to_string(),
+            port: 8080,
+            auto_rejection_logs: true,
+            stats_cache_ttl_seconds: 0,
+            ..AppConfig::for_tests()
+        };
+        let db_config = get_database_config(&config);
+
+        let report = StartupReport::build(&config, &db_config, Some(20251105000010));
+        assert_eq!(report.environment, "production");
+        assert_eq!(report.listen_address, config.server_address());
+        assert!(report.listen_address.ends_with(":8080"));
+        assert_eq!(report.pool_min_connections, db_config.min_connections());
+        assert_eq!(report.pool_max_connections, db_config.max_connections());
+        assert_eq!(report.migration_version, Some(20251105000010));
+        assert!(report.enabled_features.contains(&"auto_rejection_logs"));
+        assert!(!report.enabled_features.contains(&"stats_cache"));
+        assert!(!report.enabled_features.contains(&"allow_data_purge"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        for field in ["version", "environment", "listenAddress", "poolMinConnections", "poolMaxConnections", "migrationVersion", "enabledFeatures"] {
+            assert!(json.get(field).is_some(), "missing {}", field);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// Cache in-memory untuk statistik per penerbangan (GET /api/flights/{id}/statistics)
+// Dashboard gate mem-poll endpoint ini terus-menerus; entri berumur pendek (STATS_CACHE_TTL_SECONDS)
+// dan dibuang setiap kali penerbangan, scan, atau hasil decode-nya berubah.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::models::FlightStatistics;
+
+// Key: (flight_id, zero_fill) -> (waktu disimpan, statistik)
+type Entries = HashMap<(i32, bool), (Instant, FlightStatistics)>;
+
+/// Per-flight statistics cache shared by all handlers (cheap to clone)
+#[derive(Clone)]
+pub struct StatsCache {
+    ttl: Duration,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl StatsCache {
+    /// Create a cache whose entries live for `ttl`; a zero TTL disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Cached statistics for the flight, if present and younger than the TTL
+    pub fn get(&self, flight_id: i32, zero_fill: bool) -> Option<FlightStatistics> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(&(flight_id, zero_fill)) {
+            Some((stored_at, stats)) if stored_at.elapsed() < self.ttl => Some(stats.clone()),
+            Some(_) => {
+                entries.remove(&(flight_id, zero_fill));
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store freshly computed statistics
+    pub fn insert(&self, zero_fill: bool, stats: &FlightStatistics) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .insert((stats.flight_id, zero_fill), (Instant::now(), stats.clone()));
+    }
+
+    /// Drop every cached variant for the flight (call after it, its scans or its decodes change)
+    pub fn invalidate(&self, flight_id: i32) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(cached_flight_id, _), _| *cached_flight_id != flight_id);
+    }
+
+    /// Drop every cached entry (call after flights are deleted or updated in bulk)
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(flight_id: i32, total_scans: i64) -> FlightStatistics {
+        FlightStatistics {
+            flight_id,
+            flight_number: "GA123".to_string(),
+            total_scans,
+            unique_scans: total_scans,
+            duplicate_scans: 0,
+            scans_by_hour: Vec::new(),
+            top_devices: Vec::new(),
+            format_breakdown: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_entries_expire_and_invalidate_per_flight() {
+        let cache = StatsCache::new(Duration::from_secs(60));
+        cache.insert(false, &stats(1, 3));
+        cache.insert(true, &stats(1, 3));
+        cache.insert(false, &stats(2, 7));
+        assert_eq!(cache.get(1, false).unwrap().total_scans, 3);
+
+        cache.invalidate(1);
+        assert!(cache.get(1, false).is_none());
+        assert!(cache.get(1, true).is_none());
+        assert_eq!(cache.get(2, false).unwrap().total_scans, 7);
+
+        let expired = StatsCache::new(Duration::from_millis(1));
+        expired.insert(false, &stats(1, 3));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(expired.get(1, false).is_none());
+
+        let disabled = StatsCache::new(Duration::ZERO);
+        disabled.insert(false, &stats(1, 3));
+        assert!(disabled.get(1, false).is_none());
+    }
+}